@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+
+/// Archive formats whose listing can be read without extracting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub fn from_name(file_name: &str) -> Option<Self> {
+        let lower = file_name.to_lowercase();
+        if lower.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if lower.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if lower.ends_with(".tar.zst") || lower.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else {
+            None
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "ZIP",
+            ArchiveFormat::Tar => "TAR",
+            ArchiveFormat::TarGz => "TAR.GZ",
+            ArchiveFormat::TarZst => "TAR.ZST",
+        }
+    }
+}
+
+/// File or folder inside an archive. Folder sizes are totals of their contents.
+#[derive(Debug, Clone)]
+pub struct ArchiveNode {
+    pub name: String,
+    /// Path inside the archive, `/`-separated
+    pub path: String,
+    pub size: u64,
+    /// Stored size; only known per entry for ZIP and plain TAR
+    pub compressed_size: Option<u64>,
+    pub is_dir: bool,
+    pub file_count: u64,
+    /// Child node indexes, largest first
+    pub children: Vec<usize>,
+}
+
+/// An archive's entries arranged as a folder tree, node 0 being the root
+#[derive(Debug, Clone)]
+pub struct ArchiveListing {
+    pub archive_path: String,
+    pub format: ArchiveFormat,
+    pub archive_size: u64,
+    pub nodes: Vec<ArchiveNode>,
+}
+
+impl ArchiveListing {
+    pub fn root(&self) -> &ArchiveNode {
+        &self.nodes[0]
+    }
+    
+    /// Largest files anywhere in the archive
+    pub fn largest_files(&self, limit: usize) -> Vec<&ArchiveNode> {
+        let mut files: Vec<&ArchiveNode> = self.nodes.iter().filter(|node| !node.is_dir).collect();
+        files.sort_by_key(|node| std::cmp::Reverse(node.size));
+        files.truncate(limit);
+        files
+    }
+    
+    fn new(archive_path: &Path, format: ArchiveFormat, archive_size: u64) -> Self {
+        let root = ArchiveNode {
+            name: archive_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            path: String::new(),
+            size: 0,
+            compressed_size: None,
+            is_dir: true,
+            file_count: 0,
+            children: Vec::new(),
+        };
+        Self {
+            archive_path: archive_path.to_string_lossy().to_string(),
+            format,
+            archive_size,
+            nodes: vec![root],
+        }
+    }
+}
+
+/// Builds the folder tree from a flat entry list
+struct TreeBuilder {
+    listing: ArchiveListing,
+    dirs: HashMap<String, usize>,
+}
+
+impl TreeBuilder {
+    fn dir_node(&mut self, path: &str) -> usize {
+        if path.is_empty() {
+            return 0;
+        }
+        if let Some(&index) = self.dirs.get(path) {
+            return index;
+        }
+        let (parent_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = self.dir_node(parent_path);
+        let index = self.push(parent, name, path, true);
+        self.dirs.insert(path.to_string(), index);
+        index
+    }
+    
+    fn push(&mut self, parent: usize, name: &str, path: &str, is_dir: bool) -> usize {
+        let index = self.listing.nodes.len();
+        self.listing.nodes.push(ArchiveNode {
+            name: name.to_string(),
+            path: path.to_string(),
+            size: 0,
+            compressed_size: None,
+            is_dir,
+            file_count: 0,
+            children: Vec::new(),
+        });
+        self.listing.nodes[parent].children.push(index);
+        index
+    }
+    
+    fn add_entry(&mut self, path: &str, is_dir: bool, size: u64, compressed_size: Option<u64>) {
+        let path = path.trim_start_matches("./").trim_matches('/');
+        if path.is_empty() {
+            return;
+        }
+        if is_dir {
+            self.dir_node(path);
+            return;
+        }
+        
+        let (parent_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = self.dir_node(parent_path);
+        let index = self.push(parent, name, path, false);
+        self.listing.nodes[index].size = size;
+        self.listing.nodes[index].compressed_size = compressed_size;
+        self.listing.nodes[index].file_count = 1;
+    }
+    
+    /// Roll sizes up into folders and sort children by size
+    fn finish(mut self) -> ArchiveListing {
+        let nodes = &mut self.listing.nodes;
+        // Children always come after their parent, so a reverse pass sees them first
+        for index in (0..nodes.len()).rev() {
+            if !nodes[index].is_dir {
+                continue;
+            }
+            let children = nodes[index].children.clone();
+            let size = children.iter().map(|&child| nodes[child].size).sum();
+            let file_count = children.iter().map(|&child| nodes[child].file_count).sum();
+            let compressed_size = children.iter().map(|&child| nodes[child].compressed_size).sum();
+            let mut sorted = children;
+            sorted.sort_by_key(|&child| std::cmp::Reverse(nodes[child].size));
+            let node = &mut nodes[index];
+            node.size = size;
+            node.file_count = file_count;
+            node.compressed_size = compressed_size;
+            node.children = sorted;
+        }
+        self.listing
+    }
+}
+
+/// Read an archive's entry list. Compressed TAR streams are decompressed
+/// in full to walk their headers, checking `should_stop` along the way.
+pub fn list_archive(path: &Path, should_stop: &Arc<Mutex<bool>>) -> Result<ArchiveListing, Box<dyn std::error::Error>> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let format = ArchiveFormat::from_name(&file_name).ok_or("Unsupported archive format")?;
+    let file = File::open(path)?;
+    let archive_size = file.metadata()?.len();
+    info!("Listing {} archive {}", format.label(), path.display());
+    
+    let mut builder = TreeBuilder {
+        listing: ArchiveListing::new(path, format, archive_size),
+        dirs: HashMap::new(),
+    };
+    
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(BufReader::new(file))?;
+            for index in 0..archive.len() {
+                // Raw access reads the central directory only, nothing is inflated or decrypted
+                let entry = archive.by_index_raw(index)?;
+                builder.add_entry(&entry.name().replace('\\', "/"), entry.is_dir(), entry.size(), Some(entry.compressed_size()));
+            }
+        }
+        ArchiveFormat::Tar => list_tar(BufReader::new(file), &mut builder, true, should_stop)?,
+        ArchiveFormat::TarGz => list_tar(flate2::read::MultiGzDecoder::new(BufReader::new(file)), &mut builder, false, should_stop)?,
+        ArchiveFormat::TarZst => list_tar(zstd::stream::read::Decoder::new(file)?, &mut builder, false, should_stop)?,
+    }
+    
+    let listing = builder.finish();
+    info!("Archive {} holds {} files, {} uncompressed",
+        listing.archive_path, listing.root().file_count, listing.root().size);
+    Ok(listing)
+}
+
+fn list_tar<R: Read>(reader: R, builder: &mut TreeBuilder, stored: bool, should_stop: &Arc<Mutex<bool>>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        if *should_stop.lock().unwrap() {
+            warn!("Archive listing stopped by user request");
+            return Err("Archive listing cancelled".into());
+        }
+        let entry = entry?;
+        let header = entry.header();
+        let is_dir = header.entry_type().is_dir();
+        let size = header.size().unwrap_or(0);
+        let entry_path = entry.path()?.to_string_lossy().replace('\\', "/");
+        builder.add_entry(&entry_path, is_dir, size, stored.then_some(size));
+    }
+    Ok(())
+}
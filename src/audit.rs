@@ -0,0 +1,178 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::data_file;
+use crate::file_age::format_timestamp;
+
+/// Append-only audit trail, one JSON object per line, in the per-user data folder so the GUI
+/// and `--run-plan` share it. Unlike the debug log it is never removed by the application.
+const AUDIT_FILE: &str = "fat-folder-audit.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Done,
+    /// Attempted but failed or cancelled
+    Failed,
+    /// Refused by the protected-path policy
+    Blocked,
+}
+
+impl AuditOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditOutcome::Done => "done",
+            AuditOutcome::Failed => "failed",
+            AuditOutcome::Blocked => "blocked",
+        }
+    }
+}
+
+/// One file-modifying action on one path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: SystemTime,
+    /// Account that ran the application
+    #[serde(default)]
+    pub user: String,
+    pub action: String,
+    pub path: String,
+    /// Bytes the path held before the action, when known
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// `content_hash` of the path, for actions that compute one anyway
+    #[serde(default)]
+    pub hash: Option<String>,
+    pub outcome: AuditOutcome,
+    pub detail: String,
+}
+
+/// Record a completed action
+pub fn record_done(action: &str, path: &str, size: Option<u64>, hash: Option<&str>, detail: &str) {
+    record(action, path, size, hash, AuditOutcome::Done, detail);
+}
+
+/// Record an action that failed or was cancelled
+pub fn record_failed(action: &str, path: &str, size: Option<u64>, error: &str) {
+    record(action, path, size, None, AuditOutcome::Failed, error);
+}
+
+/// Record a blocked attempt
+pub fn record_blocked(action: &str, path: &str, reason: &str) {
+    record(action, path, None, None, AuditOutcome::Blocked, reason);
+}
+
+/// Failing to write is logged but never stops the caller
+fn record(action: &str, path: &str, size: Option<u64>, hash: Option<&str>, outcome: AuditOutcome, detail: &str) {
+    let entry = AuditEntry {
+        time: SystemTime::now(),
+        user: current_user(),
+        action: action.to_string(),
+        path: path.to_string(),
+        size,
+        hash: hash.map(str::to_string),
+        outcome,
+        detail: detail.to_string(),
+    };
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            error!("Failed to format audit entry for {}: {}", entry.path, e);
+            return;
+        }
+    };
+    let file = audit_file();
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    match written {
+        Ok(()) => info!("Audit: {} {} {:?}", entry.action, entry.path, entry.outcome),
+        Err(e) => error!("Failed to append to the audit log {}: {}", file.display(), e),
+    }
+}
+
+/// Where the audit log is written and read
+pub fn audit_file() -> PathBuf {
+    data_file(AUDIT_FILE)
+}
+
+/// Login name from the environment, or the numeric uid on Unix when none is set
+fn current_user() -> String {
+    let name = ["USERNAME", "USER", "LOGNAME"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    #[cfg(unix)]
+    let name = name.or_else(|| Some(format!("uid {}", unsafe { libc::getuid() })));
+    name.unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Every recorded entry, oldest first. Unreadable lines are skipped.
+pub fn load_audit_log() -> Vec<AuditEntry> {
+    let file = audit_file();
+    let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            warn!("Failed to read the audit log {}: {}", file.display(), e);
+            return Vec::new();
+        }
+    };
+    let entries: Vec<AuditEntry> = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(number, line)| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("Skipping unreadable line {} of {}: {}", number + 1, file.display(), e);
+                None
+            }
+        })
+        .collect();
+    info!("Loaded {} audit entries", entries.len());
+    entries
+}
+
+pub fn export_json(entries: &[AuditEntry], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, serde_json::to_string_pretty(entries)?)?;
+    info!("Exported {} audit entries to {}", entries.len(), path.display());
+    Ok(())
+}
+
+/// Times are UTC, sizes in bytes
+pub fn export_csv(entries: &[AuditEntry], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv = String::from("time,user,action,path,size,hash,outcome,detail\n");
+    for entry in entries {
+        let fields = [
+            format!("{}Z", format_timestamp(entry.time)),
+            entry.user.clone(),
+            entry.action.clone(),
+            entry.path.clone(),
+            entry.size.map(|size| size.to_string()).unwrap_or_default(),
+            entry.hash.clone().unwrap_or_default(),
+            entry.outcome.label().to_string(),
+            entry.detail.clone(),
+        ];
+        let quoted: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&quoted.join(","));
+        csv.push('\n');
+    }
+    fs::write(path, csv)?;
+    info!("Exported {} audit entries to {}", entries.len(), path.display());
+    Ok(())
+}
+
+/// Quote a field holding a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
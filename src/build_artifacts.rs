@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use log::{info, warn};
+use walkdir::WalkDir;
+
+use crate::audit::{record_done, record_failed};
+
+/// Build system recognised by its marker file in the project root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProjectKind {
+    Cargo,
+    Node,
+    Python,
+    DotNet,
+    Gradle,
+}
+
+impl ProjectKind {
+    /// Project kind whose marker file is named `file_name`
+    pub fn from_marker(file_name: &str) -> Option<Self> {
+        let lower = file_name.to_lowercase();
+        match lower.as_str() {
+            "cargo.toml" => Some(ProjectKind::Cargo),
+            "package.json" => Some(ProjectKind::Node),
+            "pyproject.toml" => Some(ProjectKind::Python),
+            "build.gradle" | "build.gradle.kts" => Some(ProjectKind::Gradle),
+            _ if lower.ends_with(".csproj") => Some(ProjectKind::DotNet),
+            _ => None,
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectKind::Cargo => "Rust",
+            ProjectKind::Node => "Node.js",
+            ProjectKind::Python => "Python",
+            ProjectKind::DotNet => ".NET",
+            ProjectKind::Gradle => "Gradle",
+        }
+    }
+    
+    /// Folders next to the marker that the build (or package install) regenerates
+    pub fn artifact_dirs(&self) -> &'static [&'static str] {
+        match self {
+            ProjectKind::Cargo => &["target"],
+            ProjectKind::Node => &["node_modules"],
+            ProjectKind::Python => &[".venv", "venv", "build"],
+            ProjectKind::DotNet => &["bin", "obj"],
+            ProjectKind::Gradle => &["build", ".gradle"],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildArtifact {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    /// Newest modification time of anything inside, i.e. the last build
+    pub last_modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildProject {
+    pub root: String,
+    /// Every build system found in the root, e.g. Rust and Node.js side by side
+    pub kinds: Vec<ProjectKind>,
+    pub artifacts: Vec<BuildArtifact>,
+}
+
+impl BuildProject {
+    pub fn total_size(&self) -> u64 {
+        self.artifacts.iter().map(|artifact| artifact.size).sum()
+    }
+    
+    pub fn last_build(&self) -> Option<SystemTime> {
+        self.artifacts.iter().filter_map(|artifact| artifact.last_modified).max()
+    }
+    
+    pub fn kinds_label(&self) -> String {
+        self.kinds.iter().map(|kind| kind.label()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Collects project roots from marker files while the scanner walks
+#[derive(Default)]
+pub struct ProjectTracker {
+    roots: BTreeMap<PathBuf, Vec<ProjectKind>>,
+}
+
+impl ProjectTracker {
+    pub fn add_file(&mut self, path: &Path, file_name: &str) {
+        let Some(kind) = ProjectKind::from_marker(file_name) else { return };
+        let Some(root) = path.parent() else { return };
+        let kinds = self.roots.entry(root.to_path_buf()).or_default();
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    
+    /// Project roots that do not lie inside another project's artifacts
+    /// (every package in `node_modules` has its own package.json)
+    fn project_roots(&self) -> Vec<(PathBuf, Vec<ProjectKind>)> {
+        let artifact_dirs: Vec<PathBuf> = self.roots
+            .iter()
+            .flat_map(|(root, kinds)| kinds.iter().flat_map(move |kind| kind.artifact_dirs().iter().map(move |dir| root.join(dir))))
+            .collect();
+        
+        self.roots
+            .iter()
+            .filter(|(root, _)| !artifact_dirs.iter().any(|dir| root.starts_with(dir)))
+            .map(|(root, kinds)| (root.clone(), kinds.clone()))
+            .collect()
+    }
+}
+
+/// Measure the artifact folders of every tracked project, largest project first
+pub fn find_build_artifacts<F>(tracker: &ProjectTracker, should_stop: &Arc<Mutex<bool>>, mut progress: F) -> Vec<BuildProject>
+where
+    F: FnMut(String),
+{
+    let roots = tracker.project_roots();
+    let total = roots.len();
+    info!("Measuring build artifacts of {} projects", total);
+    
+    let mut projects = Vec::new();
+    for (index, (root, mut kinds)) in roots.into_iter().enumerate() {
+        if *should_stop.lock().unwrap() {
+            warn!("Build artifact search stopped by user request");
+            return Vec::new();
+        }
+        progress(format!("Measuring build artifacts: project {} of {}", index + 1, total));
+        
+        kinds.sort();
+        let mut dirs: Vec<&str> = Vec::new();
+        for dir in kinds.iter().flat_map(|kind| kind.artifact_dirs().iter().copied()) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        
+        let artifacts: Vec<BuildArtifact> = dirs
+            .into_iter()
+            .map(|dir| root.join(dir))
+            .filter(|path| is_real_dir(path))
+            .map(|path| measure_artifact(&path))
+            .filter(|artifact| artifact.size > 0)
+            .collect();
+        
+        if !artifacts.is_empty() {
+            projects.push(BuildProject {
+                root: root.to_string_lossy().to_string(),
+                kinds,
+                artifacts,
+            });
+        }
+    }
+    
+    projects.sort_by_key(|project| std::cmp::Reverse(project.total_size()));
+    info!("Found {} projects with build artifacts", projects.len());
+    projects
+}
+
+/// A directory and not a link, so cleaning never follows a junction elsewhere
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+}
+
+fn measure_artifact(path: &Path) -> BuildArtifact {
+    let mut artifact = BuildArtifact {
+        path: path.to_string_lossy().to_string(),
+        size: 0,
+        file_count: 0,
+        last_modified: None,
+    };
+    
+    for entry in WalkDir::new(path).follow_links(false).into_iter().filter_map(|entry| entry.ok()) {
+        if entry.file_type().is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                artifact.size += metadata.len();
+                artifact.file_count += 1;
+                artifact.last_modified = artifact.last_modified.max(metadata.modified().ok());
+            }
+        }
+    }
+    
+    artifact
+}
+
+/// Delete exactly the listed artifact folders. `cargo clean` is not used since it removes
+/// whatever target dir cargo resolves, which may be shared with other projects.
+/// Returns the artifact paths that are gone and the errors hit.
+pub fn clean_project(project: &BuildProject) -> (Vec<String>, Vec<String>) {
+    let mut removed = Vec::new();
+    let mut errors = Vec::new();
+    
+    for artifact in &project.artifacts {
+        let path = Path::new(&artifact.path);
+        if !path.exists() {
+            removed.push(artifact.path.clone());
+            continue;
+        }
+        if !is_real_dir(path) {
+            errors.push(format!("{}: no longer a directory", artifact.path));
+            continue;
+        }
+        match fs::remove_dir_all(path) {
+            Ok(_) => {
+                record_done("clean", &artifact.path, Some(artifact.size), None, "build artifacts");
+                removed.push(artifact.path.clone());
+            }
+            Err(e) => {
+                record_failed("clean", &artifact.path, Some(artifact.size), &e.to_string());
+                errors.push(format!("{}: {}", artifact.path, e));
+            }
+        }
+    }
+    
+    info!("Cleaned {} of {} artifact folders in {}", removed.len(), project.artifacts.len(), project.root);
+    for error in &errors {
+        warn!("Failed to clean build artifact: {}", error);
+    }
+    
+    (removed, errors)
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{record_done, record_failed};
+use crate::compress::{compress_in_place, CompressFormat};
+use crate::file_utils::{free_space, total_size, volume_root};
+use crate::protected_paths::ProtectedPaths;
+use crate::relocate::relocate;
+use crate::trash::move_to_trash;
+
+/// What to do with a marked result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Trash,
+    Delete,
+    Move,
+    Compress,
+}
+
+impl PlanAction {
+    pub const ALL: [PlanAction; 4] = [PlanAction::Trash, PlanAction::Delete, PlanAction::Move, PlanAction::Compress];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanAction::Trash => "🗑 Trash",
+            PlanAction::Delete => "❌ Delete",
+            PlanAction::Move => "➡ Move",
+            PlanAction::Compress => "🗜 Compress",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanItem {
+    pub path: String,
+    /// Size when the item was marked
+    pub size: u64,
+    pub action: PlanAction,
+    /// Bytes compressing is expected to save, from the scan's estimate
+    #[serde(default)]
+    pub estimated_savings: Option<u64>,
+    /// Archive format of a `Compress` item
+    #[serde(default)]
+    pub format: CompressFormat,
+    /// Whether a `Compress` item's original is deleted once the archive is verified
+    #[serde(default)]
+    pub delete_original: bool,
+    /// View the item was marked in
+    #[serde(default)]
+    pub source: String,
+}
+
+impl PlanItem {
+    /// Bytes the action should free where the item is now
+    pub fn projected_savings(&self) -> u64 {
+        match self.action {
+            PlanAction::Compress if self.delete_original => self.estimated_savings.unwrap_or(0),
+            PlanAction::Compress => 0,
+            // The trash is on the same volume, so nothing is freed until it is emptied
+            PlanAction::Trash => 0,
+            _ => self.size,
+        }
+    }
+}
+
+/// Marked results with their actions, saved as JSON so a plan can be reviewed and run again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupPlan {
+    pub items: Vec<PlanItem>,
+    /// Folder that `Move` items go to
+    pub move_destination: String,
+    /// Leave a link where each moved item was
+    pub leave_links: bool,
+}
+
+/// Expected change of one volume's free space
+#[derive(Debug, Clone, Default)]
+pub struct VolumeProjection {
+    pub volume: String,
+    pub freed: u64,
+    /// Bytes moved onto this volume
+    pub added: u64,
+}
+
+impl VolumeProjection {
+    /// Bytes freed once what the volume receives is taken off, so a move within it frees nothing
+    pub fn net_freed(&self) -> u64 {
+        self.freed.saturating_sub(self.added)
+    }
+}
+
+impl CleanupPlan {
+    /// Add an item, replacing the action of one already marked at the same path
+    pub fn mark(&mut self, item: PlanItem) {
+        match self.items.iter_mut().find(|existing| existing.path == item.path) {
+            Some(existing) => *existing = item,
+            None => self.items.push(item),
+        }
+    }
+    
+    /// The marked folder an item is inside of, if any; the item goes along with it
+    pub fn covering_item(&self, item: &PlanItem) -> Option<&PlanItem> {
+        self.items.iter().find(|other| other.path != item.path && Path::new(&item.path).starts_with(&other.path))
+    }
+    
+    pub fn projection(&self) -> Vec<VolumeProjection> {
+        let mut volumes: BTreeMap<String, VolumeProjection> = BTreeMap::new();
+        let mut change = |path: &Path, freed: u64, added: u64| {
+            let volume = volume_root(path).to_string_lossy().to_string();
+            let projection = volumes.entry(volume.clone()).or_insert_with(|| VolumeProjection { volume, ..Default::default() });
+            projection.freed += freed;
+            projection.added += added;
+        };
+        for item in self.items.iter().filter(|item| self.covering_item(item).is_none()) {
+            change(Path::new(&item.path), item.projected_savings(), 0);
+            if item.action == PlanAction::Move && !self.move_destination.is_empty() {
+                change(Path::new(&self.move_destination), 0, item.size);
+            }
+        }
+        volumes.into_values().collect()
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        info!("Saving cleanup plan with {} items to {}", self.items.len(), path.display());
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+    
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let plan: CleanupPlan = serde_json::from_str(&fs::read_to_string(path)?)?;
+        info!("Loaded cleanup plan with {} items from {}", plan.items.len(), path.display());
+        Ok(plan)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "lowercase")]
+pub enum ItemOutcome {
+    /// Bytes released where the item was
+    Freed(u64),
+    /// Bytes sent to the trash or renamed within their volume, which frees nothing there
+    Moved(u64),
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemReport {
+    pub path: String,
+    pub action: PlanAction,
+    pub outcome: ItemOutcome,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeFreeSpace {
+    pub volume: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+/// What a plan run actually did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanReport {
+    pub started: Option<SystemTime>,
+    pub finished: Option<SystemTime>,
+    pub items: Vec<ItemReport>,
+    pub volumes: Vec<VolumeFreeSpace>,
+    pub cancelled: bool,
+}
+
+impl PlanReport {
+    pub fn freed(&self) -> u64 {
+        self.items.iter().map(|item| match item.outcome {
+            ItemOutcome::Freed(bytes) => bytes,
+            _ => 0,
+        }).sum()
+    }
+    
+    pub fn moved(&self) -> u64 {
+        self.items.iter().map(|item| match item.outcome {
+            ItemOutcome::Moved(bytes) => bytes,
+            _ => 0,
+        }).sum()
+    }
+    
+    pub fn failed(&self) -> usize {
+        self.items.iter().filter(|item| matches!(item.outcome, ItemOutcome::Failed(_))).count()
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Messages from a plan running in the background
+pub enum PlanMessage {
+    Progress { done: usize, total: usize, path: String },
+    Finished(PlanReport),
+}
+
+/// Run every item of the plan in order, reporting progress before each one.
+/// Stops between items once `should_stop` is set. Protected paths are skipped.
+pub fn execute_plan(plan: &CleanupPlan, protected: &ProtectedPaths, mut progress: impl FnMut(usize, usize, &str), should_stop: &Arc<Mutex<bool>>) -> PlanReport {
+    info!("Executing cleanup plan with {} items", plan.items.len());
+    let mut report = PlanReport {
+        started: Some(SystemTime::now()),
+        ..Default::default()
+    };
+    
+    let mut volumes: Vec<PathBuf> = Vec::new();
+    let destination = (!plan.move_destination.is_empty()).then(|| PathBuf::from(&plan.move_destination));
+    for path in plan.items.iter().map(|item| Path::new(&item.path)).chain(destination.as_deref()) {
+        let volume = volume_root(path);
+        if !volumes.contains(&volume) {
+            volumes.push(volume);
+        }
+    }
+    let before: Vec<Option<u64>> = volumes.iter().map(|volume| free_space(volume)).collect();
+    
+    for (done, item) in plan.items.iter().enumerate() {
+        if *should_stop.lock().unwrap() {
+            warn!("Cleanup plan cancelled after {} of {} items", done, plan.items.len());
+            report.cancelled = true;
+            break;
+        }
+        progress(done, plan.items.len(), &item.path);
+        // A protected folder is never run, so it does not take the items inside it along
+        let covering = plan.items.iter().find(|other| {
+            other.path != item.path && Path::new(&item.path).starts_with(&other.path) && protected.reason(Path::new(&other.path)).is_none()
+        });
+        let outcome = match covering {
+            Some(covering) => ItemOutcome::Skipped(format!("inside {}, which is also in the plan", covering.path)),
+            None => match guard_item(item, destination.as_deref(), protected) {
+                Err(e) => ItemOutcome::Skipped(e),
+                Ok(()) => execute_item(item, destination.as_deref(), plan.leave_links, should_stop),
+            },
+        };
+        match &outcome {
+            ItemOutcome::Freed(bytes) => info!("{:?} {}: freed {} bytes", item.action, item.path, bytes),
+            ItemOutcome::Moved(bytes) => info!("{:?} {}: moved {} bytes, nothing freed", item.action, item.path, bytes),
+            ItemOutcome::Skipped(reason) => info!("{:?} {}: skipped, {}", item.action, item.path, reason),
+            ItemOutcome::Failed(e) => warn!("{:?} {} failed: {}", item.action, item.path, e),
+        }
+        report.items.push(ItemReport {
+            path: item.path.clone(),
+            action: item.action,
+            outcome,
+        });
+    }
+    progress(report.items.len(), plan.items.len(), "");
+    
+    report.volumes = volumes.iter().zip(before).map(|(volume, before)| VolumeFreeSpace {
+        volume: volume.to_string_lossy().to_string(),
+        before,
+        after: free_space(volume),
+    }).collect();
+    report.finished = Some(SystemTime::now());
+    info!("Cleanup plan finished: {} bytes freed, {} moved, {} failed", report.freed(), report.moved(), report.failed());
+    report
+}
+
+/// The item itself, and for moves where it would land, must be outside protected paths
+fn guard_item(item: &PlanItem, destination: Option<&Path>, protected: &ProtectedPaths) -> Result<(), String> {
+    let path = Path::new(&item.path);
+    let action = format!("{:?}", item.action).to_lowercase();
+    protected.guard(&action, path)?;
+    if let (PlanAction::Move, Some(destination), Some(name)) = (item.action, destination, path.file_name()) {
+        protected.guard(&action, &destination.join(name))?;
+    }
+    Ok(())
+}
+
+fn execute_item(item: &PlanItem, destination: Option<&Path>, leave_links: bool, should_stop: &Arc<Mutex<bool>>) -> ItemOutcome {
+    let path = Path::new(&item.path);
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return ItemOutcome::Skipped("no longer exists".to_string()),
+        Err(e) => return ItemOutcome::Failed(e.to_string()),
+    };
+    let size = match total_size(path) {
+        Ok(size) => size,
+        Err(e) => return ItemOutcome::Failed(e.to_string()),
+    };
+    
+    let result = match item.action {
+        PlanAction::Trash => {
+            let (_, errors) = move_to_trash(std::slice::from_ref(&item.path));
+            match errors.into_iter().next() {
+                Some(e) => Err(e.into()),
+                None => return ItemOutcome::Moved(size),
+            }
+        }
+        PlanAction::Delete => {
+            let removed = if metadata.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+            match removed {
+                Ok(_) => record_done("delete", &item.path, Some(size), None, "cleanup plan"),
+                Err(ref e) => record_failed("delete", &item.path, Some(size), &e.to_string()),
+            }
+            removed.map(|_| size).map_err(|e| e.into())
+        }
+        PlanAction::Move => match destination {
+            Some(destination) => match relocate(path, destination, leave_links, |_, _, _| {}, should_stop) {
+                // A rename within the volume leaves its free space as it was
+                Ok(relocated) if relocated.hash.is_none() => return ItemOutcome::Moved(size),
+                Ok(_) => Ok(size),
+                Err(e) => Err(e),
+            },
+            None => Err("no move destination set in the plan".into()),
+        },
+        PlanAction::Compress => compress_in_place(path, item.format, item.delete_original, |_, _, _| {}, should_stop).map(|compressed| compressed.saved()),
+    };
+    match result {
+        Ok(freed) => ItemOutcome::Freed(freed),
+        Err(e) => ItemOutcome::Failed(e.to_string()),
+    }
+}
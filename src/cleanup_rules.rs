@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::file_utils::wildcard_match;
+
+/// Locations kept per rule in the report, largest first
+const MAX_LOCATIONS_PER_RULE: usize = 50;
+
+/// A kind of regenerable data, identified by path patterns.
+/// Patterns are matched against the whole path, lowercase with `/` separators:
+/// `*` matches within one path segment, `**` matches any number of segments,
+/// a leading `~` is the user's home folder and `%VAR%` expands environment variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupRule {
+    pub name: String,
+    pub explanation: String,
+    pub patterns: Vec<String>,
+    /// Only apply on this OS ("windows", "linux", ...), any OS when empty
+    pub os: Option<String>,
+}
+
+/// Bytes found under one matching location
+#[derive(Debug, Clone)]
+pub struct CleanupLocation {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CleanupRuleReport {
+    pub name: String,
+    pub explanation: String,
+    pub total_size: u64,
+    pub file_count: u64,
+    /// Largest matching locations, at most `MAX_LOCATIONS_PER_RULE`
+    pub locations: Vec<CleanupLocation>,
+    pub location_count: usize,
+}
+
+fn rule(name: &str, explanation: &str, os: Option<&str>, patterns: &[&str]) -> CleanupRule {
+    CleanupRule {
+        name: name.to_string(),
+        explanation: explanation.to_string(),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        os: os.map(|os| os.to_string()),
+    }
+}
+
+fn builtin_rules() -> Vec<CleanupRule> {
+    const WINDOWS: Option<&str> = Some("windows");
+    const LINUX: Option<&str> = Some("linux");
+    
+    vec![
+        // Windows
+        rule("Temporary files", "Scratch files left behind by applications and installers. Safe to delete when no program is running; Windows recreates the folders.", WINDOWS,
+            &["%TEMP%", "*:/windows/temp", "*:/users/*/appdata/local/temp"]),
+        rule("Browser caches", "Cached web content. Browsers download it again as needed; clear it from the browser settings or delete while the browser is closed.", WINDOWS,
+            &["*:/users/*/appdata/local/google/chrome/user data/*/cache", "*:/users/*/appdata/local/google/chrome/user data/*/code cache",
+              "*:/users/*/appdata/local/microsoft/edge/user data/*/cache", "*:/users/*/appdata/local/microsoft/edge/user data/*/code cache",
+              "*:/users/*/appdata/local/mozilla/firefox/profiles/*/cache2", "*:/users/*/appdata/local/microsoft/windows/inetcache"]),
+        rule("Thumbnail cache", "Explorer thumbnail databases, rebuilt automatically. Use Disk Cleanup > Thumbnails to clear them.", WINDOWS,
+            &["*:/users/*/appdata/local/microsoft/windows/explorer/thumbcache_*.db", "*:/users/*/appdata/local/microsoft/windows/explorer/iconcache_*.db"]),
+        rule("Crash dumps", "Memory dumps and error reports written after crashes. Only needed to debug those crashes.", WINDOWS,
+            &["*:/users/*/appdata/local/crashdumps", "*:/windows/minidump", "*:/windows/memory.dmp",
+              "*:/programdata/microsoft/windows/wer", "*:/users/*/appdata/local/microsoft/windows/wer"]),
+        rule("Windows Update leftovers", "Downloaded update packages and delivery optimization cache. Clear with Disk Cleanup > Windows Update Cleanup.", WINDOWS,
+            &["*:/windows/softwaredistribution/download", "*:/windows/serviceprofiles/networkservice/appdata/local/microsoft/windows/deliveryoptimization/cache"]),
+        rule("Previous Windows installation", "Kept after a feature upgrade to allow rollback. Remove with Disk Cleanup > Previous Windows installation(s).", WINDOWS,
+            &["*:/windows.old", "*:/$windows.~bt", "*:/$windows.~ws"]),
+        rule("Recycle Bin", "Files already deleted by users. Empty the Recycle Bin to reclaim the space.", WINDOWS,
+            &["*:/$recycle.bin"]),
+        
+        // Linux
+        rule("Temporary files", "Scratch files in the system temp folders. Usually cleared at boot or by systemd-tmpfiles.", LINUX,
+            &["/tmp", "/var/tmp"]),
+        rule("Browser caches", "Cached web content. Browsers download it again as needed.", LINUX,
+            &["/home/*/.cache/google-chrome", "/home/*/.cache/chromium", "/home/*/.cache/mozilla/firefox/*/cache2", "/root/.cache/mozilla/firefox/*/cache2"]),
+        rule("Thumbnail cache", "Desktop thumbnail cache, regenerated on demand.", LINUX,
+            &["/home/*/.cache/thumbnails", "/root/.cache/thumbnails"]),
+        rule("Crash dumps", "Core dumps and crash reports. Only needed to debug those crashes; `coredumpctl` lists them.", LINUX,
+            &["/var/crash", "/var/lib/systemd/coredump"]),
+        rule("Trash", "Files already deleted from the desktop. Empty the Trash to reclaim the space.", LINUX,
+            &["/home/*/.local/share/trash", "/root/.local/share/trash"]),
+        rule("APT package cache", "Downloaded .deb packages. Clear with `apt-get clean`.", LINUX,
+            &["/var/cache/apt/archives"]),
+        rule("systemd journal", "Archived system logs. Shrink with `journalctl --vacuum-size=...`.", LINUX,
+            &["/var/log/journal"]),
+        
+        // Any OS
+        rule("npm cache", "Package tarballs cached by npm. Clear with `npm cache clean --force`.", None,
+            &["**/.npm/_cacache", "*:/users/*/appdata/local/npm-cache"]),
+        rule("pip cache", "Wheels and sources cached by pip. Clear with `pip cache purge`.", None,
+            &["**/.cache/pip", "*:/users/*/appdata/local/pip/cache"]),
+        rule("Cargo cache", "Downloaded crates and git checkouts. Clear with `cargo cache --autoclean` or delete; Cargo downloads them again.", None,
+            &["**/.cargo/registry/cache", "**/.cargo/registry/src", "**/.cargo/git/checkouts"]),
+        rule("NuGet cache", "Global NuGet packages folder. Clear with `dotnet nuget locals all --clear`.", None,
+            &["**/.nuget/packages"]),
+        rule("Gradle cache", "Gradle dependency and build caches, downloaded again on the next build.", None,
+            &["**/.gradle/caches", "**/.gradle/wrapper/dists"]),
+        rule("Maven repository", "Local Maven repository, downloaded again on the next build.", None,
+            &["**/.m2/repository"]),
+    ]
+}
+
+/// A path pattern in the cleanup rule syntax, ready to match normalized path segments
+pub struct CompiledPattern {
+    segments: Vec<String>,
+}
+
+impl CompiledPattern {
+    pub fn new(pattern: &str) -> Self {
+        let expanded = normalize_path(&expand_pattern(pattern));
+        Self {
+            segments: expanded.split('/').map(|segment| segment.to_string()).collect(),
+        }
+    }
+    
+    pub fn matches(&self, path_segments: &[&str]) -> bool {
+        match_segments(&self.segments, path_segments)
+    }
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some(segment) if segment == "**" => (0..=path.len()).any(|skip| match_segments(&pattern[1..], &path[skip..])),
+        Some(segment) => !path.is_empty() && wildcard_match(segment, path[0]) && match_segments(&pattern[1..], &path[1..]),
+    }
+}
+
+/// Expand a leading `~` and `%VAR%` references
+fn expand_pattern(pattern: &str) -> String {
+    let mut expanded = pattern.to_string();
+    if let Some(rest) = expanded.strip_prefix('~') {
+        if let Some(home) = std::env::var_os("USERPROFILE").or_else(|| std::env::var_os("HOME")) {
+            expanded = format!("{}{}", home.to_string_lossy(), rest);
+        }
+    }
+    
+    while let Some(start) = expanded.find('%') {
+        let Some(length) = expanded[start + 1..].find('%') else { break };
+        let name = &expanded[start + 1..start + 1 + length];
+        match std::env::var(name) {
+            Ok(value) => expanded.replace_range(start..start + length + 2, &value),
+            Err(_) => {
+                warn!("Unknown environment variable %{}% in cleanup rule pattern", name);
+                break;
+            }
+        }
+    }
+    
+    expanded
+}
+
+/// Lowercase with `/` separators and no trailing `/`, the form patterns are matched against
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+/// Built-in rules for the current OS plus the user's rules
+pub struct CleanupRules {
+    rules: Vec<CleanupRule>,
+    patterns: Vec<(CompiledPattern, usize)>,
+}
+
+impl CleanupRules {
+    pub fn with_user_rules(user_rules: &[CleanupRule]) -> Self {
+        let applies = |rule: &CleanupRule| rule.os.as_deref().is_none_or(|os| os.eq_ignore_ascii_case(std::env::consts::OS));
+        
+        let mut rules: Vec<CleanupRule> = builtin_rules().into_iter().filter(applies).collect();
+        for rule in user_rules.iter().filter(|rule| applies(rule)) {
+            if rule.name.trim().is_empty() || rule.patterns.is_empty() {
+                warn!("Ignoring cleanup rule without a name or patterns");
+                continue;
+            }
+            // A user rule replaces the built-in rule of the same name
+            rules.retain(|existing| !existing.name.eq_ignore_ascii_case(&rule.name));
+            rules.push(rule.clone());
+        }
+        
+        let patterns = rules
+            .iter()
+            .enumerate()
+            .flat_map(|(index, rule)| rule.patterns.iter().map(move |pattern| (CompiledPattern::new(pattern), index)))
+            .collect::<Vec<_>>();
+        
+        info!("Cleanup rules ready: {} rules, {} patterns", rules.len(), patterns.len());
+        Self { rules, patterns }
+    }
+    
+    fn match_path(&self, path: &Path) -> Option<usize> {
+        let normalized = normalize_path(&path.to_string_lossy());
+        let segments: Vec<&str> = normalized.split('/').collect();
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&segments))
+            .map(|(_, index)| *index)
+    }
+}
+
+impl Default for CleanupRules {
+    fn default() -> Self {
+        Self::with_user_rules(&[])
+    }
+}
+
+/// Attributes scanned entries to cleanup rules while the scanner walks.
+/// A matching folder claims everything below it, so nothing is counted twice.
+#[derive(Default)]
+pub struct CleanupTracker {
+    // rule index -> locations found
+    locations: HashMap<usize, Vec<CleanupLocation>>,
+    // folder currently being attributed: (path, rule index, location index)
+    current: Option<(PathBuf, usize, usize)>,
+}
+
+impl CleanupTracker {
+    pub fn observe(&mut self, rules: &CleanupRules, path: &Path, is_dir: bool, size: u64) {
+        // The walk is depth-first, so leaving the matched folder means we are done with it
+        if let Some((root, rule, location)) = &self.current {
+            if path.starts_with(root) {
+                if !is_dir {
+                    let location = &mut self.locations.get_mut(rule).unwrap()[*location];
+                    location.size += size;
+                    location.file_count += 1;
+                }
+                return;
+            }
+            self.current = None;
+        }
+        
+        let Some(rule) = rules.match_path(path) else { return };
+        let locations = self.locations.entry(rule).or_default();
+        locations.push(CleanupLocation {
+            path: path.to_string_lossy().to_string(),
+            size: if is_dir { 0 } else { size },
+            file_count: if is_dir { 0 } else { 1 },
+        });
+        if is_dir {
+            self.current = Some((path.to_path_buf(), rule, locations.len() - 1));
+        }
+    }
+    
+    /// Per-rule totals, most reclaimable first, empty rules left out
+    pub fn report(&self, rules: &CleanupRules) -> Vec<CleanupRuleReport> {
+        let mut reports: Vec<CleanupRuleReport> = self.locations
+            .iter()
+            .map(|(&index, locations)| {
+                let mut locations: Vec<CleanupLocation> = locations.iter().filter(|l| l.size > 0).cloned().collect();
+                locations.sort_by_key(|l| std::cmp::Reverse(l.size));
+                let location_count = locations.len();
+                let total_size = locations.iter().map(|l| l.size).sum();
+                let file_count = locations.iter().map(|l| l.file_count).sum();
+                locations.truncate(MAX_LOCATIONS_PER_RULE);
+                CleanupRuleReport {
+                    name: rules.rules[index].name.clone(),
+                    explanation: rules.rules[index].explanation.clone(),
+                    total_size,
+                    file_count,
+                    locations,
+                    location_count,
+                }
+            })
+            .filter(|report| report.total_size > 0)
+            .collect();
+        reports.sort_by_key(|report| std::cmp::Reverse(report.total_size));
+        reports
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use log::{error, info};
+
+use crate::cleanup_plan::{execute_plan, CleanupPlan, ItemOutcome};
+use crate::cleanup_rules::CleanupRules;
+use crate::config::load_config;
+use crate::disk_scanner::{DiskScanner, ScanOptions};
+use crate::file_category::CategoryRegistry;
+use crate::file_utils::format_size;
+use crate::protected_paths::ProtectedPaths;
+use crate::query::{format_table, parse_query, run_query, to_json};
+use crate::scan_index::ScanIndex;
+use crate::snapshot::{load_snapshot, save_snapshot};
+
+const USAGE: &str = "Usage:
+  fat-folder-discovery --scan <path> [--save-snapshot <file>] [--query <query>] [--json]
+  fat-folder-discovery --snapshot <file> --query <query> [--json]
+  fat-folder-discovery --run-plan <plan.json> [--json]
+
+Queries combine terms such as:
+  type:file ext:iso,vhdx size>4G mtime<2023-01-01 path:\"D:\\\\Archive\" sort:size limit:50
+  group:ext sum:size";
+
+/// Headless mode: scan or load a snapshot, then save it or run a query. Returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let mut scan_path = None;
+    let mut snapshot_path = None;
+    let mut save_path = None;
+    let mut query_text = None;
+    let mut plan_path = None;
+    let mut json = false;
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| match args.next() {
+            Some(value) => Ok(value.clone()),
+            None => Err(format!("{} needs a value", name)),
+        };
+        let parsed = match arg.as_str() {
+            "--scan" => value(arg).map(|value| scan_path = Some(PathBuf::from(value))),
+            "--snapshot" => value(arg).map(|value| snapshot_path = Some(PathBuf::from(value))),
+            "--save-snapshot" => value(arg).map(|value| save_path = Some(PathBuf::from(value))),
+            "--query" => value(arg).map(|value| query_text = Some(value)),
+            "--run-plan" => value(arg).map(|value| plan_path = Some(PathBuf::from(value))),
+            "--json" => {
+                json = true;
+                Ok(())
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return 0;
+            }
+            other => Err(format!("Unknown argument \"{}\"", other)),
+        };
+        if let Err(e) = parsed {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    }
+    
+    if let Some(path) = plan_path {
+        return run_plan(&path, json);
+    }
+    
+    // Parse first so a typo does not cost a whole scan
+    let query = match query_text.as_deref().map(parse_query).transpose() {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("Invalid query: {}", e);
+            if let Some(text) = &query_text {
+                eprintln!("  {}\n  {}^", text, " ".repeat(e.position));
+            }
+            return 2;
+        }
+    };
+    
+    let index = match (&scan_path, &snapshot_path) {
+        (Some(path), None) => scan(path),
+        (None, Some(path)) => match load_snapshot(path) {
+            Ok(snapshot) => Some(snapshot.index),
+            Err(e) => {
+                error!("Failed to load snapshot {}: {}", path.display(), e);
+                eprintln!("Failed to load snapshot {}: {}", path.display(), e);
+                None
+            }
+        },
+        _ => {
+            eprintln!("Pass either --scan or --snapshot\n\n{}", USAGE);
+            return 2;
+        }
+    };
+    let Some(index) = index else { return 1 };
+    
+    if let Some(path) = &save_path {
+        if let Err(e) = save_snapshot(path, &index) {
+            error!("Failed to save snapshot {}: {}", path.display(), e);
+            eprintln!("Failed to save snapshot {}: {}", path.display(), e);
+            return 1;
+        }
+        eprintln!("Saved {} entries to {}", index.nodes.len(), path.display());
+    }
+    
+    if let Some(query) = query {
+        let output = run_query(&index, &query);
+        if json {
+            println!("{}", to_json(&output));
+        } else {
+            println!("{}", format_table(&output));
+        }
+    }
+    0
+}
+
+/// Execute a saved cleanup plan and print what it freed
+fn run_plan(path: &Path, json: bool) -> i32 {
+    let plan = match CleanupPlan::load(path) {
+        Ok(plan) => plan,
+        Err(e) => {
+            error!("Failed to load cleanup plan {}: {}", path.display(), e);
+            eprintln!("Failed to load cleanup plan {}: {}", path.display(), e);
+            return 1;
+        }
+    };
+    
+    let protected = ProtectedPaths::from_config(&load_config());
+    eprintln!("{}", protected.summary());
+    let should_stop = Arc::new(Mutex::new(false));
+    let report = execute_plan(&plan, &protected, |done, total, item| {
+        if !item.is_empty() {
+            eprintln!("[{}/{}] {}", done + 1, total, item);
+        }
+    }, &should_stop);
+    
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("Failed to format the report: {}", e),
+        }
+    } else {
+        for item in &report.items {
+            let outcome = match &item.outcome {
+                ItemOutcome::Freed(bytes) => format!("freed {}", format_size(*bytes)),
+                ItemOutcome::Moved(bytes) => format!("moved {}, nothing freed", format_size(*bytes)),
+                ItemOutcome::Skipped(reason) => format!("skipped: {}", reason),
+                ItemOutcome::Failed(e) => format!("FAILED: {}", e),
+            };
+            println!("{:<10}  {}  ({})", format!("{:?}", item.action).to_lowercase(), item.path, outcome);
+        }
+        println!("Freed {}, moved {} without freeing it, {} failed", format_size(report.freed()), format_size(report.moved()), report.failed());
+    }
+    if report.failed() > 0 { 1 } else { 0 }
+}
+
+fn scan(path: &Path) -> Option<ScanIndex> {
+    info!("Headless scan of {}", path.display());
+    eprintln!("Scanning {}...", path.display());
+    let config = load_config();
+    let options = ScanOptions {
+        categories: Arc::new(CategoryRegistry::with_user_categories(&config.categories)),
+        cleanup_rules: Arc::new(CleanupRules::with_user_rules(&config.cleanup_rules)),
+        ..Default::default()
+    };
+    // Nobody listens for progress updates here, so they are dropped right away
+    let (sender, receiver) = mpsc::channel();
+    drop(receiver);
+    
+    let mut scanner = DiskScanner::new_with_sender(path.to_path_buf(), 10, 10, options, sender);
+    match scanner.scan() {
+        Ok(results) => results.index.map(Arc::unwrap_or_clone),
+        Err(e) => {
+            error!("Headless scan of {} failed: {}", path.display(), e);
+            eprintln!("Scan failed: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::audit::{record_done, record_failed};
+use crate::file_age::{civil_date, SECONDS_PER_DAY};
+use crate::file_utils::{format_size, total_size};
+
+/// Close to the best tar.zst ratio at several times the speed of the maximum level
+const ZSTD_LEVEL: i32 = 15;
+/// Progress is reported at most once per this many bytes
+const PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+
+/// Archive written next to a compressed folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressFormat {
+    /// Opens anywhere, Windows included
+    #[default]
+    Zip,
+    /// Smallest result
+    TarZst,
+}
+
+impl CompressFormat {
+    pub const ALL: [CompressFormat; 2] = [CompressFormat::Zip, CompressFormat::TarZst];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompressFormat::Zip => "ZIP",
+            CompressFormat::TarZst => "TAR.ZST",
+        }
+    }
+    
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressFormat::Zip => ".zip",
+            CompressFormat::TarZst => ".tar.zst",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Compressed {
+    pub archive: PathBuf,
+    pub original_size: u64,
+    pub archive_size: u64,
+    pub files: usize,
+    pub original_deleted: bool,
+}
+
+impl Compressed {
+    /// Space actually freed, nothing while the original is kept
+    pub fn saved(&self) -> u64 {
+        if self.original_deleted {
+            self.original_size.saturating_sub(self.archive_size)
+        } else {
+            0
+        }
+    }
+}
+
+/// Messages from compression running in the background
+pub enum CompressMessage {
+    Progress { done: u64, total: u64, path: String },
+    Finished(Vec<(String, Result<Compressed, String>)>),
+}
+
+/// What each archive entry must hold, keyed by its name in the archive
+#[derive(Debug, PartialEq)]
+enum Expected {
+    Folder,
+    File { size: u64, hash: String },
+    Link(String),
+}
+
+/// Archive a file or folder next to itself, verify every entry by reading the archive back,
+/// and then optionally delete the original. Nothing is left behind when cancelled or failed.
+pub fn compress_in_place(
+    path: &Path,
+    format: CompressFormat,
+    delete_original: bool,
+    progress: impl FnMut(u64, u64, &str),
+    should_stop: &Arc<Mutex<bool>>,
+) -> Result<Compressed, Box<dyn std::error::Error>> {
+    let result = compress_verified(path, format, delete_original, progress, should_stop);
+    let shown = path.to_string_lossy();
+    match &result {
+        Ok(compressed) => record_done("compress", &shown, Some(compressed.original_size), None, &format!(
+            "into {} ({}), original {}, freed {}",
+            compressed.archive.display(),
+            format_size(compressed.archive_size),
+            if compressed.original_deleted { "deleted" } else { "kept" },
+            format_size(compressed.saved()),
+        )),
+        Err(e) => record_failed("compress", &shown, total_size(path).ok(), &e.to_string()),
+    }
+    result
+}
+
+fn compress_verified(
+    path: &Path,
+    format: CompressFormat,
+    delete_original: bool,
+    mut progress: impl FnMut(u64, u64, &str),
+    should_stop: &Arc<Mutex<bool>>,
+) -> Result<Compressed, Box<dyn std::error::Error>> {
+    let mut archive = path.as_os_str().to_os_string();
+    archive.push(format.extension());
+    let archive = PathBuf::from(archive);
+    if fs::symlink_metadata(&archive).is_ok() {
+        return Err(format!("{} already exists", archive.display()).into());
+    }
+    let original_size = total_size(path)?;
+    info!("Compressing {} ({}) into {}", path.display(), format_size(original_size), archive.display());
+    
+    // Only gets the archive's name once verified
+    let mut partial = archive.as_os_str().to_os_string();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = write_archive(path, &partial, format, original_size, &mut progress, should_stop)
+        .and_then(|expected| {
+            progress(original_size, original_size, &format!("Verifying {}", archive.display()));
+            verify_archive(&partial, format, &expected, should_stop).map(|_| expected)
+        });
+    let expected = match result {
+        Ok(expected) => expected,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial, &archive)?;
+    let archive_size = fs::metadata(&archive)?.len();
+    info!("Verified {} entries in {} ({})", expected.len(), archive.display(), format_size(archive_size));
+    
+    if delete_original {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+        info!("Deleted {} after compressing, saved {}", path.display(), format_size(original_size.saturating_sub(archive_size)));
+    }
+    Ok(Compressed {
+        archive,
+        original_size,
+        archive_size,
+        files: expected.values().filter(|entry| matches!(entry, Expected::File { .. })).count(),
+        original_deleted: delete_original,
+    })
+}
+
+/// Passes file content through while hashing it and checking for cancellation
+struct HashingReader<'a, R> {
+    inner: R,
+    hasher: blake3::Hasher,
+    read: u64,
+    should_stop: &'a Arc<Mutex<bool>>,
+}
+
+impl<R: Read> Read for HashingReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if *self.should_stop.lock().unwrap() {
+            // Not `Interrupted`, which `io::copy` would retry
+            return Err(io::Error::other("cancelled"));
+        }
+        let read = self.inner.read(buffer)?;
+        self.hasher.update(&buffer[..read]);
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+fn write_archive(
+    source: &Path,
+    archive: &Path,
+    format: CompressFormat,
+    total: u64,
+    progress: &mut impl FnMut(u64, u64, &str),
+    should_stop: &Arc<Mutex<bool>>,
+) -> Result<BTreeMap<String, Expected>, Box<dyn std::error::Error>> {
+    let output = BufWriter::new(File::create(archive)?);
+    let mut writer = match format {
+        CompressFormat::Zip => ArchiveWriter::Zip(Box::new(zip::ZipWriter::new(output))),
+        CompressFormat::TarZst => ArchiveWriter::TarZst(tar::Builder::new(zstd::Encoder::new(output, ZSTD_LEVEL)?)),
+    };
+    
+    let mut expected = BTreeMap::new();
+    let mut done = 0;
+    let mut reported = 0;
+    // Entry names start with the item's own name so extracting recreates it
+    let base = source.parent().unwrap_or(source);
+    for entry in WalkDir::new(source).follow_links(false).sort_by_file_name() {
+        let entry = entry?;
+        let name = entry.path().strip_prefix(base)?.to_string_lossy().replace('\\', "/");
+        let metadata = entry.metadata()?;
+        if entry.file_type().is_dir() {
+            writer.add_folder(&name, &metadata)?;
+            expected.insert(name, Expected::Folder);
+        } else if entry.file_type().is_symlink() {
+            let target = fs::read_link(entry.path())?.to_string_lossy().to_string();
+            writer.add_link(&name, &target, &metadata)?;
+            expected.insert(name, Expected::Link(target));
+        } else {
+            let mut reader = HashingReader {
+                inner: File::open(entry.path())?,
+                hasher: blake3::Hasher::new(),
+                read: 0,
+                should_stop,
+            };
+            writer.add_file(&name, &metadata, &mut reader)
+                .map_err(|e| if *should_stop.lock().unwrap() { "cancelled".to_string() } else { e.to_string() })?;
+            done += reader.read;
+            expected.insert(name, Expected::File { size: reader.read, hash: reader.hasher.finalize().to_hex().to_string() });
+            if done >= reported + PROGRESS_INTERVAL || done == total {
+                reported = done;
+                progress(done, total, &entry.path().to_string_lossy());
+            }
+        }
+    }
+    writer.finish()?;
+    Ok(expected)
+}
+
+enum ArchiveWriter {
+    Zip(Box<zip::ZipWriter<BufWriter<File>>>),
+    TarZst(tar::Builder<zstd::Encoder<'static, BufWriter<File>>>),
+}
+
+impl ArchiveWriter {
+    fn add_folder(&mut self, name: &str, metadata: &fs::Metadata) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ArchiveWriter::Zip(writer) => writer.add_directory(name, zip_options(metadata))?,
+            ArchiveWriter::TarZst(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+                builder.append_data(&mut header, format!("{}/", name), io::empty())?;
+            }
+        }
+        Ok(())
+    }
+    
+    fn add_link(&mut self, name: &str, target: &str, metadata: &fs::Metadata) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ArchiveWriter::Zip(writer) => writer.add_symlink(name, target, zip_options(metadata))?,
+            ArchiveWriter::TarZst(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, name, target)?;
+            }
+        }
+        Ok(())
+    }
+    
+    fn add_file(&mut self, name: &str, metadata: &fs::Metadata, content: &mut impl Read) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Zip(writer) => {
+                writer.start_file(name, zip_options(metadata))?;
+                io::copy(content, writer.as_mut())?;
+            }
+            ArchiveWriter::TarZst(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+                builder.append_data(&mut header, name, content)?;
+            }
+        }
+        Ok(())
+    }
+    
+    fn finish(self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut output = match self {
+            ArchiveWriter::Zip(writer) => writer.finish()?,
+            ArchiveWriter::TarZst(builder) => builder.into_inner()?.finish()?,
+        };
+        output.flush()?;
+        output.get_ref().sync_all()?;
+        Ok(())
+    }
+}
+
+/// Deflate with the entry's modification time, which zip stores without a zone, so UTC is used
+fn zip_options(metadata: &fs::Metadata) -> zip::write::SimpleFileOptions {
+    let mut options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    if let Ok(modified) = metadata.modified() {
+        let (year, month, day) = civil_date(modified);
+        let seconds = modified.duration_since(UNIX_EPOCH).map(|age| age.as_secs()).unwrap_or(0) % SECONDS_PER_DAY;
+        let time = zip::DateTime::from_date_and_time(year as u16, month as u8, day as u8,
+            (seconds / 3600) as u8, (seconds / 60 % 60) as u8, (seconds % 60) as u8);
+        // Zip dates start in 1980, older files keep the default
+        if let Ok(time) = time {
+            options = options.last_modified_time(time);
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        options = options.unix_permissions(metadata.permissions().mode() & 0o7777);
+    }
+    options
+}
+
+/// Read every entry back and check it against what was written
+fn verify_archive(archive: &Path, format: CompressFormat, expected: &BTreeMap<String, Expected>, should_stop: &Arc<Mutex<bool>>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut found = BTreeMap::new();
+    let mut check = |name: String, entry: Expected| -> Result<(), Box<dyn std::error::Error>> {
+        if *should_stop.lock().unwrap() {
+            return Err("cancelled".into());
+        }
+        if expected.get(&name) != Some(&entry) {
+            return Err(format!("{} in the archive does not match the original", name).into());
+        }
+        found.insert(name, ());
+        Ok(())
+    };
+    
+    match format {
+        CompressFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(BufReader::new(File::open(archive)?))?;
+            for index in 0..zip.len() {
+                let mut file = zip.by_index(index)?;
+                let name = file.name().trim_end_matches('/').to_string();
+                let entry = if file.is_dir() {
+                    Expected::Folder
+                } else if file.is_symlink() {
+                    let mut target = String::new();
+                    file.read_to_string(&mut target)?;
+                    Expected::Link(target)
+                } else {
+                    hash_entry(&mut file)?
+                };
+                check(name, entry)?;
+            }
+        }
+        CompressFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+            for file in tar.entries()? {
+                let mut file = file?;
+                let name = file.path()?.to_string_lossy().trim_end_matches('/').to_string();
+                let entry = match file.header().entry_type() {
+                    tar::EntryType::Directory => Expected::Folder,
+                    tar::EntryType::Symlink => Expected::Link(file.link_name()?.map(|target| target.to_string_lossy().to_string()).unwrap_or_default()),
+                    _ => hash_entry(&mut file)?,
+                };
+                check(name, entry)?;
+            }
+        }
+    }
+    
+    if found.len() != expected.len() {
+        let missing = expected.keys().find(|name| !found.contains_key(*name)).cloned().unwrap_or_default();
+        warn!("{} is missing {} entries, first {}", archive.display(), expected.len() - found.len(), missing);
+        return Err(format!("{} is missing from the archive", missing).into());
+    }
+    Ok(())
+}
+
+fn hash_entry(content: &mut impl Read) -> io::Result<Expected> {
+    let mut hasher = blake3::Hasher::new();
+    let size = io::copy(content, &mut hasher)?;
+    Ok(Expected::File { size, hash: hasher.finalize().to_hex().to_string() })
+}
@@ -0,0 +1,179 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use walkdir::WalkDir;
+
+use crate::file_category::CategoryRegistry;
+
+/// Bytes read per sample and samples taken per file
+const SAMPLE_SIZE: u64 = 64 * 1024;
+const SAMPLES_PER_FILE: u64 = 8;
+/// Fast zstd level, close to what file system compression achieves
+const ZSTD_LEVEL: i32 = 1;
+/// Largest files sampled per folder; they dominate the folder's bytes
+const FILES_PER_FOLDER: usize = 32;
+/// Above this entropy (bits per byte) or compression ratio data is already compressed
+const COMPRESSED_ENTROPY: f32 = 7.5;
+const COMPRESSED_RATIO: f64 = 0.95;
+
+/// Estimated result of compressing a file, folder or category
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Compressibility {
+    /// Bytes the estimate covers
+    pub total_bytes: u64,
+    /// Estimated size after compression
+    pub compressed_bytes: u64,
+    /// Shannon entropy of the sampled bytes, 0 to 8 bits per byte
+    pub entropy: f32,
+}
+
+impl Compressibility {
+    pub fn ratio(&self) -> f64 {
+        if self.total_bytes == 0 { 1.0 } else { self.compressed_bytes as f64 / self.total_bytes as f64 }
+    }
+    
+    pub fn savings(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.compressed_bytes)
+    }
+    
+    /// Media, archives and encrypted data: compressing again gains nothing
+    pub fn is_already_compressed(&self) -> bool {
+        self.entropy >= COMPRESSED_ENTROPY || self.ratio() >= COMPRESSED_RATIO
+    }
+    
+    fn merge(&mut self, other: &Compressibility) {
+        let total = self.total_bytes + other.total_bytes;
+        if total > 0 {
+            self.entropy = (self.entropy as f64 * self.total_bytes as f64 / total as f64
+                + other.entropy as f64 * other.total_bytes as f64 / total as f64) as f32;
+        }
+        self.total_bytes = total;
+        self.compressed_bytes += other.compressed_bytes;
+    }
+    
+    /// The same ratio and entropy applied to `total_bytes`
+    pub fn scaled_to(&self, total_bytes: u64) -> Compressibility {
+        Compressibility {
+            total_bytes,
+            compressed_bytes: (total_bytes as f64 * self.ratio()) as u64,
+            entropy: self.entropy,
+        }
+    }
+}
+
+/// Compress evenly spaced samples of a file and extrapolate to its whole size
+pub fn estimate_file(path: &Path, size: u64) -> std::io::Result<Compressibility> {
+    let mut file = File::open(path)?;
+    let sample_count = size.div_ceil(SAMPLE_SIZE).min(SAMPLES_PER_FILE);
+    let stride = if sample_count > 1 { (size - SAMPLE_SIZE) / (sample_count - 1) } else { 0 };
+    
+    let mut histogram = [0u64; 256];
+    let mut sampled = 0u64;
+    let mut compressed = 0u64;
+    let mut buffer = Vec::with_capacity(SAMPLE_SIZE as usize);
+    for index in 0..sample_count {
+        buffer.clear();
+        file.seek(SeekFrom::Start(index * stride))?;
+        (&mut file).take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+        if buffer.is_empty() {
+            break;
+        }
+        for &byte in &buffer {
+            histogram[byte as usize] += 1;
+        }
+        sampled += buffer.len() as u64;
+        compressed += zstd::bulk::compress(&buffer, ZSTD_LEVEL)?.len().min(buffer.len()) as u64;
+    }
+    
+    if sampled == 0 {
+        return Ok(Compressibility::default());
+    }
+    let sample = Compressibility {
+        total_bytes: sampled,
+        compressed_bytes: compressed,
+        entropy: entropy(&histogram, sampled),
+    };
+    Ok(sample.scaled_to(size))
+}
+
+fn entropy(histogram: &[u64; 256], total: u64) -> f32 {
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>() as f32
+}
+
+/// Estimates files and folders, reusing per-file results and totalling them per category
+pub struct CompressionEstimator<'a> {
+    categories: &'a CategoryRegistry,
+    should_stop: &'a Arc<Mutex<bool>>,
+    files: HashMap<String, Compressibility>,
+}
+
+impl<'a> CompressionEstimator<'a> {
+    pub fn new(categories: &'a CategoryRegistry, should_stop: &'a Arc<Mutex<bool>>) -> Self {
+        Self { categories, should_stop, files: HashMap::new() }
+    }
+    
+    pub fn file(&mut self, path: &str, size: u64) -> Option<Compressibility> {
+        if let Some(estimate) = self.files.get(path) {
+            return Some(*estimate);
+        }
+        match estimate_file(Path::new(path), size) {
+            Ok(estimate) => {
+                self.files.insert(path.to_string(), estimate);
+                Some(estimate)
+            }
+            Err(e) => {
+                warn!("Failed to sample {} for compressibility: {}", path, e);
+                None
+            }
+        }
+    }
+    
+    /// Sample the folder's largest files and extrapolate their ratio to `folder_size`
+    pub fn folder(&mut self, path: &str, folder_size: u64) -> Option<Compressibility> {
+        let mut largest: BinaryHeap<Reverse<(u64, String)>> = BinaryHeap::new();
+        for entry in WalkDir::new(path).follow_links(false).into_iter().filter_map(|entry| entry.ok()) {
+            if *self.should_stop.lock().unwrap() {
+                return None;
+            }
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            largest.push(Reverse((metadata.len(), entry.path().to_string_lossy().to_string())));
+            if largest.len() > FILES_PER_FOLDER {
+                largest.pop();
+            }
+        }
+        
+        let mut sample = Compressibility::default();
+        for Reverse((size, file)) in largest.into_vec() {
+            if let Some(estimate) = self.file(&file, size) {
+                sample.merge(&estimate);
+            }
+        }
+        (sample.total_bytes > 0).then(|| sample.scaled_to(folder_size))
+    }
+    
+    /// Every sampled file's estimate totalled per category
+    pub fn category_totals(&self) -> HashMap<String, Compressibility> {
+        let mut totals: HashMap<String, Compressibility> = HashMap::new();
+        for (path, estimate) in &self.files {
+            let name = Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let category = self.categories.categorize(&name).name.clone();
+            totals.entry(category).or_default().merge(estimate);
+        }
+        info!("Compressibility estimated for {} files in {} categories", self.files.len(), totals.len());
+        totals
+    }
+}
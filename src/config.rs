@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use log::{error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::cleanup_rules::CleanupRule;
+use crate::file_category::CategoryConfig;
+use crate::protected_paths::ProtectedConfig;
+use crate::quarantine::QuarantineConfig;
+
+pub const CONFIG_FILE: &str = "fat-folder-discovery.json";
+
+/// User configuration read from `fat-folder-discovery.json` (see `config_file`).
+/// Every section is optional, a missing file means built-in defaults only.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub categories: Vec<CategoryConfig>,
+    pub cleanup_rules: Vec<CleanupRule>,
+    pub quarantine: QuarantineConfig,
+    pub protected: ProtectedConfig,
+    /// File the config was read from, `None` when there is none
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Why the `protected` section could not be read. Destructive actions are refused while set,
+    /// since running them without the user's protections could touch what they meant to keep.
+    #[serde(skip)]
+    pub protected_error: Option<String>,
+}
+
+/// File in the per-user data folder, shared by every run whatever its working directory:
+/// `%LOCALAPPDATA%\fat-folder-discovery` on Windows, `~/Library/Application Support/fat-folder-discovery`
+/// on macOS and `$XDG_DATA_HOME/fat-folder-discovery` (`~/.local/share/...`) elsewhere.
+/// Falls back to the working directory when none of these can be found or created.
+pub fn data_file(name: &str) -> PathBuf {
+    let variable = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        variable("LOCALAPPDATA").or_else(|| variable("APPDATA"))
+    } else if cfg!(target_os = "macos") {
+        variable("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        variable("XDG_DATA_HOME").or_else(|| variable("HOME").map(|home| home.join(".local/share")))
+    };
+    let folder = base.map(|base| base.join("fat-folder-discovery"));
+    match folder {
+        Some(folder) => match std::fs::create_dir_all(&folder) {
+            Ok(()) => folder.join(name),
+            Err(e) => {
+                warn!("Could not create data folder {}: {}, using the working directory", folder.display(), e);
+                PathBuf::from(name)
+            }
+        },
+        None => PathBuf::from(name),
+    }
+}
+
+/// The config in the per-user data folder, or failing that in the working directory
+/// where it has always been read from
+pub fn config_file() -> PathBuf {
+    let shared = data_file(CONFIG_FILE);
+    if shared.exists() {
+        shared
+    } else {
+        PathBuf::from(CONFIG_FILE)
+    }
+}
+
+/// Each section is read on its own, so a mistake in one only resets that section
+pub fn load_config() -> AppConfig {
+    let path = config_file();
+    if !path.exists() {
+        info!("No user config found at {}, using defaults", path.display());
+        return AppConfig::default();
+    }
+    
+    let mut config = AppConfig { source: Some(path.clone()), ..Default::default() };
+    let value: serde_json::Value = match std::fs::read_to_string(&path).map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string())) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Could not read user config {}: {}, using defaults and refusing destructive actions", path.display(), e);
+            config.protected_error = Some(format!("{} could not be read: {}", path.display(), e));
+            return config;
+        }
+    };
+    
+    let path_shown = path.display().to_string();
+    config.categories = section(&value, "categories", &path_shown).unwrap_or_default();
+    config.cleanup_rules = section(&value, "cleanup_rules", &path_shown).unwrap_or_default();
+    config.quarantine = section(&value, "quarantine", &path_shown).unwrap_or_default();
+    match section(&value, "protected", &path_shown) {
+        Ok(protected) => config.protected = protected,
+        Err(e) => {
+            error!("Refusing destructive actions until the protected section of {} is fixed", path.display());
+            config.protected_error = Some(format!("the protected section of {} is invalid: {}", path.display(), e));
+        }
+    }
+    info!("Loaded user config from {}", path.display());
+    config
+}
+
+fn section<T: DeserializeOwned + Default>(value: &serde_json::Value, name: &str, path: &str) -> Result<T, String> {
+    match value.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(T::default()),
+        Some(section) => T::deserialize(section).map_err(|e| {
+            warn!("Invalid {} section in user config {}: {}, using defaults", name, path, e);
+            e.to_string()
+        }),
+    }
+}
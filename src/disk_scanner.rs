@@ -1,16 +1,82 @@
 use std::path::{Path, PathBuf};
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::cmp::Ordering;
 use walkdir::WalkDir;
 use std::sync::{Arc, Mutex};
 use log::{info, warn};
 use std::sync::mpsc;
+use std::time::SystemTime;
+use crate::file_category::{CategoryRegistry, CategoryStats, CategoryTotals};
+use crate::file_signature::{detect_file_type, ContentDetection, ContentType};
+use crate::duplicates::{find_duplicates, DuplicateSet};
+use crate::similar_images::{find_similar_images, HashAlgorithm, SimilarImageGroup};
+use crate::empty_items::{EmptyItemsReport, EmptyItemsTracker};
+use crate::file_age::{age_in_days, AgeBuckets, StaleBasis};
+use crate::size_histogram::SizeHistogram;
+use crate::build_artifacts::{find_build_artifacts, BuildProject, ProjectTracker};
+use crate::git_repos::{analyze_repositories, GitRepository, RepositoryTracker};
+use crate::vm_images::{inspect_vm_images, is_vm_image_name, VmImage};
+use crate::system_files::{identify_system_file, SystemFileKind};
+use crate::sparse_files::{allocation_of, map_holes, Allocation};
+use crate::scan_index::{IndexBuilder, ScanIndex};
+use crate::archives::{list_archive, ArchiveFormat, ArchiveListing};
+use crate::compressibility::{Compressibility, CompressionEstimator};
+use crate::cleanup_rules::{CleanupRuleReport, CleanupRules, CleanupTracker};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ScanResult {
     pub name: String,
     pub path: String,
     pub size: u64,
+    /// File category name, `None` for folders
+    pub category: Option<String>,
+    /// Type identified from the file's content, when detection ran and matched
+    pub detected_type: Option<String>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    /// Creation time on Windows, birth time where the platform reports one
+    pub created: Option<SystemTime>,
+    /// Files anywhere below a folder, `None` for files
+    pub file_count: Option<u64>,
+    /// Files directly inside a folder, `None` for files
+    pub direct_file_count: Option<u64>,
+    /// OS-managed page, hibernation or swap file
+    pub system_file: Option<SystemFileKind>,
+    /// Sampled compression estimate, when estimation ran
+    pub compression: Option<Compressibility>,
+    /// Real bytes on disk of sparse, compressed or preallocated files
+    pub allocation: Option<Allocation>,
+}
+
+/// Heap entry ranking folders by recursive file count instead of size
+struct ByFileCount(ScanResult);
+
+impl PartialEq for ByFileCount {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.file_count == other.0.file_count
+    }
+}
+
+impl Eq for ByFileCount {}
+
+impl PartialOrd for ByFileCount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByFileCount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reverse order like ScanResult, so the heap drops the smallest count first
+        other.0.file_count.cmp(&self.0.file_count)
+    }
+}
+
+/// Sizes and file counts of a folder's subtree
+struct FolderTotals {
+    size: u64,
+    file_count: u64,
+    direct_file_count: u64,
 }
 
 impl PartialEq for ScanResult {
@@ -34,33 +100,123 @@ impl Ord for ScanResult {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct ScanResults {
     pub folders: Vec<ScanResult>,
     pub files: Vec<ScanResult>,
+    /// Per-category totals over every scanned file, largest first
+    pub categories: Vec<CategoryStats>,
+    /// Duplicate sets, most wasted bytes first (empty unless enabled)
+    pub duplicates: Vec<DuplicateSet>,
+    /// Groups of near-duplicate images, largest first (empty unless enabled)
+    pub similar_images: Vec<SimilarImageGroup>,
+    /// Empty folders and zero-byte files, filled in once the walk completes
+    pub empty_items: EmptyItemsReport,
+    /// Bytes per last-modified and last-accessed age bucket
+    pub age_buckets: AgeBuckets,
+    /// Largest files not touched for `stale_after_days`, largest first
+    pub stale_files: Vec<ScanResult>,
+    /// Largest files whose allocation differs from their length, largest first
+    pub sparse_files: Vec<ScanResult>,
+    /// File counts and bytes per size bucket
+    pub size_histogram: SizeHistogram,
+    /// Folders holding the most files (recursively), most first
+    pub busiest_folders: Vec<ScanResult>,
+    /// Bytes matched by each cleanup rule, most reclaimable first
+    pub cleanup: Vec<CleanupRuleReport>,
+    /// Projects with build artifact folders, largest first (empty unless enabled)
+    pub build_projects: Vec<BuildProject>,
+    /// Breakdown of every `.git` folder, largest first (empty unless enabled)
+    pub git_repositories: Vec<GitRepository>,
+    /// Virtual machine, container and ISO images, largest first
+    pub vm_images: Vec<VmImage>,
+    /// Listings of the ranked archive files (empty unless enabled)
+    pub archives: Vec<ArchiveListing>,
+    /// Compression estimates of the sampled files per category (empty unless enabled)
+    pub compression_by_category: HashMap<String, Compressibility>,
+    /// Every file and folder of the scan, for filtering without rescanning (final results only)
+    pub index: Option<Arc<ScanIndex>>,
+    /// Progress message for post-walk stages such as duplicate detection
+    pub status: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct ScanOptions {
+    pub categories: Arc<CategoryRegistry>,
+    /// Only rank files of this category (totals still cover every file)
+    pub category_filter: Option<String>,
+    pub content_detection: ContentDetection,
+    /// Run duplicate detection over files of at least `duplicate_min_size` bytes
+    pub find_duplicates: bool,
+    pub duplicate_min_size: u64,
+    /// Group images whose perceptual hashes differ by at most `image_max_distance` bits
+    pub find_similar_images: bool,
+    pub image_hash: HashAlgorithm,
+    pub image_max_distance: u32,
+    /// Files older than this many days by `stale_basis` enter the stale ranking
+    pub stale_after_days: u64,
+    pub stale_basis: StaleBasis,
+    /// Known cache, temp and junk locations to total up
+    pub cleanup_rules: Arc<CleanupRules>,
+    /// Find project roots by their marker files and measure their build artifacts
+    pub find_build_artifacts: bool,
+    /// Break `.git` folders down into packs, loose objects, LFS objects and worktrees
+    pub analyze_git_repos: bool,
+    /// Keep page, hibernation and swap files out of the file and stale rankings
+    pub exclude_system_files: bool,
+    /// List the contents of zip/tar archives that make it into the file ranking
+    pub inspect_archives: bool,
+    /// Sample the ranked files and folders to estimate what compressing them would save
+    pub estimate_compression: bool,
+}
+
+/// Running totals collected during the walk, sent along with each update
+#[derive(Default)]
+struct ScanTotals {
+    categories: CategoryTotals,
+    empty_items: EmptyItemsTracker,
+    ages: AgeBuckets,
+    stale_files: BinaryHeap<ScanResult>,
+    sparse_files: BinaryHeap<ScanResult>,
+    histogram: SizeHistogram,
+    busiest_folders: BinaryHeap<ByFileCount>,
+    cleanup: CleanupTracker,
+    projects: ProjectTracker,
+    repositories: RepositoryTracker,
+    index: IndexBuilder,
+}
+
+impl ScanTotals {
+    fn busiest_folders(&self) -> Vec<ScanResult> {
+        let mut folders: Vec<ScanResult> = self.busiest_folders.iter().map(|entry| entry.0.clone()).collect();
+        folders.sort_by_key(|folder| std::cmp::Reverse(folder.file_count));
+        folders
+    }
 }
 
 pub struct DiskScanner {
     root_path: PathBuf,
     file_limit: usize,
     folder_limit: usize,
+    options: ScanOptions,
     should_stop: Arc<Mutex<bool>>,
     result_sender: Option<mpsc::Sender<ScanResults>>,
 }
 
 impl DiskScanner {
     
-    pub fn new_with_sender(root_path: PathBuf, file_limit: usize, folder_limit: usize, sender: mpsc::Sender<ScanResults>) -> Self {
+    pub fn new_with_sender(root_path: PathBuf, file_limit: usize, folder_limit: usize, options: ScanOptions, sender: mpsc::Sender<ScanResults>) -> Self {
         Self {
             root_path,
             file_limit,
             folder_limit,
+            options,
             should_stop: Arc::new(Mutex::new(false)),
             result_sender: Some(sender),
         }
     }
     
-    fn send_incremental_results(&self, folders: &BinaryHeap<ScanResult>, files: &BinaryHeap<ScanResult>) {
+    fn send_incremental_results(&self, folders: &BinaryHeap<ScanResult>, files: &BinaryHeap<ScanResult>, totals: &ScanTotals) {
         if let Some(sender) = &self.result_sender {
             let mut folder_vec: Vec<ScanResult> = folders.iter().cloned().collect();
             folder_vec.sort_by(|a, b| b.size.cmp(&a.size));
@@ -71,6 +227,14 @@ impl DiskScanner {
             let results = ScanResults {
                 folders: folder_vec,
                 files: file_vec,
+                categories: totals.categories.to_sorted_vec(),
+                age_buckets: totals.ages.clone(),
+                stale_files: sorted_by_size(&totals.stale_files),
+                sparse_files: sorted_by_size(&totals.sparse_files),
+                size_histogram: totals.histogram.clone(),
+                busiest_folders: totals.busiest_folders(),
+                cleanup: totals.cleanup.report(&self.options.cleanup_rules),
+                ..Default::default()
             };
             
             let _ = sender.send(results);
@@ -85,6 +249,11 @@ impl DiskScanner {
         let mut file_heap: BinaryHeap<ScanResult> = BinaryHeap::new();
         let mut total_files_scanned = 0u64;
         let mut total_folders_scanned = 0u64;
+        let mut totals = ScanTotals::default();
+        let now = SystemTime::now();
+        let mut duplicate_candidates: Vec<(String, u64)> = Vec::new();
+        let mut image_candidates: Vec<(String, u64)> = Vec::new();
+        let mut vm_candidates: Vec<(String, u64)> = Vec::new();
         
         info!("Walking directory tree starting from: {}", self.root_path.display());
         info!("Note: Scanning entire disk, limits only affect display (top {} folders, {} files)", 
@@ -105,14 +274,26 @@ impl DiskScanner {
                 Ok(entry) => entry,
                 Err(e) => {
                     warn!("Failed to access entry: {}", e);
+                    if let Some(path) = e.path() {
+                        totals.empty_items.add_opaque(path);
+                    }
                     continue; // Skip inaccessible files/folders
                 }
             };
             
             let path = entry.path();
             
+            // Cleanup rules also cover the system folders skipped below, such as temp and the Recycle Bin
+            if entry.file_type().is_dir() {
+                totals.cleanup.observe(&self.options.cleanup_rules, path, true, 0);
+            } else if entry.file_type().is_file() {
+                let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                totals.cleanup.observe(&self.options.cleanup_rules, path, false, size);
+            }
+            
             // Skip system directories and files
             if self.should_skip_path(path) {
+                totals.empty_items.add_opaque(path);
                 continue;
             }
             
@@ -120,26 +301,44 @@ impl DiskScanner {
                 Ok(metadata) => metadata,
                 Err(e) => {
                     warn!("Failed to get metadata for {}: {}", path.display(), e);
+                    totals.empty_items.add_opaque(path);
                     continue;
                 }
             };
             
             if metadata.is_dir() {
+                totals.empty_items.add_folder(path);
+                totals.index.add(path, entry.depth(), true, 0, metadata.modified().ok(), None);
+                if self.options.analyze_git_repos {
+                    totals.repositories.add_folder(path);
+                }
                 total_folders_scanned += 1;
                 if total_folders_scanned % 1000 == 0 {
                     info!("Scanned {} folders so far...", total_folders_scanned);
                 }
                 
-                let folder_size = self.calculate_folder_size(path)?;
+                let folder_totals = self.calculate_folder_totals(path)?;
                 let folder_result = ScanResult {
                     name: path.file_name()
                         .and_then(|name| name.to_str())
                         .unwrap_or("Root")
                         .to_string(),
                     path: path.to_string_lossy().to_string(),
-                    size: folder_size,
+                    size: folder_totals.size,
+                    modified: metadata.modified().ok(),
+                    accessed: metadata.accessed().ok(),
+                    created: metadata.created().ok(),
+                    file_count: Some(folder_totals.file_count),
+                    direct_file_count: Some(folder_totals.direct_file_count),
+                    ..Default::default()
                 };
                 
+                // Same walk also ranks folders by how many files they hold
+                totals.busiest_folders.push(ByFileCount(folder_result.clone()));
+                if totals.busiest_folders.len() > self.folder_limit {
+                    totals.busiest_folders.pop();
+                }
+                
                 folder_heap.push(folder_result);
                 
                 // Keep only the largest folders for display, but continue scanning
@@ -149,7 +348,7 @@ impl DiskScanner {
                 
                 // Send incremental update every 50 folders (more frequent for better real-time feel)
                 if total_folders_scanned % 50 == 0 {
-                    self.send_incremental_results(&folder_heap, &file_heap);
+                    self.send_incremental_results(&folder_heap, &file_heap, &totals);
                 }
             } else if metadata.is_file() {
                 total_files_scanned += 1;
@@ -157,33 +356,108 @@ impl DiskScanner {
                     info!("Scanned {} files so far...", total_files_scanned);
                 }
                 
+                let name = path.file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("Unknown")
+                    .to_string();
+                let mut category = self.options.categories.categorize(&name).name.clone();
+                let mut detected_type = None;
+                
+                // Content detection overrides the extension guess for large files
+                if let ContentDetection::LargerThan(threshold) = self.options.content_detection {
+                    if metadata.len() >= threshold {
+                        if let Some(content_type) = self.detect_content_type(path) {
+                            category = content_type.category.to_string();
+                            detected_type = Some(content_type.name.to_string());
+                        }
+                    }
+                }
+                
+                totals.categories.add(&category, metadata.len());
+                totals.empty_items.add_file(path, metadata.len());
+                
+                if self.options.find_duplicates && metadata.len() >= self.options.duplicate_min_size.max(1) {
+                    duplicate_candidates.push((path.to_string_lossy().to_string(), metadata.len()));
+                }
+                
+                if self.options.find_similar_images && category == "Images" {
+                    image_candidates.push((path.to_string_lossy().to_string(), metadata.len()));
+                }
+                
+                if category == "Disk images" || is_vm_image_name(&name) {
+                    vm_candidates.push((path.to_string_lossy().to_string(), metadata.len()));
+                }
+                
+                let modified = metadata.modified().ok();
+                let accessed = metadata.accessed().ok();
+                totals.ages.add(now, metadata.len(), modified, accessed);
+                totals.index.add(path, entry.depth(), false, metadata.len(), modified, Some(&category));
+                totals.histogram.add(metadata.len());
+                
+                if self.options.find_build_artifacts {
+                    totals.projects.add_file(path, &name);
+                }
+                
                 let file_result = ScanResult {
-                    name: path.file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("Unknown")
-                        .to_string(),
+                    name,
                     path: path.to_string_lossy().to_string(),
                     size: metadata.len(),
+                    category: Some(category),
+                    detected_type,
+                    modified,
+                    accessed,
+                    created: metadata.created().ok(),
+                    system_file: identify_system_file(path),
+                    allocation: allocation_of(path, &metadata),
+                    ..Default::default()
                 };
                 
-                file_heap.push(file_result);
+                if file_result.allocation.is_some() {
+                    totals.sparse_files.push(file_result.clone());
+                    if totals.sparse_files.len() > self.file_limit {
+                        totals.sparse_files.pop();
+                    }
+                }
+                let excluded = self.options.exclude_system_files && file_result.system_file.is_some();
+                
+                let is_stale = self.options.stale_basis
+                    .pick(modified, accessed)
+                    .is_some_and(|time| age_in_days(now, time) >= self.options.stale_after_days);
+                if is_stale && !excluded {
+                    totals.stale_files.push(file_result.clone());
+                    if totals.stale_files.len() > self.file_limit {
+                        totals.stale_files.pop();
+                    }
+                }
+                
+                let ranked = !excluded && self.options.category_filter
+                    .as_ref()
+                    .is_none_or(|filter| Some(filter) == file_result.category.as_ref());
                 
-                // Keep only the largest files for display, but continue scanning
-                if file_heap.len() > self.file_limit {
-                    file_heap.pop();
+                if ranked {
+                    file_heap.push(file_result);
+                    
+                    // Keep only the largest files for display, but continue scanning
+                    if file_heap.len() > self.file_limit {
+                        file_heap.pop();
+                    }
                 }
                 
                 // Send incremental update every 500 files (more frequent for better real-time feel)
                 if total_files_scanned % 500 == 0 {
-                    self.send_incremental_results(&folder_heap, &file_heap);
+                    self.send_incremental_results(&folder_heap, &file_heap, &totals);
                 }
+            } else {
+                // Symlinks and other special entries
+                totals.empty_items.add_opaque(path);
             }
         }
         
         info!("Scan completed: {} files, {} folders processed", total_files_scanned, total_folders_scanned);
         
-        // Send final results
-        self.send_incremental_results(&folder_heap, &file_heap);
+        if self.options.content_detection == ContentDetection::TopFiles {
+            file_heap = self.detect_top_files(file_heap, &mut totals);
+        }
         
         // Convert heaps to sorted vectors
         let mut folders: Vec<ScanResult> = folder_heap.into_vec();
@@ -194,11 +468,177 @@ impl DiskScanner {
         
         info!("Returning top {} folders and {} files", folders.len(), files.len());
         
-        Ok(ScanResults { folders, files })
+        let mut results = ScanResults {
+            folders,
+            files,
+            categories: totals.categories.to_sorted_vec(),
+            age_buckets: totals.ages.clone(),
+            stale_files: sorted_by_size(&totals.stale_files),
+            sparse_files: sorted_by_size(&totals.sparse_files),
+            size_histogram: totals.histogram.clone(),
+            busiest_folders: totals.busiest_folders(),
+            cleanup: totals.cleanup.report(&self.options.cleanup_rules),
+            index: Some(Arc::new(std::mem::take(&mut totals.index).finish())),
+            ..Default::default()
+        };
+        
+        // An interrupted walk has not seen every folder's content, so nothing is reported empty
+        if !*self.should_stop.lock().unwrap() {
+            results.empty_items = totals.empty_items.report(&self.root_path);
+            info!("Found {} empty folders and {} zero-byte files", 
+                results.empty_items.total_folders(), results.empty_items.zero_byte_files.len());
+            results.vm_images = inspect_vm_images(&vm_candidates);
+            for file in &mut results.sparse_files {
+                if let Some(allocation) = &mut file.allocation {
+                    map_holes(Path::new(&file.path), file.size, allocation);
+                }
+            }
+            for file in &mut results.files {
+                if let Some(sparse) = results.sparse_files.iter().find(|sparse| sparse.path == file.path) {
+                    file.allocation = sparse.allocation;
+                }
+            }
+        }
+        
+        // Send final results
+        self.send_results(&results);
+        
+        if self.options.find_duplicates && !*self.should_stop.lock().unwrap() {
+            results.duplicates = find_duplicates(duplicate_candidates, &self.should_stop, |status| {
+                self.send_status(&results, status);
+            });
+            self.send_status(&results, format!("Found {} duplicate sets", results.duplicates.len()));
+        }
+        
+        if self.options.find_similar_images && !*self.should_stop.lock().unwrap() {
+            results.similar_images = find_similar_images(
+                image_candidates,
+                self.options.image_hash,
+                self.options.image_max_distance,
+                &self.should_stop,
+                |status| self.send_status(&results, status),
+            );
+            self.send_status(&results, format!("Found {} groups of similar images", results.similar_images.len()));
+        }
+        
+        if self.options.find_build_artifacts && !*self.should_stop.lock().unwrap() {
+            results.build_projects = find_build_artifacts(&totals.projects, &self.should_stop, |status| {
+                self.send_status(&results, status);
+            });
+            self.send_status(&results, format!("Found {} projects with build artifacts", results.build_projects.len()));
+        }
+        
+        if self.options.analyze_git_repos && !*self.should_stop.lock().unwrap() {
+            results.git_repositories = analyze_repositories(&totals.repositories, &self.should_stop, |status| {
+                self.send_status(&results, status);
+            });
+            self.send_status(&results, format!("Analyzed {} git repositories", results.git_repositories.len()));
+        }
+        
+        if self.options.inspect_archives && !*self.should_stop.lock().unwrap() {
+            results.archives = self.inspect_ranked_archives(&results);
+            self.send_status(&results, format!("Inspected {} archives", results.archives.len()));
+        }
+        
+        if self.options.estimate_compression && !*self.should_stop.lock().unwrap() {
+            self.estimate_compression(&mut results);
+            self.send_status(&results, "Compressibility estimated".to_string());
+        }
+        
+        Ok(results)
+    }
+    
+    fn send_results(&self, results: &ScanResults) {
+        if let Some(sender) = &self.result_sender {
+            let _ = sender.send(results.clone());
+        }
+    }
+    
+    /// Re-send the final lists with a progress message for post-walk stages
+    fn send_status(&self, results: &ScanResults, status: String) {
+        self.send_results(&ScanResults {
+            status: Some(status),
+            ..results.clone()
+        });
+    }
+    
+    /// List the contents of every supported archive in the file ranking
+    fn inspect_ranked_archives(&self, results: &ScanResults) -> Vec<ArchiveListing> {
+        let archives: Vec<&ScanResult> = results.files
+            .iter()
+            .filter(|file| ArchiveFormat::from_name(&file.name).is_some())
+            .collect();
+        
+        let mut listings = Vec::new();
+        for (index, file) in archives.iter().enumerate() {
+            self.send_status(results, format!("Inspecting archives: {} of {} ({})", index + 1, archives.len(), file.name));
+            match list_archive(Path::new(&file.path), &self.should_stop) {
+                Ok(listing) => listings.push(listing),
+                Err(e) => warn!("Failed to list archive {}: {}", file.path, e),
+            }
+        }
+        listings
     }
     
-    fn calculate_folder_size(&self, path: &Path) -> Result<u64, Box<dyn std::error::Error>> {
-        let mut total_size = 0u64;
+    /// Fill in compression estimates for the ranked files and folders
+    fn estimate_compression(&self, results: &mut ScanResults) {
+        let mut estimator = CompressionEstimator::new(&self.options.categories, &self.should_stop);
+        
+        for index in 0..results.files.len() {
+            if *self.should_stop.lock().unwrap() {
+                return;
+            }
+            self.send_status(results, format!("Estimating compressibility: file {} of {}", index + 1, results.files.len()));
+            let file = &results.files[index];
+            results.files[index].compression = estimator.file(&file.path, file.size);
+        }
+        
+        for index in 0..results.folders.len() {
+            if *self.should_stop.lock().unwrap() {
+                return;
+            }
+            self.send_status(results, format!("Estimating compressibility: folder {} of {}", index + 1, results.folders.len()));
+            let folder = &results.folders[index];
+            results.folders[index].compression = estimator.folder(&folder.path, folder.size);
+        }
+        
+        results.compression_by_category = estimator.category_totals();
+    }
+    
+    fn detect_content_type(&self, path: &Path) -> Option<ContentType> {
+        match detect_file_type(path) {
+            Ok(content_type) => content_type,
+            Err(e) => {
+                warn!("Failed to read header of {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+    
+    /// Run content detection over the ranked files only, fixing up category totals
+    fn detect_top_files(&self, file_heap: BinaryHeap<ScanResult>, totals: &mut ScanTotals) -> BinaryHeap<ScanResult> {
+        info!("Detecting content types of top {} files", file_heap.len());
+        let mut files = file_heap.into_vec();
+        
+        for file in &mut files {
+            if let Some(content_type) = self.detect_content_type(Path::new(&file.path)) {
+                if let Some(category) = &file.category {
+                    totals.categories.reassign(category, content_type.category, file.size);
+                }
+                file.category = Some(content_type.category.to_string());
+                file.detected_type = Some(content_type.name.to_string());
+            }
+        }
+        
+        BinaryHeap::from(files)
+    }
+    
+    fn calculate_folder_totals(&self, path: &Path) -> Result<FolderTotals, Box<dyn std::error::Error>> {
+        let mut totals = FolderTotals {
+            size: 0,
+            file_count: 0,
+            direct_file_count: 0,
+        };
         
         for entry in WalkDir::new(path)
             .follow_links(false)
@@ -215,13 +655,17 @@ impl DiskScanner {
             };
             
             if entry.file_type().is_file() {
+                totals.file_count += 1;
+                if entry.depth() == 1 {
+                    totals.direct_file_count += 1;
+                }
                 if let Ok(metadata) = entry.metadata() {
-                    total_size += metadata.len();
+                    totals.size += metadata.len();
                 }
             }
         }
         
-        Ok(total_size)
+        Ok(totals)
     }
     
     fn should_skip_path(&self, path: &Path) -> bool {
@@ -243,11 +687,22 @@ impl DiskScanner {
         skip_patterns.iter().any(|pattern| path_str.contains(pattern))
     }
     
+    /// Shared stop flag, so the UI can cancel a scan running on another thread
+    pub fn stop_handle(&self) -> Arc<Mutex<bool>> {
+        self.should_stop.clone()
+    }
+    
     pub fn stop(&self) {
         *self.should_stop.lock().unwrap() = true;
     }
 }
 
+fn sorted_by_size(heap: &BinaryHeap<ScanResult>) -> Vec<ScanResult> {
+    let mut results: Vec<ScanResult> = heap.iter().cloned().collect();
+    results.sort_by_key(|result| std::cmp::Reverse(result.size));
+    results
+}
+
 impl Drop for DiskScanner {
     fn drop(&mut self) {
         self.stop();
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+
+/// Bytes hashed from each end of a file in the partial-hash stage
+const PARTIAL_CHUNK: u64 = 64 * 1024;
+
+/// Read buffer for the full-hash stage
+const FULL_HASH_BUFFER: usize = 1024 * 1024;
+
+/// Files with identical content
+#[derive(Debug, Clone)]
+pub struct DuplicateSet {
+    /// Size of each copy
+    pub size: u64,
+    pub hash: String,
+    pub paths: Vec<String>,
+}
+
+impl DuplicateSet {
+    /// Bytes freed by keeping a single copy
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.paths.len() as u64).saturating_sub(1)
+    }
+}
+
+/// Find duplicate files among `candidates` (path, size) in three stages:
+/// same size, same hash of head and tail, then same full content hash.
+/// Each stage only reads files that still have a potential twin.
+pub fn find_duplicates<F>(candidates: Vec<(String, u64)>, should_stop: &Arc<Mutex<bool>>, mut progress: F) -> Vec<DuplicateSet>
+where
+    F: FnMut(String),
+{
+    info!("Duplicate search over {} candidate files", candidates.len());
+    
+    // Stage 1: group by size
+    let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+    for (path, size) in candidates {
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+    let size_groups: Vec<(u64, Vec<String>)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    
+    let partial_total: usize = size_groups.iter().map(|(_, paths)| paths.len()).sum();
+    info!("Duplicates stage 1: {} size groups, {} files", size_groups.len(), partial_total);
+    
+    // Stage 2: group by hash of head and tail
+    let mut partial_groups: Vec<(u64, blake3::Hash, Vec<String>)> = Vec::new();
+    let mut hashed = 0usize;
+    for (size, paths) in size_groups {
+        let mut by_partial: HashMap<blake3::Hash, Vec<String>> = HashMap::new();
+        for path in paths {
+            if *should_stop.lock().unwrap() {
+                warn!("Duplicate search stopped by user request");
+                return Vec::new();
+            }
+            
+            hashed += 1;
+            if hashed.is_multiple_of(100) {
+                progress(format!("Finding duplicates: partial hash {}/{} files", hashed, partial_total));
+            }
+            
+            match partial_hash(Path::new(&path), size) {
+                Ok(hash) => by_partial.entry(hash).or_default().push(path),
+                Err(e) => warn!("Failed to read {} for duplicate check: {}", path, e),
+            }
+        }
+        partial_groups.extend(by_partial.into_iter().filter(|(_, paths)| paths.len() > 1).map(|(hash, paths)| (size, hash, paths)));
+    }
+    
+    let full_total: usize = partial_groups.iter().map(|(_, _, paths)| paths.len()).sum();
+    info!("Duplicates stage 2: {} partial-hash groups, {} files", partial_groups.len(), full_total);
+    
+    // Stage 3: group by full content hash
+    let mut sets = Vec::new();
+    let mut hashed = 0usize;
+    for (size, partial, paths) in partial_groups {
+        // Small files were hashed entirely in stage 2
+        if size <= PARTIAL_CHUNK * 2 {
+            hashed += paths.len();
+            sets.push(duplicate_set(size, partial, paths));
+            continue;
+        }
+        
+        let mut by_full: HashMap<blake3::Hash, Vec<String>> = HashMap::new();
+        for path in paths {
+            hashed += 1;
+            progress(format!("Finding duplicates: full hash {}/{} files", hashed, full_total));
+            
+            match full_hash(Path::new(&path), should_stop) {
+                Ok(Some(hash)) => by_full.entry(hash).or_default().push(path),
+                Ok(None) => {
+                    warn!("Duplicate search stopped by user request");
+                    return Vec::new();
+                }
+                Err(e) => warn!("Failed to hash {} for duplicate check: {}", path, e),
+            }
+        }
+        sets.extend(by_full.into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(hash, paths)| duplicate_set(size, hash, paths)));
+    }
+    
+    sets.sort_by_key(|set| std::cmp::Reverse(set.wasted_bytes()));
+    info!("Duplicates stage 3: {} duplicate sets, {} bytes reclaimable",
+        sets.len(), sets.iter().map(|s| s.wasted_bytes()).sum::<u64>());
+    
+    sets
+}
+
+fn duplicate_set(size: u64, hash: blake3::Hash, mut paths: Vec<String>) -> DuplicateSet {
+    paths.sort();
+    DuplicateSet { size, hash: hash.to_hex().to_string(), paths }
+}
+
+/// Hash the first and last `PARTIAL_CHUNK` bytes (the whole file when it is small)
+fn partial_hash(path: &Path, size: u64) -> std::io::Result<blake3::Hash> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; PARTIAL_CHUNK as usize];
+    
+    if size <= PARTIAL_CHUNK * 2 {
+        let mut content = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut content)?;
+        hasher.update(&content);
+    } else {
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+        file.seek(SeekFrom::Start(size - PARTIAL_CHUNK))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+    
+    Ok(hasher.finalize())
+}
+
+/// Hash the whole file, returning `Ok(None)` if the scan was stopped midway
+fn full_hash(path: &Path, should_stop: &Arc<Mutex<bool>>) -> std::io::Result<Option<blake3::Hash>> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; FULL_HASH_BUFFER];
+    
+    loop {
+        if *should_stop.lock().unwrap() {
+            return Ok(None);
+        }
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    
+    Ok(Some(hasher.finalize()))
+}
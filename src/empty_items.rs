@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use walkdir::WalkDir;
+
+use crate::audit::{record_done, record_failed};
+
+/// Folder whose whole subtree contains no files
+#[derive(Debug, Clone)]
+pub struct EmptyFolder {
+    pub path: String,
+    /// Empty subfolders below it that go away with it
+    pub nested_folders: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmptyItemsReport {
+    /// Topmost recursively empty folders, the scan root excluded
+    pub folders: Vec<EmptyFolder>,
+    pub zero_byte_files: Vec<String>,
+}
+
+impl EmptyItemsReport {
+    pub fn total_folders(&self) -> u64 {
+        self.folders.iter().map(|f| 1 + f.nested_folders).sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty() && self.zero_byte_files.is_empty()
+    }
+}
+
+/// Collects directories and the folders that hold something while the scanner walks
+#[derive(Default)]
+pub struct EmptyItemsTracker {
+    folders: Vec<PathBuf>,
+    non_empty: HashSet<PathBuf>,
+    zero_byte_files: Vec<String>,
+}
+
+impl EmptyItemsTracker {
+    pub fn add_folder(&mut self, path: &Path) {
+        self.folders.push(path.to_path_buf());
+    }
+    
+    pub fn add_file(&mut self, path: &Path, size: u64) {
+        if size == 0 {
+            self.zero_byte_files.push(path.to_string_lossy().to_string());
+        }
+        self.mark_parents_non_empty(path);
+    }
+    
+    /// Anything the scan cannot see into (skipped paths, links, unreadable
+    /// entries) keeps its parent folders from being reported as empty
+    pub fn add_opaque(&mut self, path: &Path) {
+        self.mark_parents_non_empty(path);
+    }
+    
+    fn mark_parents_non_empty(&mut self, path: &Path) {
+        let mut current = path.parent();
+        while let Some(parent) = current {
+            if !self.non_empty.insert(parent.to_path_buf()) {
+                break; // ancestors were marked by an earlier entry
+            }
+            current = parent.parent();
+        }
+    }
+    
+    pub fn report(&self, root: &Path) -> EmptyItemsReport {
+        let empty: HashSet<&Path> = self.folders
+            .iter()
+            .map(|folder| folder.as_path())
+            .filter(|folder| *folder != root && !self.non_empty.contains(*folder))
+            .collect();
+        
+        // Attribute every empty folder to its topmost empty ancestor
+        let mut nested: HashMap<&Path, u64> = HashMap::new();
+        for folder in &empty {
+            let mut top: &Path = folder;
+            while let Some(parent) = top.parent().filter(|parent| empty.contains(parent)) {
+                top = parent;
+            }
+            let count = nested.entry(top).or_insert(0);
+            if top != *folder {
+                *count += 1;
+            }
+        }
+        
+        let mut folders: Vec<EmptyFolder> = nested
+            .into_iter()
+            .map(|(path, nested_folders)| EmptyFolder {
+                path: path.to_string_lossy().to_string(),
+                nested_folders,
+            })
+            .collect();
+        folders.sort_by(|a, b| a.path.cmp(&b.path));
+        
+        let mut zero_byte_files = self.zero_byte_files.clone();
+        zero_byte_files.sort();
+        
+        EmptyItemsReport { folders, zero_byte_files }
+    }
+}
+
+/// Delete the listed zero-byte files and empty folders. Folders are removed
+/// bottom-up with `remove_dir`, which refuses anything that gained content
+/// since the scan. Returns the paths removed and the errors hit.
+pub fn remove_empty_items(report: &EmptyItemsReport) -> (Vec<String>, Vec<String>) {
+    let mut removed = Vec::new();
+    let mut errors = Vec::new();
+    
+    for path in &report.zero_byte_files {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() && metadata.len() == 0 => match fs::remove_file(path) {
+                Ok(_) => {
+                    record_done("remove empty", path, Some(0), None, "zero-byte file");
+                    removed.push(path.clone());
+                }
+                Err(e) => {
+                    record_failed("remove empty", path, Some(0), &e.to_string());
+                    errors.push(format!("{}: {}", path, e));
+                }
+            },
+            Ok(_) => errors.push(format!("{}: no longer a zero-byte file", path)),
+            Err(e) => errors.push(format!("{}: {}", path, e)),
+        }
+    }
+    
+    for folder in &report.folders {
+        let mut failed = false;
+        for entry in WalkDir::new(&folder.path).follow_links(false).contents_first(true) {
+            let result = entry
+                .map_err(|e| e.to_string())
+                .and_then(|entry| fs::remove_dir(entry.path()).map_err(|e| format!("{}: {}", entry.path().display(), e)));
+            if let Err(e) = result {
+                record_failed("remove empty", &folder.path, Some(0), &e);
+                errors.push(e);
+                failed = true;
+                break;
+            }
+        }
+        if !failed {
+            record_done("remove empty", &folder.path, Some(0), None, "empty folder");
+            removed.push(folder.path.clone());
+        }
+    }
+    
+    info!("Removed {} empty items, {} errors", removed.len(), errors.len());
+    for error in &errors {
+        warn!("Failed to remove empty item: {}", error);
+    }
+    
+    (removed, errors)
+}
@@ -0,0 +1,117 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Upper bounds in days of each age bucket; the last bucket is open-ended
+pub const AGE_BUCKET_LIMITS: [u64; 3] = [30, 365, 3 * 365];
+pub const AGE_BUCKET_LABELS: [&str; 4] = ["< 30 days", "< 1 year", "< 3 years", "3+ years"];
+
+/// Which timestamp decides whether a file is stale
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StaleBasis {
+    Modified,
+    Accessed,
+    /// The newer of last-modified and last-accessed
+    #[default]
+    LastTouched,
+}
+
+impl StaleBasis {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StaleBasis::Modified => "Last modified",
+            StaleBasis::Accessed => "Last accessed",
+            StaleBasis::LastTouched => "Last touched",
+        }
+    }
+    
+    pub fn pick(&self, modified: Option<SystemTime>, accessed: Option<SystemTime>) -> Option<SystemTime> {
+        match self {
+            StaleBasis::Modified => modified,
+            StaleBasis::Accessed => accessed,
+            StaleBasis::LastTouched => modified.max(accessed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AgeBucket {
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+/// Bytes and file counts per age bucket, by modification and by access time
+#[derive(Debug, Clone, Default)]
+pub struct AgeBuckets {
+    pub modified: [AgeBucket; 4],
+    pub accessed: [AgeBucket; 4],
+}
+
+impl AgeBuckets {
+    pub fn add(&mut self, now: SystemTime, size: u64, modified: Option<SystemTime>, accessed: Option<SystemTime>) {
+        if let Some(days) = modified.map(|time| age_in_days(now, time)) {
+            let bucket = &mut self.modified[bucket_index(days)];
+            bucket.file_count += 1;
+            bucket.total_size += size;
+        }
+        if let Some(days) = accessed.map(|time| age_in_days(now, time)) {
+            let bucket = &mut self.accessed[bucket_index(days)];
+            bucket.file_count += 1;
+            bucket.total_size += size;
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.modified.iter().chain(&self.accessed).all(|bucket| bucket.file_count == 0)
+    }
+}
+
+fn bucket_index(days: u64) -> usize {
+    AGE_BUCKET_LIMITS
+        .iter()
+        .position(|&limit| days < limit)
+        .unwrap_or(AGE_BUCKET_LIMITS.len())
+}
+
+/// Whole days between `time` and `now`, zero for timestamps in the future
+pub fn age_in_days(now: SystemTime, time: SystemTime) -> u64 {
+    now.duration_since(time).unwrap_or(Duration::ZERO).as_secs() / SECONDS_PER_DAY
+}
+
+pub fn format_age(days: u64) -> String {
+    if days < 60 {
+        format!("{} days", days)
+    } else if days < 2 * 365 {
+        format!("{} months", days / 30)
+    } else {
+        format!("{:.1} years", days as f64 / 365.0)
+    }
+}
+
+/// `YYYY-MM-DD` of a timestamp, in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let (year, month, day) = civil_date(time);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Year, month and day of a timestamp, in UTC
+pub fn civil_date(time: SystemTime) -> (i64, i64, i64) {
+    let days = time.duration_since(UNIX_EPOCH).map(|age| age.as_secs() / SECONDS_PER_DAY).unwrap_or(0) as i64;
+    // Civil date from days since 1970-01-01, the inverse of `result_filter::parse_date`
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` of a timestamp, in UTC
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|age| age.as_secs()).unwrap_or(0) % SECONDS_PER_DAY;
+    format!("{}T{:02}:{:02}:{:02}", format_date(time), seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::file_utils::wildcard_match;
+
+/// Name of the category assigned to files no other category matches
+pub const OTHER_CATEGORY: &str = "Other";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCategory {
+    pub name: String,
+    pub icon: String,
+    pub color: [u8; 3],
+    /// Extensions without the leading dot, compound ones like "tar.gz" allowed
+    pub extensions: Vec<String>,
+    /// File name patterns using `*`, `?` and `[0-9]` wildcards, e.g. "*.log.1"
+    pub patterns: Vec<String>,
+}
+
+/// Category entry as written in the user config file.
+/// A name matching a built-in category extends it, any other name adds a new one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryConfig {
+    pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<[u8; 3]>,
+    pub extensions: Vec<String>,
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CategoryStats {
+    pub name: String,
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+pub struct CategoryRegistry {
+    categories: Vec<FileCategory>,
+    // (lowercase extension, category index), longest extension first
+    extensions: Vec<(String, usize)>,
+    // (lowercase pattern, category index), user patterns first
+    patterns: Vec<(String, usize)>,
+    other: FileCategory,
+}
+
+fn category(name: &str, icon: &str, color: [u8; 3], extensions: &[&str], patterns: &[&str]) -> FileCategory {
+    FileCategory {
+        name: name.to_string(),
+        icon: icon.to_string(),
+        color,
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+fn builtin_categories() -> Vec<FileCategory> {
+    vec![
+        category("Text", "📄", [200, 200, 200], &["txt", "md", "log", "csv", "ini", "cfg", "conf"], &["*.log.*", "*.log.gz"]),
+        category("Images", "🖼️", [120, 200, 120], &["jpg", "jpeg", "png", "gif", "bmp", "svg", "webp", "tif", "tiff", "heic", "raw", "cr2", "nef"], &[]),
+        category("Videos", "🎬", [230, 120, 120], &["mp4", "avi", "mkv", "mov", "wmv", "webm", "m4v", "mpg", "mpeg", "flv", "ts"], &[]),
+        category("Audio", "🎵", [200, 140, 230], &["mp3", "wav", "flac", "aac", "ogg", "m4a", "wma", "opus"], &[]),
+        category("Archives", "📦", [230, 190, 100], &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "tar.gz", "tar.bz2", "tar.xz", "tar.zst", "cab"], &["*.7z.[0-9][0-9][0-9]", "*.part[0-9]*.rar"]),
+        category("Disk images", "💿", [150, 170, 230], &["iso", "img", "vhd", "vhdx", "vmdk", "qcow2", "vdi"], &[]),
+        category("Executables", "⚙️", [170, 170, 170], &["exe", "msi", "app", "dll", "sys", "so", "bin"], &[]),
+        category("PDF", "📕", [220, 90, 90], &["pdf"], &[]),
+        category("Word", "📘", [90, 140, 230], &["doc", "docx", "odt", "rtf"], &[]),
+        category("Spreadsheets", "📗", [90, 190, 120], &["xls", "xlsx", "ods"], &[]),
+        category("Presentations", "📙", [230, 150, 80], &["ppt", "pptx", "odp"], &[]),
+        category("Web", "🌐", [100, 200, 220], &["html", "htm", "css", "js", "json", "xml"], &[]),
+        category("Databases", "🗄", [180, 150, 110], &["db", "sqlite", "sqlite3", "mdf", "ldf", "accdb"], &[]),
+    ]
+}
+
+impl CategoryRegistry {
+    pub fn builtin() -> Self {
+        Self::with_user_categories(&[])
+    }
+    
+    pub fn with_user_categories(user_categories: &[CategoryConfig]) -> Self {
+        let mut categories = builtin_categories();
+        let builtin_count = categories.len();
+        
+        for config in user_categories {
+            if config.name.trim().is_empty() {
+                warn!("Ignoring user category without a name");
+                continue;
+            }
+            
+            match categories.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&config.name)) {
+                Some(existing) => {
+                    info!("Extending category '{}' from user config", existing.name);
+                    if let Some(icon) = &config.icon {
+                        existing.icon = icon.clone();
+                    }
+                    if let Some(color) = config.color {
+                        existing.color = color;
+                    }
+                    existing.extensions.extend(config.extensions.iter().cloned());
+                    existing.patterns.extend(config.patterns.iter().cloned());
+                }
+                None => {
+                    info!("Adding category '{}' from user config", config.name);
+                    categories.push(FileCategory {
+                        name: config.name.clone(),
+                        icon: config.icon.clone().unwrap_or_else(|| "📄".to_string()),
+                        color: config.color.unwrap_or([200, 200, 200]),
+                        extensions: config.extensions.clone(),
+                        patterns: config.patterns.clone(),
+                    });
+                }
+            }
+        }
+        
+        // User-added categories win ties over built-in ones
+        let order: Vec<usize> = (builtin_count..categories.len()).chain(0..builtin_count).collect();
+        
+        let mut extensions = Vec::new();
+        let mut patterns = Vec::new();
+        for &index in &order {
+            for ext in &categories[index].extensions {
+                extensions.push((ext.trim_start_matches('.').to_lowercase(), index));
+            }
+            for pattern in &categories[index].patterns {
+                patterns.push((pattern.to_lowercase(), index));
+            }
+        }
+        // Stable sort keeps the user-first order for extensions of equal length
+        extensions.sort_by_key(|(ext, _)| std::cmp::Reverse(ext.len()));
+        
+        info!("Category registry ready: {} categories, {} extensions, {} patterns",
+            categories.len(), extensions.len(), patterns.len());
+        
+        Self {
+            categories,
+            extensions,
+            patterns,
+            other: category(OTHER_CATEGORY, "❓", [140, 140, 140], &[], &[]),
+        }
+    }
+    
+    /// Categorize a file by its name or path. Patterns are checked before
+    /// extensions, and the longest matching extension wins (".tar.gz" over ".gz").
+    pub fn categorize(&self, file_name: &str) -> &FileCategory {
+        let name = file_name
+            .rsplit(['\\', '/'])
+            .next()
+            .unwrap_or(file_name)
+            .to_lowercase();
+        
+        if let Some((_, index)) = self.patterns.iter().find(|(pattern, _)| wildcard_match(pattern, &name)) {
+            return &self.categories[*index];
+        }
+        
+        for (ext, index) in &self.extensions {
+            if name.len() > ext.len() + 1
+                && name.ends_with(ext.as_str())
+                && name.as_bytes()[name.len() - ext.len() - 1] == b'.'
+            {
+                return &self.categories[*index];
+            }
+        }
+        
+        &self.other
+    }
+    
+    pub fn get(&self, name: &str) -> &FileCategory {
+        self.categories
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap_or(&self.other)
+    }
+    
+    /// All categories in display order, including the fallback category
+    pub fn all(&self) -> impl Iterator<Item = &FileCategory> {
+        self.categories.iter().chain(std::iter::once(&self.other))
+    }
+}
+
+impl Default for CategoryRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Per-category totals collected while scanning
+#[derive(Debug, Default)]
+pub struct CategoryTotals {
+    totals: HashMap<String, CategoryStats>,
+}
+
+impl CategoryTotals {
+    pub fn add(&mut self, category: &str, size: u64) {
+        let stats = self.totals.entry(category.to_string()).or_insert_with(|| CategoryStats {
+            name: category.to_string(),
+            ..Default::default()
+        });
+        stats.file_count += 1;
+        stats.total_size += size;
+    }
+    
+    /// Move a file counted under one category to another, e.g. after content detection
+    pub fn reassign(&mut self, from: &str, to: &str, size: u64) {
+        if from == to {
+            return;
+        }
+        if let Some(stats) = self.totals.get_mut(from) {
+            stats.file_count = stats.file_count.saturating_sub(1);
+            stats.total_size = stats.total_size.saturating_sub(size);
+            if stats.file_count == 0 {
+                self.totals.remove(from);
+            }
+        }
+        self.add(to, size);
+    }
+    
+    /// Snapshot of the totals, largest category first
+    pub fn to_sorted_vec(&self) -> Vec<CategoryStats> {
+        let mut stats: Vec<CategoryStats> = self.totals.values().cloned().collect();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.total_size));
+        stats
+    }
+}
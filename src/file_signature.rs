@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Number of bytes read from the start of a file for signature matching
+const HEADER_LEN: usize = 4096;
+
+// ISO 9660 volume descriptor identifier lives past the 32 KB system area
+const ISO_SIGNATURE_OFFSET: u64 = 0x8001;
+
+/// File type identified from the file's content rather than its name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentType {
+    pub name: &'static str,
+    /// Built-in category the type belongs to (see `file_category`)
+    pub category: &'static str,
+}
+
+/// When to read file headers during a scan
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ContentDetection {
+    #[default]
+    Off,
+    /// Only the files that end up in the fat-files ranking
+    TopFiles,
+    /// Every file at least this many bytes long
+    LargerThan(u64),
+}
+
+struct Signature {
+    offset: usize,
+    magic: &'static [u8],
+    content_type: ContentType,
+}
+
+const fn sig(offset: usize, magic: &'static [u8], name: &'static str, category: &'static str) -> Signature {
+    Signature { offset, magic, content_type: ContentType { name, category } }
+}
+
+// Ordered so that more specific signatures come before generic ones
+const SIGNATURES: &[Signature] = &[
+    sig(0, b"vhdxfile", "VHDX virtual disk", "Disk images"),
+    sig(0, b"conectix", "VHD virtual disk", "Disk images"),
+    sig(0, b"QFI\xfb", "QCOW2 virtual disk", "Disk images"),
+    sig(0, b"KDMV", "VMDK virtual disk", "Disk images"),
+    sig(0, b"# Disk DescriptorFile", "VMDK descriptor", "Disk images"),
+    sig(64, b"\x7f\x10\xda\xbe", "VDI virtual disk", "Disk images"),
+    sig(0, b"SQLite format 3\0", "SQLite database", "Databases"),
+    sig(0, b"\x7fELF", "ELF executable", "Executables"),
+    sig(0, b"MZ", "Windows executable", "Executables"),
+    sig(0, b"%PDF-", "PDF document", "PDF"),
+    sig(0, b"\x89PNG\r\n\x1a\n", "PNG image", "Images"),
+    sig(0, b"\xff\xd8\xff", "JPEG image", "Images"),
+    sig(0, b"GIF87a", "GIF image", "Images"),
+    sig(0, b"GIF89a", "GIF image", "Images"),
+    sig(0, b"BM", "BMP image", "Images"),
+    sig(4, b"ftypqt", "QuickTime video", "Videos"),
+    sig(4, b"ftypheic", "HEIC image", "Images"),
+    sig(4, b"ftypM4A", "M4A audio", "Audio"),
+    sig(4, b"ftyp", "MP4 video", "Videos"),
+    sig(0, b"\x1a\x45\xdf\xa3", "Matroska/WebM video", "Videos"),
+    sig(8, b"AVI ", "AVI video", "Videos"),
+    sig(8, b"WAVE", "WAV audio", "Audio"),
+    sig(8, b"WEBP", "WebP image", "Images"),
+    sig(0, b"ID3", "MP3 audio", "Audio"),
+    sig(0, b"fLaC", "FLAC audio", "Audio"),
+    sig(0, b"OggS", "Ogg media", "Audio"),
+    sig(0, b"PK\x03\x04", "ZIP archive", "Archives"),
+    sig(0, b"PK\x05\x06", "ZIP archive", "Archives"),
+    sig(0, b"7z\xbc\xaf\x27\x1c", "7-Zip archive", "Archives"),
+    sig(0, b"Rar!\x1a\x07", "RAR archive", "Archives"),
+    sig(0, b"\x1f\x8b", "GZIP archive", "Archives"),
+    sig(0, b"\x28\xb5\x2f\xfd", "Zstandard archive", "Archives"),
+    sig(0, b"\xfd7zXZ\0", "XZ archive", "Archives"),
+    sig(0, b"BZh", "BZIP2 archive", "Archives"),
+    sig(0, b"MSCF", "Cabinet archive", "Archives"),
+    sig(257, b"ustar", "TAR archive", "Archives"),
+];
+
+/// Identify a file type from the first bytes of its content
+pub fn detect_from_header(header: &[u8]) -> Option<ContentType> {
+    let signature = SIGNATURES.iter().find(|s| {
+        header.len() >= s.offset + s.magic.len()
+            && &header[s.offset..s.offset + s.magic.len()] == s.magic
+            && confirmed(s.content_type.name, header)
+    })?;
+    
+    // ZIP is also the container for Office documents, tell them apart by their first entries
+    if signature.content_type.name == "ZIP archive" {
+        let refined = if contains(header, b"word/") {
+            Some(ContentType { name: "Word document (OOXML)", category: "Word" })
+        } else if contains(header, b"xl/") {
+            Some(ContentType { name: "Excel workbook (OOXML)", category: "Spreadsheets" })
+        } else if contains(header, b"ppt/") {
+            Some(ContentType { name: "PowerPoint presentation (OOXML)", category: "Presentations" })
+        } else {
+            None
+        };
+        if refined.is_some() {
+            return refined;
+        }
+    }
+    
+    Some(signature.content_type)
+}
+
+/// Two-byte magics occur in plenty of text and data files, so their headers must check out too
+fn confirmed(name: &str, header: &[u8]) -> bool {
+    let u32_at = |offset: usize| header.get(offset..offset + 4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    match name {
+        // The DOS stub points at the PE header through e_lfanew
+        "Windows executable" => u32_at(0x3c)
+            .and_then(|pe_offset| header.get(pe_offset as usize..pe_offset as usize + 4))
+            .is_some_and(|magic| magic == b"PE\0\0"),
+        // Reserved fields are zero and the DIB header has one of its known sizes
+        "BMP image" => u32_at(6) == Some(0)
+            && u32_at(14).is_some_and(|dib_size| [12, 40, 52, 56, 64, 108, 124].contains(&dib_size))
+            && u32_at(10).zip(u32_at(14)).is_some_and(|(data_offset, dib_size)| data_offset >= 14 + dib_size),
+        _ => true,
+    }
+}
+
+/// Read the head of a file and identify its type. Returns `Ok(None)` when
+/// no known signature matches.
+pub fn detect_file_type(path: &Path) -> std::io::Result<Option<ContentType>> {
+    let mut file = File::open(path)?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    (&mut file).take(HEADER_LEN as u64).read_to_end(&mut header)?;
+    
+    if let Some(content_type) = detect_from_header(&header) {
+        return Ok(Some(content_type));
+    }
+    
+    let mut iso_magic = [0u8; 5];
+    if file.seek(SeekFrom::Start(ISO_SIGNATURE_OFFSET)).is_ok()
+        && file.read_exact(&mut iso_magic).is_ok()
+        && &iso_magic == b"CD001"
+    {
+        return Ok(Some(ContentType { name: "ISO 9660 image", category: "Disk images" }));
+    }
+    
+    Ok(None)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
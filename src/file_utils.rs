@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use log::{info, warn};
 use winapi::um::fileapi::GetLogicalDrives;
@@ -66,29 +66,6 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
-pub fn get_file_icon(file_path: &str) -> &'static str {
-    let extension = PathBuf::from(file_path)
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_lowercase())
-        .unwrap_or_default();
-    
-    match extension.as_str() {
-        "txt" | "md" | "log" => "📄",
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" => "🖼️",
-        "mp4" | "avi" | "mkv" | "mov" | "wmv" => "🎬",
-        "mp3" | "wav" | "flac" | "aac" | "ogg" => "🎵",
-        "zip" | "rar" | "7z" | "tar" | "gz" => "📦",
-        "exe" | "msi" | "app" => "⚙️",
-        "pdf" => "📕",
-        "doc" | "docx" => "📘",
-        "xls" | "xlsx" => "📗",
-        "ppt" | "pptx" => "📙",
-        "html" | "htm" | "css" | "js" | "json" | "xml" => "🌐",
-        _ => "📄",
-    }
-}
-
 pub fn open_in_explorer(path: &str) -> Result<(), Box<dyn std::error::Error>> {
     info!("Opening Explorer for path: {}", path);
     
@@ -126,5 +103,179 @@ pub fn open_in_explorer(path: &str) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Bytes a file occupies on disk. Less than its length for sparse, compressed
+/// and dynamically allocated files; `None` when the size cannot be queried.
+#[cfg(windows)]
+pub fn allocated_size(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::errhandlingapi::{GetLastError, SetLastError};
+    use winapi::um::fileapi::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+    
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut high: u32 = 0;
+    unsafe {
+        // Success leaves the last error alone, so clear any stale code first
+        SetLastError(0);
+        let low = GetCompressedFileSizeW(wide.as_ptr(), &mut high);
+        // INVALID_FILE_SIZE is also a valid low word, only an error code makes it a failure
+        if low == INVALID_FILE_SIZE && GetLastError() != 0 {
+            warn!("Failed to query allocated size of {}", path.display());
+            return None;
+        }
+        Some(((high as u64) << 32) | low as u64)
+    }
+}
+
+/// Bytes a file occupies on disk. Less than its length for sparse, compressed
+/// and dynamically allocated files; `None` when the size cannot be queried.
+#[cfg(unix)]
+pub fn allocated_size(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    
+    // st_blocks is always in 512-byte units, whatever the filesystem block size
+    std::fs::metadata(path).ok().map(|metadata| metadata.blocks() * 512)
+}
+
+/// Top folder of the volume holding `path`, e.g. `D:\` or a mount point
+#[cfg(windows)]
+pub fn volume_root(path: &Path) -> PathBuf {
+    path.ancestors().last().unwrap_or(path).to_path_buf()
+}
+
+/// Top folder of the volume holding `path`, e.g. `D:\` or a mount point
+#[cfg(unix)]
+pub fn volume_root(path: &Path) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+    
+    // A destination may not exist yet, its nearest existing folder is on the same volume
+    let Some((mut top, device)) = path.ancestors()
+        .find_map(|dir| std::fs::symlink_metadata(dir).ok().map(|metadata| (dir, metadata.dev()))) else {
+        return path.ancestors().last().unwrap_or(path).to_path_buf();
+    };
+    while let Some(parent) = top.parent() {
+        match std::fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => top = parent,
+            _ => break,
+        }
+    }
+    top.to_path_buf()
+}
+
+/// Whether two existing paths are on the same volume, so a rename moves without copying
+#[cfg(windows)]
+pub fn same_volume(a: &Path, b: &Path) -> bool {
+    volume_root(a).to_string_lossy().to_lowercase() == volume_root(b).to_string_lossy().to_lowercase()
+}
+
+/// Whether two existing paths are on the same volume, so a rename moves without copying
+#[cfg(unix)]
+pub fn same_volume(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+/// Bytes available to the current user on the volume holding `path`
+#[cfg(windows)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::winnt::ULARGE_INTEGER;
+    use winapi::um::fileapi::GetDiskFreeSpaceExW;
+    
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    unsafe {
+        let mut available: ULARGE_INTEGER = std::mem::zeroed();
+        if GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) == 0 {
+            warn!("Failed to query free space of {}", path.display());
+            return None;
+        }
+        Some(*available.QuadPart())
+    }
+}
 
+/// Bytes available to the current user on the volume holding `path`
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stats) != 0 {
+            warn!("Failed to query free space of {}", path.display());
+            return None;
+        }
+        Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+}
 
+/// Bytes of a file, or of every file below a folder
+pub fn total_size(path: &Path) -> std::io::Result<u64> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    Ok(walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum())
+}
+
+/// BLAKE3 of a file's content, or of every file below a folder together with its
+/// relative path, so a moved or restored item can be checked against the original.
+pub fn content_hash(path: &Path) -> std::io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        for entry in walkdir::WalkDir::new(path).follow_links(false).sort_by_file_name() {
+            let entry = entry.map_err(std::io::Error::other)?;
+            let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+            hasher.update(relative.to_string_lossy().as_bytes());
+            hasher.update(&[0]);
+            if entry.file_type().is_file() {
+                hasher.update_reader(std::fs::File::open(entry.path())?)?;
+            }
+        }
+    } else {
+        hasher.update_reader(std::fs::File::open(path)?)?;
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters,
+/// `?` matches one character and `[0-9]` matches one character in the range.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    wildcard_match_from(&pattern, &text)
+}
+
+fn wildcard_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|skip| wildcard_match_from(&pattern[1..], &text[skip..])),
+        Some('?') => !text.is_empty() && wildcard_match_from(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(close) = pattern.iter().position(|&c| c == ']') else {
+                return text.first() == Some(&'[') && wildcard_match_from(&pattern[1..], &text[1..]);
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let class = &pattern[1..close];
+            let matched = if class.len() == 3 && class[1] == '-' {
+                class[0] <= c && c <= class[2]
+            } else {
+                class.contains(&c)
+            };
+            matched && wildcard_match_from(&pattern[close + 1..], &text[1..])
+        }
+        Some(&p) => text.first() == Some(&p) && wildcard_match_from(&pattern[1..], &text[1..]),
+    }
+}
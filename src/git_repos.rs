@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// git's own `gc.auto` and `gc.autoPackLimit` defaults
+const GC_LOOSE_OBJECT_LIMIT: u64 = 6700;
+const GC_PACK_LIMIT: u64 = 50;
+/// Rough share of loose object bytes saved by packing them with deltas
+const LOOSE_PACKING_SAVINGS: f64 = 0.5;
+/// Rough share of pack bytes saved by consolidating many packs into one
+const REPACK_SAVINGS: f64 = 0.1;
+/// Unreferenced LFS bytes below this are not worth a prune recommendation
+const LFS_PRUNE_MIN_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjectStats {
+    pub count: u64,
+    pub size: u64,
+}
+
+impl ObjectStats {
+    fn add(&mut self, size: u64) {
+        self.count += 1;
+        self.size += size;
+    }
+}
+
+/// Breakdown of one repository's `.git` folder
+#[derive(Debug, Clone, Default)]
+pub struct GitRepository {
+    /// Working tree root, the folder holding `.git`
+    pub root: String,
+    pub git_size: u64,
+    /// Packfiles with their indexes, bitmaps and reverse indexes
+    pub packs: ObjectStats,
+    pub loose_objects: ObjectStats,
+    pub lfs_objects: ObjectStats,
+    /// LFS objects whose content is not in the current checkout
+    pub lfs_unreferenced: ObjectStats,
+    /// Leftover `tmp_pack_*` / `tmp_idx_*` files from interrupted fetches or repacks
+    pub garbage: ObjectStats,
+    /// Linked worktree folders registered in `.git/worktrees`
+    pub worktrees: Vec<String>,
+    pub worktree_metadata_size: u64,
+}
+
+impl GitRepository {
+    /// Index, hooks, logs, submodule repositories and everything else
+    pub fn other_size(&self) -> u64 {
+        self.git_size.saturating_sub(self.packs.size + self.loose_objects.size + self.lfs_objects.size
+            + self.garbage.size + self.worktree_metadata_size)
+    }
+    
+    pub fn needs_gc(&self) -> bool {
+        self.loose_objects.count >= GC_LOOSE_OBJECT_LIMIT || self.packs.count >= GC_PACK_LIMIT || self.garbage.count > 0
+    }
+    
+    /// Estimated bytes `git gc` would free; a heuristic, git's delta compression varies widely
+    pub fn gc_savings(&self) -> u64 {
+        if !self.needs_gc() {
+            return 0;
+        }
+        let mut savings = self.garbage.size + (self.loose_objects.size as f64 * LOOSE_PACKING_SAVINGS) as u64;
+        if self.packs.count >= GC_PACK_LIMIT {
+            savings += (self.packs.size as f64 * REPACK_SAVINGS) as u64;
+        }
+        savings
+    }
+    
+    pub fn needs_lfs_prune(&self) -> bool {
+        self.lfs_unreferenced.size >= LFS_PRUNE_MIN_BYTES
+    }
+    
+    /// Upper bound of what `git lfs prune` frees: it also keeps objects of recent commits
+    pub fn lfs_prune_savings(&self) -> u64 {
+        if self.needs_lfs_prune() { self.lfs_unreferenced.size } else { 0 }
+    }
+}
+
+/// Collects `.git` folders while the scanner walks
+#[derive(Default)]
+pub struct RepositoryTracker {
+    git_dirs: Vec<PathBuf>,
+}
+
+impl RepositoryTracker {
+    pub fn add_folder(&mut self, path: &Path) {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            self.git_dirs.push(path.to_path_buf());
+        }
+    }
+}
+
+/// Break down every tracked repository, largest `.git` first. Reads the
+/// on-disk layout only; nothing runs git or touches the network.
+pub fn analyze_repositories<F>(tracker: &RepositoryTracker, should_stop: &Arc<Mutex<bool>>, mut progress: F) -> Vec<GitRepository>
+where
+    F: FnMut(String),
+{
+    info!("Analyzing {} git repositories", tracker.git_dirs.len());
+    
+    let mut repositories = Vec::new();
+    for (index, git_dir) in tracker.git_dirs.iter().enumerate() {
+        if *should_stop.lock().unwrap() {
+            warn!("Git repository analysis stopped by user request");
+            return Vec::new();
+        }
+        progress(format!("Analyzing git repositories: {} of {}", index + 1, tracker.git_dirs.len()));
+        repositories.push(analyze_repository(git_dir, should_stop));
+    }
+    
+    repositories.sort_by_key(|repository| std::cmp::Reverse(repository.git_size));
+    info!("Analyzed {} git repositories", repositories.len());
+    repositories
+}
+
+fn analyze_repository(git_dir: &Path, should_stop: &Arc<Mutex<bool>>) -> GitRepository {
+    let root = git_dir.parent().unwrap_or(git_dir);
+    let mut repository = GitRepository {
+        root: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    
+    // LFS objects live at lfs/objects/ab/cd/<sha256 oid>
+    let mut lfs_objects: HashMap<String, u64> = HashMap::new();
+    
+    for entry in WalkDir::new(git_dir).follow_links(false).into_iter().filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        let size = metadata.len();
+        repository.git_size += size;
+        
+        let Ok(relative) = entry.path().strip_prefix(git_dir) else { continue };
+        let parts: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_lowercase()).collect();
+        let file_name = parts.last().map(String::as_str).unwrap_or_default();
+        
+        match parts.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            ["objects", "pack", _] if file_name.starts_with("tmp_") => repository.garbage.add(size),
+            ["objects", "pack", _] if file_name.ends_with(".pack") => repository.packs.add(size),
+            ["objects", "pack", _] => repository.packs.size += size,
+            ["objects", dir, _] if dir.len() == 2 && dir.chars().all(|c| c.is_ascii_hexdigit()) => repository.loose_objects.add(size),
+            ["lfs", "objects", _, _, oid] => {
+                repository.lfs_objects.add(size);
+                lfs_objects.insert(oid.to_string(), size);
+            }
+            ["worktrees", ..] => repository.worktree_metadata_size += size,
+            _ => {}
+        }
+    }
+    
+    repository.worktrees = linked_worktrees(git_dir);
+    
+    if !lfs_objects.is_empty() {
+        // A stopped walk has not seen every file, so nothing is reported unreferenced
+        if let Some(referenced) = checked_out_lfs_objects(root, &lfs_objects, should_stop) {
+            for (oid, &size) in &lfs_objects {
+                if !referenced.contains(oid) {
+                    repository.lfs_unreferenced.add(size);
+                }
+            }
+        }
+    }
+    
+    info!("Git repository {}: {} bytes in .git, {} packs, {} loose objects, {} LFS objects ({} unreferenced)",
+        repository.root, repository.git_size, repository.packs.count, repository.loose_objects.count,
+        repository.lfs_objects.count, repository.lfs_unreferenced.count);
+    repository
+}
+
+/// Worktree roots registered in `.git/worktrees/<name>/gitdir`
+fn linked_worktrees(git_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(git_dir.join("worktrees")) else { return Vec::new() };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_to_string(entry.path().join("gitdir")).ok())
+        .map(|gitdir| {
+            // gitdir points at the worktree's `.git` file
+            let gitdir = PathBuf::from(gitdir.trim());
+            gitdir.parent().unwrap_or(&gitdir).to_string_lossy().to_string()
+        })
+        .collect()
+}
+
+/// OIDs of LFS objects whose content is checked out in the working tree, `None` when stopped.
+/// Only files with the size of some LFS object are hashed.
+fn checked_out_lfs_objects(root: &Path, lfs_objects: &HashMap<String, u64>, should_stop: &Arc<Mutex<bool>>) -> Option<HashSet<String>> {
+    let sizes: HashSet<u64> = lfs_objects.values().copied().collect();
+    let mut referenced = HashSet::new();
+    
+    let walker = WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git");
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if *should_stop.lock().unwrap() {
+            return None;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !sizes.contains(&metadata.len()) {
+            continue;
+        }
+        match sha256_hex(entry.path()) {
+            Ok(oid) if lfs_objects.contains_key(&oid) => {
+                referenced.insert(oid);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to hash {}: {}", entry.path().display(), e),
+        }
+    }
+    
+    Some(referenced)
+}
+
+fn sha256_hex(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
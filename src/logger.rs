@@ -77,8 +77,9 @@ pub fn cleanup_logs() {
     if Path::new(log_file_path).exists() {
         match std::fs::remove_file(log_file_path) {
             Ok(_) => {
-                // Use println! instead of log::info! since we're shutting down
-                println!("Log file cleaned up successfully");
+                // Use eprintln! instead of log::info! since we're shutting down,
+                // and keep stdout clean for headless query output
+                eprintln!("Log file cleaned up successfully");
             }
             Err(e) => {
                 eprintln!("Warning: Could not delete log file: {}", e);
@@ -5,13 +5,91 @@ use std::time::Duration;
 use std::sync::mpsc;
 use log::{info, warn, error};
 
+mod archives;
+mod audit;
+mod build_artifacts;
+mod cleanup_plan;
+mod cleanup_rules;
+mod cli;
+mod compress;
+mod compressibility;
+mod config;
 mod disk_scanner;
+mod duplicates;
+mod empty_items;
+mod file_age;
+mod file_category;
+mod file_signature;
 mod file_utils;
+mod git_repos;
 mod logger;
+mod protected_paths;
+mod quarantine;
+mod relocate;
+mod query;
+mod result_filter;
+mod similar_images;
+mod scan_index;
+mod size_histogram;
+mod snapshot;
+mod sparse_files;
+mod system_files;
+mod trash;
+mod vm_images;
 
-use disk_scanner::{DiskScanner, ScanResult, ScanResults};
-use file_utils::{get_available_disks, format_size, get_file_icon, open_in_explorer};
+use archives::{list_archive, ArchiveFormat, ArchiveListing};
+use audit::{export_csv, export_json, audit_file, load_audit_log, AuditEntry, AuditOutcome};
+use build_artifacts::{clean_project, BuildProject};
+use cleanup_plan::{execute_plan, CleanupPlan, ItemOutcome, PlanAction, PlanItem, PlanMessage, PlanReport, VolumeProjection};
+use cleanup_rules::{CleanupRuleReport, CleanupRules};
+use compress::{compress_in_place, CompressFormat, CompressMessage, Compressed};
+use compressibility::Compressibility;
+use config::load_config;
+use disk_scanner::{DiskScanner, ScanOptions, ScanResult, ScanResults};
+use duplicates::DuplicateSet;
+use empty_items::{remove_empty_items, EmptyItemsReport};
+use file_age::{age_in_days, format_age, format_date, format_timestamp, AgeBucket, AgeBuckets, StaleBasis, AGE_BUCKET_LABELS};
+use file_category::{CategoryRegistry, CategoryStats};
+use file_signature::ContentDetection;
+use file_utils::{free_space, get_available_disks, format_size, open_in_explorer, same_volume};
+use git_repos::GitRepository;
 use logger::{init_logging, cleanup_logs};
+use protected_paths::ProtectedPaths;
+use relocate::{relocate, RelocateMessage, Relocated};
+use quarantine::{load_areas, purge, purge_expired, quarantine_items, restore, QuarantineArea, QuarantineEntry};
+use query::{parse_query, run_query, QueryError, QueryOutput, QueryRows};
+use result_filter::{apply_filter, FilterBar, FilteredResults};
+use scan_index::ScanIndex;
+use similar_images::{HashAlgorithm, SimilarImageGroup};
+use size_histogram::SizeHistogram;
+use snapshot::{load_snapshot, save_snapshot};
+use system_files::identify_system_file;
+use trash::{move_to_trash, TRASH_NAME};
+use vm_images::VmImage;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum ResultView {
+    #[default]
+    Overview,
+    Categories,
+    Duplicates,
+    SimilarImages,
+    EmptyItems,
+    Stale,
+    Sparse,
+    SizeHistogram,
+    Cleanup,
+    BuildArtifacts,
+    GitRepos,
+    VmImages,
+    Archives,
+    Query,
+    Quarantine,
+    Plan,
+    Audit,
+}
 
 #[derive(Default)]
 pub struct FatFolderDiscoveryApp {
@@ -23,18 +101,105 @@ pub struct FatFolderDiscoveryApp {
     // Scanning configuration
     file_limit: usize,
     folder_limit: usize,
+    categories: Arc<CategoryRegistry>,
+    protected_paths: Arc<ProtectedPaths>,
+    protection: ProtectionCache,
+    category_filter: Option<String>,
+    exclude_system_files: bool,
+    content_detection: ContentDetection,
+    detection_threshold_mb: u64,
+    find_duplicates: bool,
+    duplicate_min_size_mb: u64,
+    find_similar_images: bool,
+    image_hash: HashAlgorithm,
+    image_max_distance: u32,
+    stale_after_days: u64,
+    stale_basis: StaleBasis,
+    cleanup_rules: Arc<CleanupRules>,
+    find_build_artifacts: bool,
+    analyze_git_repos: bool,
+    inspect_archives: bool,
+    estimate_compression: bool,
     
     // Results
     fat_folders: Vec<ScanResult>,
     fat_files: Vec<ScanResult>,
+    category_stats: Vec<CategoryStats>,
+    duplicates: Vec<DuplicateSet>,
+    similar_images: Vec<SimilarImageGroup>,
+    empty_items: EmptyItemsReport,
+    age_buckets: AgeBuckets,
+    stale_files: Vec<ScanResult>,
+    sparse_files: Vec<ScanResult>,
+    size_histogram: SizeHistogram,
+    busiest_folders: Vec<ScanResult>,
+    cleanup: Vec<CleanupRuleReport>,
+    build_projects: Vec<BuildProject>,
+    git_repositories: Vec<GitRepository>,
+    vm_images: Vec<VmImage>,
+    archives: Vec<ArchiveListing>,
+    compression_by_category: HashMap<String, Compressibility>,
+    scan_index: Option<Arc<ScanIndex>>,
+    filtered: Option<FilteredResults>,
     
     // UI state
+    /// Paths picked for a bulk action, with their size when picked
+    selected_paths: BTreeMap<String, u64>,
+    confirm_trash: bool,
+    confirm_quarantine: bool,
+    quarantine_areas: Vec<QuarantineArea>,
+    quarantine_retention_days: u64,
+    quarantine_receiver: Option<mpsc::Receiver<(Vec<QuarantineEntry>, Vec<String>)>>,
+    /// Quarantine folder and entry id awaiting confirmation of a permanent delete
+    confirm_purge: Option<(PathBuf, String)>,
+    /// Folder that "Move to..." relocates the selection into
+    move_target: String,
+    move_leave_link: bool,
+    confirm_move: bool,
+    move_receiver: Option<mpsc::Receiver<RelocateMessage>>,
+    move_stop_flag: Option<Arc<Mutex<bool>>>,
+    move_progress: Option<(u64, u64, String)>,
+    confirm_compress: bool,
+    compress_format: CompressFormat,
+    compress_delete_original: bool,
+    compress_receiver: Option<mpsc::Receiver<CompressMessage>>,
+    compress_stop_flag: Option<Arc<Mutex<bool>>>,
+    compress_progress: Option<(u64, u64, String)>,
+    cleanup_plan: CleanupPlan,
+    /// Recomputed when the plan changes, since it queries every item's volume
+    plan_projection: Option<Vec<VolumeProjection>>,
+    plan_path: String,
+    plan_receiver: Option<mpsc::Receiver<PlanMessage>>,
+    plan_stop_flag: Option<Arc<Mutex<bool>>>,
+    plan_progress: Option<(usize, usize, String)>,
+    plan_report: Option<PlanReport>,
+    confirm_execute_plan: bool,
+    /// Read from the audit file when the Audit view opens, newest first
+    audit_entries: Option<Vec<AuditEntry>>,
+    audit_export_path: String,
+    current_view: ResultView,
     is_scanning: bool,
     scan_progress: f32,
     scan_status: String,
+    thumbnail_textures: HashMap<String, egui::TextureHandle>,
+    confirm_remove_empty: bool,
+    histogram_by_bytes: bool,
+    folders_by_file_count: bool,
+    confirm_clean_project: Option<String>,
+    selected_archive: Option<usize>,
+    archive_folder: Vec<usize>,
+    archive_open_path: String,
+    archive_receiver: Option<mpsc::Receiver<Result<ArchiveListing, String>>>,
+    filter_bar: FilterBar,
+    filter_error: Option<String>,
+    query_text: String,
+    query_output: Option<QueryOutput>,
+    query_error: Option<QueryError>,
+    snapshot_path: String,
     
     // Scanner
     scanner: Option<Arc<Mutex<DiskScanner>>>,
+    scan_stop_flag: Option<Arc<Mutex<bool>>>,
     
     // Channel communication
     scan_receiver: Option<mpsc::Receiver<ScanResults>>,
@@ -43,12 +208,53 @@ pub struct FatFolderDiscoveryApp {
     
 }
 
+/// Paths refused by the protected-path policy, with the message explaining why
+type BlockedPaths = Vec<(String, String)>;
+
+/// Protection of the rows on screen. Resolving a path touches the disk, so each one is looked
+/// up once per result set or plan change rather than on every frame.
+#[derive(Default, Clone)]
+struct ProtectionCache {
+    protected: Arc<ProtectedPaths>,
+    reasons: RefCell<HashMap<String, Option<String>>>,
+}
+
+impl ProtectionCache {
+    fn new(protected: Arc<ProtectedPaths>) -> Self {
+        Self { protected, reasons: RefCell::default() }
+    }
+    
+    fn reason(&self, path: &str) -> Option<String> {
+        self.reasons
+            .borrow_mut()
+            .entry(path.to_string())
+            .or_insert_with(|| self.protected.reason(std::path::Path::new(path)))
+            .clone()
+    }
+    
+    fn clear(&self) {
+        self.reasons.borrow_mut().clear();
+    }
+}
+
 impl FatFolderDiscoveryApp {
     fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         info!("Initializing FatFolderDiscoveryApp");
         let mut app = Self::default();
         app.file_limit = 10;
         app.folder_limit = 10;
+        app.detection_threshold_mb = 100;
+        app.duplicate_min_size_mb = 1;
+        app.image_max_distance = 10;
+        app.stale_after_days = 365;
+        
+        let config = load_config();
+        app.categories = Arc::new(CategoryRegistry::with_user_categories(&config.categories));
+        app.cleanup_rules = Arc::new(CleanupRules::with_user_rules(&config.cleanup_rules));
+        app.protected_paths = Arc::new(ProtectedPaths::from_config(&config));
+        app.protection = ProtectionCache::new(app.protected_paths.clone());
+        app.quarantine_retention_days = config.quarantine.retention_days;
+        app.purge_expired_quarantine();
         
         info!("Refreshing available disks");
         // Initialize available disks
@@ -111,6 +317,30 @@ impl FatFolderDiscoveryApp {
         self.scan_status = "Initializing scan...".to_string();
         self.fat_folders.clear();
         self.fat_files.clear();
+        self.category_stats.clear();
+        self.duplicates.clear();
+        self.similar_images.clear();
+        self.empty_items = EmptyItemsReport::default();
+        self.age_buckets = AgeBuckets::default();
+        self.stale_files.clear();
+        self.sparse_files.clear();
+        self.size_histogram = SizeHistogram::default();
+        self.protection.clear();
+        self.busiest_folders.clear();
+        self.cleanup.clear();
+        self.build_projects.clear();
+        self.git_repositories.clear();
+        self.vm_images.clear();
+        self.archives.clear();
+        self.compression_by_category.clear();
+        self.scan_index = None;
+        self.filtered = None;
+        self.filter_error = None;
+        self.query_output = None;
+        self.selected_paths.clear();
+        self.selected_archive = None;
+        self.archive_folder.clear();
+        self.thumbnail_textures.clear();
         
         // Create channels for communication
         let (result_sender, result_receiver) = mpsc::channel();
@@ -124,18 +354,39 @@ impl FatFolderDiscoveryApp {
         // Start scanning in background thread
         let file_limit = self.file_limit;
         let folder_limit = self.folder_limit;
+        let options = ScanOptions {
+            categories: self.categories.clone(),
+            category_filter: self.category_filter.clone(),
+            content_detection: self.content_detection,
+            find_duplicates: self.find_duplicates,
+            duplicate_min_size: self.duplicate_min_size_mb * 1024 * 1024,
+            find_similar_images: self.find_similar_images,
+            image_hash: self.image_hash,
+            image_max_distance: self.image_max_distance,
+            stale_after_days: self.stale_after_days,
+            stale_basis: self.stale_basis,
+            cleanup_rules: self.cleanup_rules.clone(),
+            find_build_artifacts: self.find_build_artifacts,
+            analyze_git_repos: self.analyze_git_repos,
+            exclude_system_files: self.exclude_system_files,
+            inspect_archives: self.inspect_archives,
+            estimate_compression: self.estimate_compression,
+        };
+        
+        let mut scanner = DiskScanner::new_with_sender(
+            PathBuf::from(scan_path),
+            file_limit,
+            folder_limit,
+            options,
+            result_sender,
+        );
+        self.scan_stop_flag = Some(scanner.stop_handle());
         
         self.scan_status = "Scanning files and folders...".to_string();
         info!("Spawning background scan thread");
         
         std::thread::spawn(move || {
             info!("Background scan thread started");
-            let mut scanner = DiskScanner::new_with_sender(
-                PathBuf::from(scan_path),
-                file_limit,
-                folder_limit,
-                result_sender,
-            );
             
             match scanner.scan() {
                 Ok(results) => {
@@ -156,6 +407,9 @@ impl FatFolderDiscoveryApp {
     
     fn stop_scan(&mut self) {
         info!("Stopping scan process");
+        if let Some(stop_flag) = self.scan_stop_flag.take() {
+            *stop_flag.lock().unwrap() = true;
+        }
         self.is_scanning = false;
         self.scan_status = "Scan stopped".to_string();
         self.scanner = None;
@@ -176,11 +430,34 @@ impl FatFolderDiscoveryApp {
                 info!("Before update: {} folders, {} files in UI", self.fat_folders.len(), self.fat_files.len());
                 self.fat_folders = results.folders;
                 self.fat_files = results.files;
+                self.category_stats = results.categories;
+                self.duplicates = results.duplicates;
+                self.similar_images = results.similar_images;
+                self.empty_items = results.empty_items;
+                self.age_buckets = results.age_buckets;
+                self.stale_files = results.stale_files;
+                self.sparse_files = results.sparse_files;
+                self.size_histogram = results.size_histogram;
+                self.busiest_folders = results.busiest_folders;
+                self.cleanup = results.cleanup;
+                self.build_projects = results.build_projects;
+                self.git_repositories = results.git_repositories;
+                self.vm_images = results.vm_images;
+                self.compression_by_category = results.compression_by_category;
+                if !results.archives.is_empty() {
+                    self.archives = results.archives;
+                }
+                if results.index.is_some() {
+                    self.scan_index = results.index;
+                }
                 info!("After update: {} folders, {} files in UI", self.fat_folders.len(), self.fat_files.len());
                 
                 // Update status with current counts and show real-time progress
-                self.scan_status = format!("Scanning... Found {} folders, {} files", 
-                    self.fat_folders.len(), self.fat_files.len());
+                self.scan_status = match results.status {
+                    Some(status) => status,
+                    None => format!("Scanning... Found {} folders, {} files", 
+                        self.fat_folders.len(), self.fat_files.len()),
+                };
                 
                 info!("Updated UI with {} folders and {} files", 
                     self.fat_folders.len(), self.fat_files.len());
@@ -201,6 +478,7 @@ impl FatFolderDiscoveryApp {
                     self.fat_folders.len(), self.fat_files.len());
                 self.scan_progress = 1.0;
                 self.is_scanning = false;
+                self.scan_stop_flag = None;
                 self.scan_receiver = None;
                 self.scan_sender = None;
                 self.scan_completion_receiver = None;
@@ -209,260 +487,2925 @@ impl FatFolderDiscoveryApp {
             }
         }
     }
-}
-
-impl Clone for FatFolderDiscoveryApp {
-    fn clone(&self) -> Self {
-        Self {
-            selected_disk: self.selected_disk.clone(),
-            custom_path: self.custom_path.clone(),
-            available_disks: self.available_disks.clone(),
-            file_limit: self.file_limit,
-            folder_limit: self.folder_limit,
-            fat_folders: self.fat_folders.clone(),
-            fat_files: self.fat_files.clone(),
-            is_scanning: self.is_scanning,
-            scan_progress: self.scan_progress,
-            scan_status: self.scan_status.clone(),
-            scanner: None, // Don't clone scanner
-            scan_receiver: None, // Don't clone channels
-            scan_sender: None, // Don't clone channels
-            scan_completion_receiver: None, // Don't clone channels
-        }
-    }
-}
-
-impl Drop for FatFolderDiscoveryApp {
-    fn drop(&mut self) {
-        info!("Application shutting down, cleaning up logs...");
-        cleanup_logs();
-    }
-}
-
-impl eframe::App for FatFolderDiscoveryApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for scan results
-        self.check_scan_results();
+    
+    fn show_overview(&mut self, ui: &mut egui::Ui) {
+        self.show_filter_bar(ui);
+        self.show_selection_bar(ui);
         
-        // Top panel with controls
-        egui::TopBottomPanel::top("controls").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                // Disk selector
-                ui.label("Disk:");
-                let old_disk = self.selected_disk.clone();
-                egui::ComboBox::from_id_salt("disk_selector")
-                    .selected_text(&self.selected_disk)
-                    .show_ui(ui, |ui| {
-                        for disk in &self.available_disks {
-                            ui.selectable_value(&mut self.selected_disk, disk.clone(), disk);
-                        }
-                    });
-                
-                // Provide feedback when disk changes
-                if old_disk != self.selected_disk && !self.selected_disk.is_empty() {
-                    self.scan_status = format!("Selected disk: {}", self.selected_disk);
-                }
-                
-                ui.separator();
-                
-                // Path input
-                ui.label("Path:");
-                let old_path = self.custom_path.clone();
-                ui.text_edit_singleline(&mut self.custom_path);
-                
-                // Provide feedback when path changes
-                if old_path != self.custom_path {
-                    if self.custom_path.is_empty() {
-                        self.scan_status = format!("Path cleared - will scan root of {}", self.selected_disk);
-                    } else {
-                        self.scan_status = format!("Custom path: {}", self.custom_path);
-                    }
-                }
-                
-                // Auto-select disk from path
-                if !self.custom_path.is_empty() {
-                    if let Some(disk_letter) = self.custom_path.chars().next() {
-                        let disk_str = format!("{}:", disk_letter.to_uppercase());
-                        if self.available_disks.contains(&disk_str) {
-                            self.selected_disk = disk_str;
-                        }
-                    }
-                }
-                
-                // Scan button
-                let scan_button_text = if self.is_scanning { "Stop" } else { "Scan" };
-                if ui.button(scan_button_text).clicked() {
-                    if self.is_scanning {
-                        self.stop_scan();
-                    } else {
-                        self.start_scan();
+        let mut browse_archive = None;
+        let mut marked = Vec::new();
+        ui.horizontal(|ui| {
+            // Left column - Fat Folders (Fixed: 395px width, 470px height)
+            ui.push_id("folders_column", |ui| {
+                ui.allocate_ui_with_layout(
+                    egui::Vec2::new(395.0, 470.0),
+                    egui::Layout::top_down(egui::Align::LEFT),
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.folders_by_file_count, false, egui::RichText::new("📁 Fat Folders").heading());
+                            ui.selectable_value(&mut self.folders_by_file_count, true, egui::RichText::new("🗃 Most files").heading());
+                        });
+                        ui.separator();
+                        
+                        let folders = match &self.filtered {
+                            _ if self.folders_by_file_count => &self.busiest_folders,
+                            Some(filtered) => &filtered.folders,
+                            None => &self.fat_folders,
+                        };
+                        
+                        // Fixed scroll area height: 470 - 40 (heading + separator) = 430px
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .max_height(430.0)
+                            .show(ui, |ui| {
+                                if folders.is_empty() && self.is_scanning {
+                                    ui.label("Scanning folders...");
+                                }
+                                for folder in folders {
+                                    ui.horizontal(|ui| {
+                                        Self::selection_checkbox(ui, &self.protection, &mut self.selected_paths, &folder.path, folder.size);
+                                        ui.label("📁");
+                                        let savings = folder.compression.as_ref().map(Compressibility::savings);
+                                        marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, &folder.path, folder.size, savings, "Fat folders"));
+                                        if self.folders_by_file_count {
+                                            ui.label(format!("[{} files]", folder.file_count.unwrap_or(0)));
+                                        } else {
+                                            ui.label(format!("[{}]", format_size(folder.size)));
+                                        }
+                                        ui.vertical(|ui| {
+                                            ui.label(&folder.name);
+                                            if self.folders_by_file_count {
+                                                ui.label(egui::RichText::new(format!("{} directly inside, {}", 
+                                                    folder.direct_file_count.unwrap_or(0), format_size(folder.size))).size(10.0));
+                                            }
+                                            ui.label(egui::RichText::new(&folder.path).size(10.0).weak());
+                                            if let Some(estimate) = &folder.compression {
+                                                ui.label(Self::savings_text(estimate));
+                                            }
+                                        });
+                                    });
+                                    
+                                    // Make the entire row clickable
+                                    if ui.add(egui::Button::new("").fill(egui::Color32::TRANSPARENT)).clicked() {
+                                        info!("Clicked on folder: {}", folder.path);
+                                        if let Err(e) = open_in_explorer(&folder.path) {
+                                            error!("Failed to open Explorer for folder {}: {}", folder.path, e);
+                                            self.scan_status = format!("Error opening Explorer: {}", e);
+                                        }
+                                    }
+                                    ui.separator();
+                                }
+                            });
                     }
-                }
-                
-                // Refresh disks button
-                if ui.button("🔄").clicked() {
-                    self.scan_status = "Refreshing disk list...".to_string();
-                    self.refresh_disks();
-                }
+                );
             });
             
-            // Configuration row
-            ui.horizontal(|ui| {
-                ui.label("File limit:");
-                let old_file_limit = self.file_limit;
-                ui.add(egui::Slider::new(&mut self.file_limit, 5..=20));
-                
-                ui.label("Folder limit:");
-                let old_folder_limit = self.folder_limit;
-                ui.add(egui::Slider::new(&mut self.folder_limit, 5..=20));
-                
-                // Provide feedback when limits change
-                if old_file_limit != self.file_limit || old_folder_limit != self.folder_limit {
-                    self.scan_status = format!("Limits updated: {} files, {} folders", 
-                        self.file_limit, self.folder_limit);
-                }
-                
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.label(&self.scan_status);
-                    if self.is_scanning {
-                        // Animate progress bar
-                        self.scan_progress = (self.scan_progress + 0.01) % 1.0;
-                        ui.add(egui::ProgressBar::new(self.scan_progress));
+            ui.separator();
+            
+            // Right column - Fat Files (Fixed: 395px width, 470px height)
+            ui.push_id("files_column", |ui| {
+                ui.allocate_ui_with_layout(
+                    egui::Vec2::new(395.0, 470.0),
+                    egui::Layout::top_down(egui::Align::LEFT),
+                    |ui| {
+                        ui.heading("📄 Fat Files");
+                        ui.separator();
+                        
+                        let files = self.filtered.as_ref().map_or(&self.fat_files, |filtered| &filtered.files);
+                        
+                        // Fixed scroll area height: 470 - 40 (heading + separator) = 430px
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .max_height(430.0)
+                            .show(ui, |ui| {
+                                if files.is_empty() && self.is_scanning {
+                                    ui.label("Scanning files...");
+                                }
+                                for file in files {
+                                    let category = match &file.category {
+                                        Some(name) => self.categories.get(name),
+                                        None => self.categories.categorize(&file.name),
+                                    };
+                                    let [r, g, b] = category.color;
+                                    ui.horizontal(|ui| {
+                                        Self::selection_checkbox(ui, &self.protection, &mut self.selected_paths, &file.path, file.size);
+                                        ui.label(egui::RichText::new(&category.icon).color(egui::Color32::from_rgb(r, g, b)))
+                                            .on_hover_text(&category.name);
+                                        let savings = file.compression.as_ref().map(Compressibility::savings);
+                                        marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, &file.path, file.size, savings, "Fat files"));
+                                        ui.label(format!("[{}]", format_size(file.size)));
+                                        ui.vertical(|ui| {
+                                            ui.horizontal(|ui| {
+                                                ui.label(&file.name);
+                                                if ArchiveFormat::from_name(&file.name).is_some() && ui.small_button("📦 Browse").clicked() {
+                                                    browse_archive = Some(file.path.clone());
+                                                }
+                                            });
+                                            if let Some(detected) = &file.detected_type {
+                                                ui.label(egui::RichText::new(format!("🔍 {}", detected)).size(10.0));
+                                            }
+                                            if let Some(kind) = file.system_file {
+                                                ui.label(egui::RichText::new(format!("🔒 {} (managed by the OS)", kind.label()))
+                                                    .size(10.0)
+                                                    .color(egui::Color32::LIGHT_YELLOW))
+                                                    .on_hover_text(format!("{}\n\nSize is controlled by: {}", kind.explanation(), kind.setting()));
+                                            }
+                                            if let Some(allocation) = &file.allocation {
+                                                ui.label(egui::RichText::new(format!("🕸 {}, {} real", 
+                                                    allocation.kind.label(), format_size(allocation.allocated_size)))
+                                                    .size(10.0)
+                                                    .color(egui::Color32::LIGHT_BLUE));
+                                            }
+                                            ui.label(egui::RichText::new(&file.path).size(10.0).weak());
+                                            if let Some(estimate) = &file.compression {
+                                                ui.label(Self::savings_text(estimate));
+                                            }
+                                        });
+                                    });
+                                    
+                                    // Make the entire row clickable
+                                    if ui.add(egui::Button::new("").fill(egui::Color32::TRANSPARENT)).clicked() {
+                                        info!("Clicked on file: {}", file.path);
+                                        if let Err(e) = open_in_explorer(&file.path) {
+                                            error!("Failed to open Explorer for file {}: {}", file.path, e);
+                                            self.scan_status = format!("Error opening Explorer: {}", e);
+                                        }
+                                    }
+                                    ui.separator();
+                                }
+                            });
                     }
-                });
+                );
             });
         });
         
-        // Main content area - Fixed size: 800x500 (600 - 60 top - 40 bottom)
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                // Left column - Fat Folders (Fixed: 395px width, 500px height)
-                ui.push_id("folders_column", |ui| {
-                    ui.allocate_ui_with_layout(
-                        egui::Vec2::new(395.0, 500.0),
-                        egui::Layout::top_down(egui::Align::LEFT),
-                        |ui| {
-                            ui.heading("📁 Fat Folders");
-                            ui.separator();
+        if let Some(path) = browse_archive {
+            self.open_archive(path);
+        }
+        self.mark_in_plan(marked);
+    }
+    
+    fn show_categories(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🗂 Categories");
+        ui.separator();
+        
+        if self.category_stats.is_empty() {
+            ui.label(if self.is_scanning { "Scanning files..." } else { "Run a scan to see the category breakdown" });
+            return;
+        }
+        
+        let total_size: u64 = self.category_stats.iter().map(|s| s.total_size).sum();
+        
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::Grid::new("category_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for stats in &self.category_stats {
+                            let category = self.categories.get(&stats.name);
+                            let [r, g, b] = category.color;
+                            let color = egui::Color32::from_rgb(r, g, b);
+                            let share = if total_size > 0 { stats.total_size as f32 / total_size as f32 } else { 0.0 };
                             
-                            // Fixed scroll area height: 500 - 40 (heading + separator) = 460px
-                            egui::ScrollArea::vertical()
-                                .auto_shrink([false; 2])
-                                .max_height(460.0)
-                                .show(ui, |ui| {
-                                    if self.fat_folders.is_empty() && self.is_scanning {
-                                        ui.label("Scanning folders...");
-                                    }
-                                    for folder in &self.fat_folders {
-                                        ui.horizontal(|ui| {
-                                            ui.label("📁");
-                                            ui.label(format!("[{}]", format_size(folder.size)));
-                                            ui.vertical(|ui| {
-                                                ui.label(&folder.name);
-                                                ui.label(egui::RichText::new(&folder.path).size(10.0).weak());
-                                            });
-                                        });
-                                        
-                                        // Make the entire row clickable
-                                        if ui.add(egui::Button::new("").fill(egui::Color32::TRANSPARENT)).clicked() {
-                                            info!("Clicked on folder: {}", folder.path);
-                                            if let Err(e) = open_in_explorer(&folder.path) {
-                                                error!("Failed to open Explorer for folder {}: {}", folder.path, e);
-                                                self.scan_status = format!("Error opening Explorer: {}", e);
-                                            }
+                            ui.label(egui::RichText::new(format!("{} {}", category.icon, category.name)).color(color));
+                            ui.label(format_size(stats.total_size));
+                            ui.label(format!("{} files", stats.file_count));
+                            ui.add(egui::ProgressBar::new(share)
+                                .fill(color)
+                                .desired_width(250.0)
+                                .text(format!("{:.1}%", share * 100.0)));
+                            match self.compression_by_category.get(&stats.name) {
+                                Some(estimate) => ui.label(Self::savings_text(&estimate.scaled_to(stats.total_size)))
+                                    .on_hover_text(format!("Estimated from {} of sampled files", format_size(estimate.total_bytes))),
+                                None => ui.label(""),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+    
+    /// "Potential savings" column text for a compression estimate
+    fn savings_text(estimate: &Compressibility) -> egui::RichText {
+        if estimate.is_already_compressed() {
+            egui::RichText::new("🗜 already compressed").size(10.0).weak()
+        } else {
+            egui::RichText::new(format!("🗜 ~{} savable ({:.0}% of size)", 
+                format_size(estimate.savings()), (1.0 - estimate.ratio()) * 100.0))
+                .size(10.0)
+                .color(egui::Color32::LIGHT_GREEN)
+        }
+    }
+    
+    fn show_duplicates(&mut self, ui: &mut egui::Ui) {
+        ui.heading("👥 Duplicates");
+        ui.separator();
+        
+        if self.duplicates.is_empty() {
+            ui.label(if !self.find_duplicates {
+                "Enable \"Find duplicates\" and run a scan"
+            } else if self.is_scanning {
+                "Searching for duplicates..."
+            } else {
+                "No duplicate files found"
+            });
+            return;
+        }
+        
+        let wasted: u64 = self.duplicates.iter().map(|d| d.wasted_bytes()).sum();
+        ui.label(format!("{} duplicate sets, {} reclaimable", self.duplicates.len(), format_size(wasted)));
+        ui.separator();
+        
+        let mut marked = Vec::new();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for (index, set) in self.duplicates.iter().enumerate() {
+                    egui::CollapsingHeader::new(format!("[{} wasted] {} copies of {}", 
+                        format_size(set.wasted_bytes()), set.paths.len(), format_size(set.size)))
+                        .id_salt(("duplicate_set", index))
+                        .show(ui, |ui| {
+                            for path in &set.paths {
+                                ui.horizontal(|ui| {
+                                    marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, path, set.size, None, "Duplicates"));
+                                    if ui.link(egui::RichText::new(path).size(11.0)).clicked() {
+                                        info!("Clicked on duplicate: {}", path);
+                                        if let Err(e) = open_in_explorer(path) {
+                                            error!("Failed to open Explorer for file {}: {}", path, e);
+                                            self.scan_status = format!("Error opening Explorer: {}", e);
                                         }
-                                        ui.separator();
                                     }
                                 });
-                        }
-                    );
-                });
-                
-                ui.separator();
-                
-                // Right column - Fat Files (Fixed: 395px width, 500px height)
-                ui.push_id("files_column", |ui| {
-                    ui.allocate_ui_with_layout(
-                        egui::Vec2::new(395.0, 500.0),
-                        egui::Layout::top_down(egui::Align::LEFT),
-                        |ui| {
-                            ui.heading("📄 Fat Files");
-                            ui.separator();
-                            
-                            // Fixed scroll area height: 500 - 40 (heading + separator) = 460px
-                            egui::ScrollArea::vertical()
-                                .auto_shrink([false; 2])
-                                .max_height(460.0)
-                                .show(ui, |ui| {
-                                    if self.fat_files.is_empty() && self.is_scanning {
-                                        ui.label("Scanning files...");
-                                    }
-                                    for file in &self.fat_files {
-                                        ui.horizontal(|ui| {
-                                            ui.label(get_file_icon(&file.path));
-                                            ui.label(format!("[{}]", format_size(file.size)));
-                                            ui.vertical(|ui| {
-                                                ui.label(&file.name);
-                                                ui.label(egui::RichText::new(&file.path).size(10.0).weak());
-                                            });
-                                        });
-                                        
-                                        // Make the entire row clickable
-                                        if ui.add(egui::Button::new("").fill(egui::Color32::TRANSPARENT)).clicked() {
-                                            info!("Clicked on file: {}", file.path);
-                                            if let Err(e) = open_in_explorer(&file.path) {
-                                                error!("Failed to open Explorer for file {}: {}", file.path, e);
+                            }
+                        })
+                        .header_response
+                        .on_hover_text(format!("BLAKE3 {}", set.hash));
+                }
+            });
+        self.mark_in_plan(marked);
+    }
+    
+    fn show_similar_images(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🖼 Similar images");
+        ui.separator();
+        
+        if self.similar_images.is_empty() {
+            ui.label(if !self.find_similar_images {
+                "Enable \"Similar images\" and run a scan"
+            } else if self.is_scanning {
+                "Searching for similar images..."
+            } else {
+                "No similar images found"
+            });
+            return;
+        }
+        
+        let reclaimable: u64 = self.similar_images.iter().map(|g| g.reclaimable_bytes()).sum();
+        ui.label(format!("{} groups, {} reclaimable by keeping the best copy of each", 
+            self.similar_images.len(), format_size(reclaimable)));
+        ui.separator();
+        
+        let ctx = ui.ctx().clone();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for (index, group) in self.similar_images.iter().enumerate() {
+                    egui::CollapsingHeader::new(format!("[{}] {} similar images", 
+                        format_size(group.total_size()), group.images.len()))
+                        .id_salt(("similar_group", index))
+                        .default_open(index < 5)
+                        .show(ui, |ui| {
+                            for (position, image) in group.images.iter().enumerate() {
+                                let texture = self.thumbnail_textures
+                                    .entry(image.path.clone())
+                                    .or_insert_with(|| ctx.load_texture(
+                                        image.path.clone(),
+                                        egui::ColorImage::from_rgba_unmultiplied(
+                                            [image.thumbnail.width as usize, image.thumbnail.height as usize],
+                                            &image.thumbnail.rgba,
+                                        ),
+                                        egui::TextureOptions::LINEAR,
+                                    ));
+                                
+                                ui.horizontal(|ui| {
+                                    ui.image((texture.id(), texture.size_vec2()));
+                                    ui.vertical(|ui| {
+                                        if position == 0 {
+                                            ui.label(egui::RichText::new("⭐ Best copy").color(egui::Color32::GOLD));
+                                        }
+                                        ui.label(format!("{} × {} px, {}", image.width, image.height, format_size(image.size)));
+                                        if ui.link(egui::RichText::new(&image.path).size(10.0)).clicked() {
+                                            info!("Clicked on similar image: {}", image.path);
+                                            if let Err(e) = open_in_explorer(&image.path) {
+                                                error!("Failed to open Explorer for file {}: {}", image.path, e);
                                                 self.scan_status = format!("Error opening Explorer: {}", e);
                                             }
                                         }
-                                        ui.separator();
-                                    }
+                                    });
                                 });
-                        }
-                    );
-                });
+                            }
+                        });
+                }
             });
-        });
+    }
+    
+    fn show_empty_items(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🕳 Empty folders and zero-byte files");
+        ui.separator();
         
-        // Status bar panel
-        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                // Status information
-                ui.label("Status:");
-                ui.label(egui::RichText::new(&self.scan_status).color(egui::Color32::LIGHT_BLUE));
-                
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    // Show current configuration
-                    ui.label(format!("Limits: {} files, {} folders", self.file_limit, self.folder_limit));
-                    ui.separator();
-                    ui.label(format!("Disk: {}", self.selected_disk));
-                });
-            });
+        if self.empty_items.is_empty() {
+            ui.label(if self.is_scanning { "Available when the scan completes" } else { "No empty folders or zero-byte files found" });
+            return;
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label(format!("{} empty folders ({} topmost), {} zero-byte files", 
+                self.empty_items.total_folders(), self.empty_items.folders.len(), self.empty_items.zero_byte_files.len()));
+            if ui.add_enabled(!self.is_scanning, egui::Button::new("🗑 Remove all")).clicked() {
+                self.confirm_remove_empty = true;
+            }
         });
+        ui.separator();
         
-        // Bottom panel with close button
-        egui::TopBottomPanel::bottom("bottom").show(ctx, |ui| {
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if ui.button("Close").clicked() {
-                    self.stop_scan();
-                    info!("User requested application close, cleaning up...");
-                    cleanup_logs();
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                }
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::CollapsingHeader::new(format!("📁 Empty folders ({})", self.empty_items.folders.len()))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for folder in &self.empty_items.folders {
+                            let label = if folder.nested_folders > 0 {
+                                format!("{} (+{} nested)", folder.path, folder.nested_folders)
+                            } else {
+                                folder.path.clone()
+                            };
+                            if ui.link(egui::RichText::new(label).size(11.0)).clicked() {
+                                clicked_path = Some(folder.path.clone());
+                            }
+                        }
+                    });
+                egui::CollapsingHeader::new(format!("📄 Zero-byte files ({})", self.empty_items.zero_byte_files.len()))
+                    .show(ui, |ui| {
+                        for path in &self.empty_items.zero_byte_files {
+                            if ui.link(egui::RichText::new(path).size(11.0)).clicked() {
+                                clicked_path = Some(path.clone());
+                            }
+                        }
+                    });
             });
-        });
         
-        // Request repaint for progress updates
-        if self.is_scanning {
-            // More frequent repaints during scanning for real-time updates
-            ctx.request_repaint_after(Duration::from_millis(16)); // ~60 FPS
+        if let Some(path) = clicked_path {
+            info!("Clicked on empty item: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
         }
         
-        // Always request repaint to ensure UI updates are visible
-        ctx.request_repaint_after(Duration::from_millis(100));
-    }
-}
-
+        if self.confirm_remove_empty {
+            egui::Window::new("Remove empty items")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Permanently delete {} empty folders and {} zero-byte files?", 
+                        self.empty_items.total_folders(), self.empty_items.zero_byte_files.len()));
+                    ui.horizontal(|ui| {
+                        if ui.button("Remove").clicked() {
+                            self.confirm_remove_empty = false;
+                            self.remove_empty_items();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_remove_empty = false;
+                        }
+                    });
+                });
+        }
+    }
+    
+    fn remove_empty_items(&mut self) {
+        info!("Removing {} empty folders and {} zero-byte files", 
+            self.empty_items.folders.len(), self.empty_items.zero_byte_files.len());
+        let protected = &self.protected_paths;
+        let mut errors = Vec::new();
+        let mut allowed = self.empty_items.clone();
+        allowed.folders.retain(|folder| protected.guard("delete", std::path::Path::new(&folder.path)).map_err(|e| errors.push(e)).is_ok());
+        allowed.zero_byte_files.retain(|path| protected.guard("delete", std::path::Path::new(path)).map_err(|e| errors.push(e)).is_ok());
+        let (removed, removal_errors) = remove_empty_items(&allowed);
+        errors.extend(removal_errors);
+        let removed: std::collections::HashSet<String> = removed.into_iter().collect();
+        
+        self.empty_items.folders.retain(|folder| !removed.contains(&folder.path));
+        self.empty_items.zero_byte_files.retain(|path| !removed.contains(path));
+        
+        self.scan_status = if errors.is_empty() {
+            format!("Removed {} empty items", removed.len())
+        } else {
+            format!("Removed {} empty items, {} failed (see log)", removed.len(), errors.len())
+        };
+    }
+    
+    fn show_stale_files(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("⏳ Stale files");
+            ui.separator();
+            
+            let old_settings = (self.stale_basis, self.stale_after_days);
+            egui::ComboBox::from_id_salt("stale_basis")
+                .selected_text(self.stale_basis.label())
+                .show_ui(ui, |ui| {
+                    for basis in [StaleBasis::LastTouched, StaleBasis::Modified, StaleBasis::Accessed] {
+                        ui.selectable_value(&mut self.stale_basis, basis, basis.label());
+                    }
+                });
+            ui.label("older than");
+            ui.add(egui::DragValue::new(&mut self.stale_after_days).range(1..=36500).suffix(" days"));
+            
+            if old_settings != (self.stale_basis, self.stale_after_days) {
+                self.scan_status = "Stale file threshold updated (applies to next scan)".to_string();
+            }
+        });
+        ui.separator();
+        
+        if self.age_buckets.is_empty() {
+            ui.label(if self.is_scanning { "Scanning files..." } else { "Run a scan to see file ages" });
+            return;
+        }
+        
+        let show_buckets = |ui: &mut egui::Ui, id: &str, buckets: &[AgeBucket; 4]| {
+            let total: u64 = buckets.iter().map(|b| b.total_size).sum();
+            egui::Grid::new(id).num_columns(4).striped(true).show(ui, |ui| {
+                for (label, bucket) in AGE_BUCKET_LABELS.iter().zip(buckets) {
+                    let share = if total > 0 { bucket.total_size as f32 / total as f32 } else { 0.0 };
+                    ui.label(*label);
+                    ui.label(format_size(bucket.total_size));
+                    ui.label(format!("{} files", bucket.file_count));
+                    ui.add(egui::ProgressBar::new(share).desired_width(200.0).text(format!("{:.1}%", share * 100.0)));
+                    ui.end_row();
+                }
+            });
+        };
+        
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("By last modified").strong());
+                show_buckets(ui, "modified_buckets", &self.age_buckets.modified);
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("By last accessed").strong());
+                show_buckets(ui, "accessed_buckets", &self.age_buckets.accessed);
+            });
+        });
+        ui.separator();
+        
+        ui.label(egui::RichText::new(format!("Biggest files not touched for {}+ days ({})", 
+            self.stale_after_days, self.stale_basis.label().to_lowercase())).strong());
+        
+        let now = std::time::SystemTime::now();
+        let mut clicked_path = None;
+        let mut marked = Vec::new();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                if self.stale_files.is_empty() {
+                    ui.label("No stale files found");
+                }
+                for file in &self.stale_files {
+                    let age = self.stale_basis
+                        .pick(file.modified, file.accessed)
+                        .map(|time| format_age(age_in_days(now, time)))
+                        .unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        let savings = file.compression.as_ref().map(Compressibility::savings);
+                        marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, &file.path, file.size, savings, "Stale files"));
+                        ui.label(format!("[{}]", format_size(file.size)));
+                        ui.label(egui::RichText::new(age).color(egui::Color32::LIGHT_RED));
+                        let describe = |time: Option<std::time::SystemTime>| time
+                            .map(|time| format!("{} ago", format_age(age_in_days(now, time))))
+                            .unwrap_or_else(|| "unknown".to_string());
+                        let link = ui.link(&file.path).on_hover_text(format!("Modified {}\nAccessed {}\nCreated {}", 
+                            describe(file.modified), describe(file.accessed), describe(file.created)));
+                        if link.clicked() {
+                            clicked_path = Some(file.path.clone());
+                        }
+                    });
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on stale file: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for file {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        self.mark_in_plan(marked);
+    }
+    
+    fn show_size_histogram(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("📶 File size distribution");
+            ui.separator();
+            ui.selectable_value(&mut self.histogram_by_bytes, false, "File count");
+            ui.selectable_value(&mut self.histogram_by_bytes, true, "Total bytes");
+        });
+        ui.separator();
+        
+        if self.size_histogram.is_empty() {
+            ui.label(if self.is_scanning { "Scanning files..." } else { "Run a scan to see the size distribution" });
+            return;
+        }
+        
+        // Summarize where the bytes are: many small files versus a few giant ones
+        let buckets = &self.size_histogram.buckets;
+        let total_files: u64 = buckets.iter().map(|b| b.file_count).sum();
+        let total_bytes: u64 = buckets.iter().map(|b| b.total_size).sum();
+        let percent = |part: u64, whole: u64| if whole > 0 { part as f64 * 100.0 / whole as f64 } else { 0.0 };
+        let small = self.size_histogram.sum_range(0, 64 * 1024);
+        let giant = self.size_histogram.sum_range(1024 * 1024 * 1024, u64::MAX);
+        ui.label(format!("Files under 64 KB: {:.1}% of files, {:.1}% of bytes", 
+            percent(small.file_count, total_files), percent(small.total_size, total_bytes)));
+        ui.label(format!("Files of 1 GB and more: {:.1}% of files, {:.1}% of bytes", 
+            percent(giant.file_count, total_files), percent(giant.total_size, total_bytes)));
+        
+        let by_bytes = self.histogram_by_bytes;
+        let bars: Vec<egui_plot::Bar> = buckets
+            .iter()
+            .enumerate()
+            .map(|(index, bucket)| {
+                let value = if by_bytes { bucket.total_size as f64 } else { bucket.file_count as f64 };
+                egui_plot::Bar::new(index as f64, value)
+                    .width(0.8)
+                    .name(format!("{}\n{} files\n{}", 
+                        self.size_histogram.label(index), bucket.file_count, format_size(bucket.total_size)))
+            })
+            .collect();
+        
+        let labels: Vec<String> = (0..buckets.len()).map(|index| self.size_histogram.label(index)).collect();
+        egui_plot::Plot::new("size_histogram")
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .x_axis_formatter(move |mark, _range| {
+                let index = mark.value.round();
+                if (mark.value - index).abs() < f64::EPSILON && index >= 0.0 {
+                    labels.get(index as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .y_axis_formatter(move |mark, _range| {
+                if by_bytes { format_size(mark.value.max(0.0) as u64) } else { format!("{}", mark.value) }
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(egui_plot::BarChart::new(if by_bytes { "Total bytes" } else { "File count" }, bars)
+                    .color(egui::Color32::LIGHT_BLUE));
+            });
+    }
+    
+    fn show_cleanup(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🧹 Cleanup candidates");
+        ui.separator();
+        
+        if self.cleanup.is_empty() {
+            ui.label(if self.is_scanning { "Scanning files..." } else { "No known cache, temp or junk locations found" });
+            return;
+        }
+        
+        let total: u64 = self.cleanup.iter().map(|report| report.total_size).sum();
+        ui.label(format!("{} in known cache, temp and junk locations", format_size(total)));
+        ui.separator();
+        
+        let mut clicked_path = None;
+        let mut marked = Vec::new();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for report in &self.cleanup {
+                    egui::CollapsingHeader::new(format!("[{}] {} ({} files in {} locations)", 
+                        format_size(report.total_size), report.name, report.file_count, report.location_count))
+                        .id_salt(("cleanup_rule", &report.name))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(&report.explanation).italics());
+                            for location in &report.locations {
+                                ui.horizontal(|ui| {
+                                    marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, &location.path, location.size, None, &report.name));
+                                    ui.label(format!("[{}]", format_size(location.size)));
+                                    if ui.link(egui::RichText::new(&location.path).size(11.0)).clicked() {
+                                        clicked_path = Some(location.path.clone());
+                                    }
+                                });
+                            }
+                            if report.location_count > report.locations.len() {
+                                ui.label(format!("... and {} smaller locations", report.location_count - report.locations.len()));
+                            }
+                        })
+                        .header_response
+                        .on_hover_text(&report.explanation);
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on cleanup location: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        self.mark_in_plan(marked);
+    }
+    
+    fn show_build_artifacts(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🛠 Build artifacts");
+        ui.separator();
+        
+        if self.build_projects.is_empty() {
+            ui.label(if !self.find_build_artifacts {
+                "Enable \"Build artifacts\" and run a scan"
+            } else if self.is_scanning {
+                "Searching for projects..."
+            } else {
+                "No projects with build artifacts found"
+            });
+            return;
+        }
+        
+        let total: u64 = self.build_projects.iter().map(|project| project.total_size()).sum();
+        ui.label(format!("{} projects, {} of build artifacts", self.build_projects.len(), format_size(total)));
+        ui.separator();
+        
+        let now = std::time::SystemTime::now();
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for project in &self.build_projects {
+                    let last_build = project.last_build()
+                        .map(|time| format!("built {} ago", format_age(age_in_days(now, time))))
+                        .unwrap_or_else(|| "build time unknown".to_string());
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!self.is_scanning, egui::Button::new("🧹 Clean")).clicked() {
+                            self.confirm_clean_project = Some(project.root.clone());
+                        }
+                        ui.label(format!("[{}]", format_size(project.total_size())));
+                        ui.label(egui::RichText::new(project.kinds_label()).strong());
+                        ui.label(egui::RichText::new(last_build).color(egui::Color32::GRAY));
+                        if ui.link(&project.root).clicked() {
+                            clicked_path = Some(project.root.clone());
+                        }
+                    });
+                    ui.indent(("build_project", &project.root), |ui| {
+                        for artifact in &project.artifacts {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("[{}]", format_size(artifact.size)));
+                                if ui.link(egui::RichText::new(&artifact.path).size(11.0))
+                                    .on_hover_text(format!("{} files", artifact.file_count))
+                                    .clicked()
+                                {
+                                    clicked_path = Some(artifact.path.clone());
+                                }
+                            });
+                        }
+                    });
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on build project: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        
+        let Some(root) = self.confirm_clean_project.clone() else { return };
+        let Some(project) = self.build_projects.iter().find(|project| project.root == root).cloned() else {
+            self.confirm_clean_project = None;
+            return;
+        };
+        egui::Window::new("Clean project")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ui.ctx(), |ui| {
+                ui.label(format!("Permanently delete {} of build artifacts in {}?", format_size(project.total_size()), project.root));
+                for artifact in &project.artifacts {
+                    ui.label(egui::RichText::new(&artifact.path).size(11.0));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Clean").clicked() {
+                        self.confirm_clean_project = None;
+                        self.clean_project(&project);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_clean_project = None;
+                    }
+                });
+            });
+    }
+    
+    fn clean_project(&mut self, project: &BuildProject) {
+        info!("Cleaning build artifacts of {}", project.root);
+        // The confirmation covered the whole project, so one protected artifact blocks all of it
+        let artifacts: Vec<String> = project.artifacts.iter().map(|artifact| artifact.path.clone()).collect();
+        let (_, blocked) = self.protected_paths.check("clean", &artifacts);
+        if !blocked.is_empty() {
+            self.scan_status = format!("Not cleaning {}: {}", project.root, blocked.join(", "));
+            return;
+        }
+        let (removed, errors) = clean_project(project);
+        
+        if let Some(entry) = self.build_projects.iter_mut().find(|entry| entry.root == project.root) {
+            entry.artifacts.retain(|artifact| !removed.contains(&artifact.path));
+        }
+        self.build_projects.retain(|entry| !entry.artifacts.is_empty());
+        
+        self.scan_status = if errors.is_empty() {
+            format!("Cleaned {} ({} folders removed)", project.root, removed.len())
+        } else {
+            format!("Cleaned {} partially, {} folders failed (see log)", project.root, errors.len())
+        };
+    }
+    
+    fn show_git_repositories(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🌿 Git repositories");
+        ui.separator();
+        
+        if self.git_repositories.is_empty() {
+            ui.label(if !self.analyze_git_repos {
+                "Enable \"Git repos\" and run a scan"
+            } else if self.is_scanning {
+                "Analyzing repositories..."
+            } else {
+                "No git repositories found"
+            });
+            return;
+        }
+        
+        let total: u64 = self.git_repositories.iter().map(|repository| repository.git_size).sum();
+        let savings: u64 = self.git_repositories
+            .iter()
+            .map(|repository| repository.gc_savings() + repository.lfs_prune_savings())
+            .sum();
+        ui.label(format!("{} repositories, {} in .git folders, about {} reclaimable with git gc / git lfs prune", 
+            self.git_repositories.len(), format_size(total), format_size(savings)));
+        ui.separator();
+        
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for repository in &self.git_repositories {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("[{}]", format_size(repository.git_size)));
+                        if ui.link(&repository.root).clicked() {
+                            clicked_path = Some(repository.root.clone());
+                        }
+                        if repository.needs_gc() {
+                            ui.label(egui::RichText::new(format!("git gc: ~{}", format_size(repository.gc_savings())))
+                                .color(egui::Color32::LIGHT_RED))
+                                .on_hover_text("Many loose objects, many packfiles or leftover temporary packs. \
+                                    Running `git gc` packs and cleans them up; the savings are an estimate.");
+                        }
+                        if repository.needs_lfs_prune() {
+                            ui.label(egui::RichText::new(format!("git lfs prune: up to {}", format_size(repository.lfs_prune_savings())))
+                                .color(egui::Color32::LIGHT_RED))
+                                .on_hover_text("LFS objects whose content is not in the current checkout. \
+                                    `git lfs prune` deletes those not referenced by recent commits.");
+                        }
+                    });
+                    ui.indent(("git_repository", &repository.root), |ui| {
+                        egui::Grid::new(("git_breakdown", &repository.root)).num_columns(3).show(ui, |ui| {
+                            let rows = [
+                                ("Packfiles", repository.packs),
+                                ("Loose objects", repository.loose_objects),
+                                ("LFS objects", repository.lfs_objects),
+                                ("Temporary packs", repository.garbage),
+                            ];
+                            for (label, stats) in rows.iter().filter(|(_, stats)| stats.size > 0) {
+                                ui.label(*label);
+                                ui.label(format_size(stats.size));
+                                ui.label(format!("{} files", stats.count));
+                                ui.end_row();
+                            }
+                            if !repository.worktrees.is_empty() {
+                                ui.label("Worktrees");
+                                ui.label(format_size(repository.worktree_metadata_size));
+                                ui.label(format!("{} linked", repository.worktrees.len()))
+                                    .on_hover_text(repository.worktrees.join("\n"));
+                                ui.end_row();
+                            }
+                            ui.label("Other");
+                            ui.label(format_size(repository.other_size()));
+                            ui.label("");
+                            ui.end_row();
+                        });
+                    });
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on git repository: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+    }
+    
+    fn show_vm_images(&mut self, ui: &mut egui::Ui) {
+        ui.heading("💽 Virtual machine and container images");
+        ui.separator();
+        
+        if self.vm_images.is_empty() {
+            ui.label(if self.is_scanning { "Available when the scan completes" } else { "No virtual machine, container or ISO images found" });
+            return;
+        }
+        
+        let logical: u64 = self.vm_images.iter().map(|image| image.logical_size).sum();
+        let allocated: u64 = self.vm_images.iter().map(|image| image.allocated_size.unwrap_or(image.logical_size)).sum();
+        ui.label(format!("{} images, {} logical, {} allocated on disk", 
+            self.vm_images.len(), format_size(logical), format_size(allocated)));
+        ui.label(egui::RichText::new("Dynamically expanding disks keep their size when the guest deletes data; \
+            compacting them can reclaim that space.").italics());
+        ui.separator();
+        
+        let optional_size = |size: Option<u64>| size.map(format_size).unwrap_or_else(|| "?".to_string());
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::Grid::new("vm_image_grid")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Format", "Logical", "Allocated", "Virtual max", "Type", "Path"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        
+                        for image in &self.vm_images {
+                            ui.label(&image.format);
+                            ui.label(format_size(image.logical_size));
+                            ui.label(optional_size(image.allocated_size));
+                            ui.label(optional_size(image.virtual_size));
+                            if image.can_compact() {
+                                ui.label(egui::RichText::new("Dynamic").color(egui::Color32::LIGHT_GREEN))
+                                    .on_hover_text("Grows on demand and can be compacted");
+                            } else {
+                                ui.label(if image.dynamic == Some(false) { "Fixed" } else { "-" });
+                            }
+                            let link = ui.link(egui::RichText::new(&image.path).size(11.0));
+                            let link = match image.note {
+                                Some(note) => link.on_hover_text(note),
+                                None => link,
+                            };
+                            if link.clicked() {
+                                clicked_path = Some(image.path.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on VM image: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+    }
+    
+    fn open_archive(&mut self, path: String) {
+        if let Some(index) = self.archives.iter().position(|listing| listing.archive_path == path) {
+            self.selected_archive = Some(index);
+            self.archive_folder.clear();
+            self.current_view = ResultView::Archives;
+            return;
+        }
+        
+        info!("Opening archive for browsing: {}", path);
+        let (sender, receiver) = mpsc::channel();
+        self.archive_receiver = Some(receiver);
+        self.current_view = ResultView::Archives;
+        self.scan_status = format!("Reading archive {}...", path);
+        
+        std::thread::spawn(move || {
+            let should_stop = Arc::new(Mutex::new(false));
+            let result = list_archive(std::path::Path::new(&path), &should_stop).map_err(|e| {
+                error!("Failed to list archive {}: {}", path, e);
+                e.to_string()
+            });
+            let _ = sender.send(result);
+        });
+    }
+    
+    fn check_archive_listing(&mut self) {
+        let Some(receiver) = &self.archive_receiver else { return };
+        let Ok(result) = receiver.try_recv() else { return };
+        self.archive_receiver = None;
+        
+        match result {
+            Ok(listing) => {
+                self.scan_status = format!("Archive {} holds {} files", listing.archive_path, listing.root().file_count);
+                self.archives.push(listing);
+                self.selected_archive = Some(self.archives.len() - 1);
+                self.archive_folder.clear();
+            }
+            Err(e) => self.scan_status = format!("Could not read archive: {}", e),
+        }
+    }
+    
+    fn show_archives(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("📦 Archives");
+            ui.separator();
+            ui.add(egui::TextEdit::singleline(&mut self.archive_open_path)
+                .hint_text("Path to a .zip, .tar, .tar.gz or .tar.zst file")
+                .desired_width(350.0));
+            let reading = self.archive_receiver.is_some();
+            if ui.add_enabled(!reading && !self.archive_open_path.trim().is_empty(), egui::Button::new("Open")).clicked() {
+                self.open_archive(self.archive_open_path.trim().to_string());
+            }
+            if reading {
+                ui.spinner();
+                ui.label("Reading archive...");
+            }
+        });
+        ui.separator();
+        
+        if self.archives.is_empty() {
+            ui.label("Enable \"Archives\" before scanning, use \"📦 Browse\" on an archive in the file list, or open one above");
+            return;
+        }
+        
+        let selected = self.selected_archive.filter(|&index| index < self.archives.len()).unwrap_or(0);
+        ui.horizontal(|ui| {
+            ui.label("Archive:");
+            egui::ComboBox::from_id_salt("archive_selector")
+                .width(500.0)
+                .selected_text(&self.archives[selected].archive_path)
+                .show_ui(ui, |ui| {
+                    for (index, listing) in self.archives.iter().enumerate() {
+                        if ui.selectable_label(index == selected, &listing.archive_path).clicked() && index != selected {
+                            self.selected_archive = Some(index);
+                            self.archive_folder.clear();
+                        }
+                    }
+                });
+        });
+        
+        let selected = self.selected_archive.filter(|&index| index < self.archives.len()).unwrap_or(0);
+        let listing = &self.archives[selected];
+        let root = listing.root();
+        let ratio = if root.size > 0 { listing.archive_size as f64 * 100.0 / root.size as f64 } else { 100.0 };
+        ui.label(format!("{} archive, {} on disk, {} uncompressed ({:.0}%), {} files", 
+            listing.format.label(), format_size(listing.archive_size), format_size(root.size), ratio, root.file_count));
+        
+        // Breadcrumb back to any parent folder
+        let mut navigate_to: Option<Vec<usize>> = None;
+        ui.horizontal(|ui| {
+            if ui.link(format!("📦 {}", root.name)).clicked() {
+                navigate_to = Some(Vec::new());
+            }
+            for (depth, &node) in self.archive_folder.iter().enumerate() {
+                ui.label("›");
+                if ui.link(&listing.nodes[node].name).clicked() {
+                    navigate_to = Some(self.archive_folder[..=depth].to_vec());
+                }
+            }
+        });
+        ui.separator();
+        
+        let current = self.archive_folder.last().copied().unwrap_or(0);
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::Grid::new("archive_entries")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Name", "Size", "Compressed", "Files"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        
+                        for &child in &listing.nodes[current].children {
+                            let node = &listing.nodes[child];
+                            if node.is_dir {
+                                if ui.link(format!("📁 {}", node.name)).clicked() {
+                                    let mut path = self.archive_folder.clone();
+                                    path.push(child);
+                                    navigate_to = Some(path);
+                                }
+                            } else {
+                                ui.label(format!("📄 {}", node.name));
+                            }
+                            ui.label(format_size(node.size));
+                            ui.label(node.compressed_size.map(format_size).unwrap_or_else(|| "-".to_string()));
+                            ui.label(format!("{}", node.file_count));
+                            ui.end_row();
+                        }
+                    });
+                
+                ui.separator();
+                egui::CollapsingHeader::new("Largest files in the archive")
+                    .show(ui, |ui| {
+                        for node in listing.largest_files(20) {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("[{}]", format_size(node.size)));
+                                ui.label(egui::RichText::new(&node.path).size(11.0));
+                            });
+                        }
+                    });
+            });
+        
+        if let Some(path) = navigate_to {
+            self.archive_folder = path;
+        }
+    }
+    
+    fn show_sparse_files(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🕸 Sparse and preallocated files");
+        ui.separator();
+        
+        if self.sparse_files.is_empty() {
+            ui.label(if self.is_scanning { "Scanning files..." } else { "No sparse, compressed or preallocated files found" });
+            return;
+        }
+        
+        let logical: u64 = self.sparse_files.iter().map(|file| file.size).sum();
+        let real: u64 = self.sparse_files.iter().filter_map(|file| file.allocation).map(|allocation| allocation.allocated_size).sum();
+        ui.label(format!("{} files, {} logical, {} really used on disk", 
+            self.sparse_files.len(), format_size(logical), format_size(real)));
+        ui.label(egui::RichText::new("Sparse files only occupy the blocks that were written; copying them with tools \
+            that are not sparse-aware writes out every hole.").italics());
+        ui.separator();
+        
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                egui::Grid::new("sparse_file_grid")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Type", "Logical", "Real", "Hole ratio", "Holes", "Path"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        
+                        for file in &self.sparse_files {
+                            let Some(allocation) = file.allocation else { continue };
+                            ui.label(allocation.kind.label());
+                            ui.label(format_size(file.size));
+                            ui.label(format_size(allocation.allocated_size));
+                            if allocation.allocated_size > file.size {
+                                ui.label(format!("+{} reserved", format_size(allocation.allocated_size - file.size)));
+                            } else {
+                                let ratio = allocation.hole_ratio(file.size) as f32;
+                                ui.add(egui::ProgressBar::new(ratio).desired_width(120.0).text(format!("{:.1}%", ratio * 100.0)));
+                            }
+                            ui.label(allocation.holes.map(|holes| holes.to_string()).unwrap_or_else(|| "-".to_string()));
+                            if ui.link(egui::RichText::new(&file.path).size(11.0)).clicked() {
+                                clicked_path = Some(file.path.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on sparse file: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+    }
+   
+    fn show_filter_bar(&mut self, ui: &mut egui::Ui) {
+        let mut apply = false;
+        egui::CollapsingHeader::new("🔎 Filter results")
+            .default_open(false)
+            .show(ui, |ui| {
+                let submitted = |response: egui::Response| {
+                    response.lost_focus() && response.ctx.input(|input| input.key_pressed(egui::Key::Enter))
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.name).desired_width(120.0)));
+                    ui.checkbox(&mut self.filter_bar.use_regex, "Regex");
+                    ui.label("Extensions:");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.extensions)
+                        .desired_width(80.0)
+                        .hint_text("mp4, iso")));
+                    ui.label("Category:");
+                    egui::ComboBox::from_id_salt("filter_category")
+                        .selected_text(self.filter_bar.category.as_deref().unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.filter_bar.category, None, "All");
+                            for category in self.categories.all() {
+                                ui.selectable_value(
+                                    &mut self.filter_bar.category,
+                                    Some(category.name.clone()),
+                                    format!("{} {}", category.icon, category.name),
+                                );
+                            }
+                        });
+                    ui.label("Size:");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.size)
+                        .desired_width(70.0)
+                        .hint_text(">2GB"))
+                        .on_hover_text("A bare size means at least that size; also >=, <, <= and ranges like 1G-5G"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Modified:");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.modified_from)
+                        .desired_width(80.0)
+                        .hint_text("YYYY-MM-DD")));
+                    ui.label("to");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.modified_to)
+                        .desired_width(80.0)
+                        .hint_text("YYYY-MM-DD")));
+                    ui.label("Under:");
+                    apply |= submitted(ui.add(egui::TextEdit::singleline(&mut self.filter_bar.path_prefix)
+                        .desired_width(180.0)
+                        .hint_text("D:\\Projects")));
+                    if ui.add_enabled(self.scan_index.is_some(), egui::Button::new("Apply")).clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Clear").clicked() {
+                        info!("Clearing result filter");
+                        self.filter_bar = FilterBar::default();
+                        self.filtered = None;
+                        self.filter_error = None;
+                    }
+                });
+                
+                if let Some(error) = &self.filter_error {
+                    ui.label(egui::RichText::new(error).color(egui::Color32::LIGHT_RED));
+                } else if let Some(filtered) = &self.filtered {
+                    ui.label(format!("{} matching files, {} in total", filtered.matched_files, format_size(filtered.matched_bytes)));
+                } else if self.scan_index.is_none() {
+                    ui.label(egui::RichText::new("Available when a scan completes").weak());
+                }
+            });
+        
+        if apply {
+            self.apply_result_filter();
+        }
+    }
+    
+    fn apply_result_filter(&mut self) {
+        let Some(index) = &self.scan_index else {
+            self.filter_error = Some("Filtering is available when a scan completes".to_string());
+            return;
+        };
+        match self.filter_bar.compile() {
+            Ok(filter) => {
+                info!("Applying result filter over {} retained entries: {:?}", index.nodes.len(), self.filter_bar);
+                let filtered = apply_filter(index, &filter, self.file_limit, self.folder_limit);
+                self.scan_status = format!("Filter matched {} files", filtered.matched_files);
+                self.filtered = Some(filtered);
+                self.filter_error = None;
+            }
+            Err(e) => {
+                warn!("Invalid result filter: {}", e);
+                self.filter_error = Some(e);
+            }
+        }
+    }
+    
+    fn show_query(&mut self, ui: &mut egui::Ui) {
+        ui.heading("⌨ Query");
+        ui.separator();
+        
+        let mut run = false;
+        ui.horizontal(|ui| {
+            let response = ui.add(egui::TextEdit::singleline(&mut self.query_text)
+                .desired_width(620.0)
+                .font(egui::TextStyle::Monospace)
+                .hint_text("type:file ext:iso,vhdx size>4G mtime<2023-01-01 sort:size limit:50"));
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                run = true;
+            }
+            if ui.add_enabled(self.scan_index.is_some(), egui::Button::new("Run")).clicked() {
+                run = true;
+            }
+        });
+        ui.label(egui::RichText::new("Keys: type, ext, name, re, cat, size, mtime, path, sort, order, limit, group (ext, category, folder, type) \
+            with count, sum:size, avg:size, max:size. Bare words match names.").size(10.0).weak());
+        
+        if let Some(error) = &self.query_error {
+            ui.label(egui::RichText::new(format!("{}\n{}^", self.query_text, " ".repeat(error.position)))
+                .monospace()
+                .color(egui::Color32::LIGHT_RED));
+            ui.label(egui::RichText::new(&error.message).color(egui::Color32::LIGHT_RED));
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("Snapshot:");
+            ui.add(egui::TextEdit::singleline(&mut self.snapshot_path)
+                .desired_width(300.0)
+                .hint_text("scan.ffsnap"));
+            if ui.add_enabled(self.scan_index.is_some() && !self.snapshot_path.is_empty(), egui::Button::new("💾 Save")).clicked() {
+                if let Some(index) = &self.scan_index {
+                    match save_snapshot(std::path::Path::new(&self.snapshot_path), index) {
+                        Ok(()) => self.scan_status = format!("Saved snapshot to {}", self.snapshot_path),
+                        Err(e) => {
+                            error!("Failed to save snapshot {}: {}", self.snapshot_path, e);
+                            self.scan_status = format!("Error saving snapshot: {}", e);
+                        }
+                    }
+                }
+            }
+            if ui.add_enabled(!self.snapshot_path.is_empty() && !self.is_scanning, egui::Button::new("📂 Load")).clicked() {
+                match load_snapshot(std::path::Path::new(&self.snapshot_path)) {
+                    Ok(snapshot) => {
+                        self.scan_status = format!("Loaded snapshot from {} ({} entries, saved {})", 
+                            self.snapshot_path, snapshot.index.nodes.len(), format_date(snapshot.created));
+                        self.scan_index = Some(Arc::new(snapshot.index));
+                        self.filtered = None;
+                        self.query_output = None;
+                    }
+                    Err(e) => {
+                        error!("Failed to load snapshot {}: {}", self.snapshot_path, e);
+                        self.scan_status = format!("Error loading snapshot: {}", e);
+                    }
+                }
+            }
+        });
+        ui.separator();
+        
+        if run {
+            self.run_query();
+        }
+        self.show_selection_bar(ui);
+        
+        let Some(output) = &self.query_output else {
+            ui.label(if self.scan_index.is_some() { "Enter a query and press Enter" } else { "Run a scan or load a snapshot to query it" });
+            return;
+        };
+        ui.label(format!("{} matching entries, {}", output.matched, format_size(output.matched_bytes)));
+        
+        let mut clicked_path = None;
+        let mut marked = Vec::new();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| match &output.rows {
+                QueryRows::Entries(entries) => {
+                    egui::Grid::new("query_entries").num_columns(5).striped(true).show(ui, |ui| {
+                        for header in ["", "", "Size", "Modified", "Path"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for entry in entries {
+                            Self::selection_checkbox(ui, &self.protection, &mut self.selected_paths, &entry.path, entry.size);
+                            marked.extend(Self::plan_menu(ui, &self.cleanup_plan, &self.protection, &entry.path, entry.size, None, "Query"));
+                            ui.label(format_size(entry.size));
+                            ui.label(entry.modified.map(format_date).unwrap_or_else(|| "-".to_string()));
+                            if ui.link(egui::RichText::new(&entry.path).size(11.0)).clicked() {
+                                clicked_path = Some(entry.path.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                }
+                QueryRows::Groups(groups) => {
+                    egui::Grid::new("query_groups").num_columns(output.aggregates.len() + 1).striped(true).show(ui, |ui| {
+                        for aggregate in &output.aggregates {
+                            ui.label(egui::RichText::new(aggregate.label()).strong());
+                        }
+                        ui.label(egui::RichText::new("Group").strong());
+                        ui.end_row();
+                        for group in groups {
+                            for &aggregate in &output.aggregates {
+                                ui.label(group.format_value(aggregate));
+                            }
+                            ui.label(&group.key);
+                            ui.end_row();
+                        }
+                    });
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on query result: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        self.mark_in_plan(marked);
+    }
+    
+    fn run_query(&mut self) {
+        let Some(index) = &self.scan_index else { return };
+        match parse_query(&self.query_text) {
+            Ok(query) => {
+                info!("Running query \"{}\" over {} entries", self.query_text, index.nodes.len());
+                self.query_output = Some(run_query(index, &query));
+                self.query_error = None;
+            }
+            Err(e) => {
+                warn!("Invalid query \"{}\": {}", self.query_text, e);
+                self.query_error = Some(e);
+            }
+        }
+    }
+    
+    /// Selected paths that are not inside another selected folder, since those go along with it
+    fn selected_roots(&self) -> Vec<(String, u64)> {
+        self.selected_paths
+            .iter()
+            .filter(|(path, _)| !self.selected_paths.keys().any(|other| {
+                other != *path && std::path::Path::new(path).starts_with(other)
+            }))
+            .map(|(path, &size)| (path.clone(), size))
+            .collect()
+    }
+    
+    fn show_selection_bar(&mut self, ui: &mut egui::Ui) {
+        if self.selected_paths.is_empty() && self.move_progress.is_none() && self.compress_progress.is_none() {
+            return;
+        }
+        let items = self.selected_roots();
+        let total: u64 = items.iter().map(|(_, size)| size).sum();
+        
+        ui.horizontal(|ui| {
+            ui.label(format!("☑ {} selected, {}", items.len(), format_size(total)));
+            if ui.add_enabled(!self.is_scanning && self.scan_index.is_some(), egui::Button::new(format!("🗑 Move to {}", TRASH_NAME)))
+                .on_disabled_hover_text("Available when a scan completes")
+                .clicked() {
+                self.confirm_trash = true;
+            }
+            if ui.add_enabled(!self.is_scanning && self.scan_index.is_some() && self.quarantine_receiver.is_none(), egui::Button::new("🛡 Quarantine"))
+                .on_hover_text("Move to a quarantine folder on the same volume, restorable until purged")
+                .clicked() {
+                self.confirm_quarantine = true;
+            }
+            if ui.add_enabled(!self.is_scanning && self.scan_index.is_some() && self.move_receiver.is_none(), egui::Button::new("➡ Move to..."))
+                .on_hover_text("Copy to another folder or volume, verify the copy, then delete the original")
+                .clicked() {
+                self.confirm_move = true;
+            }
+            if ui.add_enabled(!self.is_scanning && self.scan_index.is_some() && self.compress_receiver.is_none(), egui::Button::new("🗜 Compress"))
+                .on_hover_text("Archive next to the original as zip or tar.zst, verify it, and optionally delete the original")
+                .clicked() {
+                self.confirm_compress = true;
+            }
+            ui.menu_button("📋 Add to plan", |ui| {
+                for action in PlanAction::ALL {
+                    if ui.button(action.label()).clicked() {
+                        let marked = items.iter()
+                            .map(|(path, size)| PlanItem {
+                                path: path.clone(),
+                                size: *size,
+                                action,
+                                estimated_savings: None,
+                                format: self.compress_format,
+                                delete_original: self.compress_delete_original,
+                                source: "Selection".to_string(),
+                            })
+                            .collect();
+                        self.mark_in_plan(marked);
+                        ui.close();
+                    }
+                }
+            });
+            if ui.button("Clear selection").clicked() {
+                self.selected_paths.clear();
+            }
+            Self::show_byte_progress(ui, &self.move_progress, &self.move_stop_flag);
+            Self::show_byte_progress(ui, &self.compress_progress, &self.compress_stop_flag);
+        });
+        ui.separator();
+        
+        if self.confirm_trash {
+            egui::Window::new(format!("Move to {}", TRASH_NAME))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Move {} items ({}) to the {}?", items.len(), format_size(total), TRASH_NAME));
+                    Self::show_selected_items(ui, &items);
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("Move to {}", TRASH_NAME)).clicked() {
+                            self.confirm_trash = false;
+                            self.move_selected_to_trash();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_trash = false;
+                        }
+                    });
+                });
+        }
+        
+        if self.confirm_quarantine {
+            egui::Window::new("Quarantine")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Move {} items ({}) into quarantine?", items.len(), format_size(total)));
+                    ui.label(if self.quarantine_retention_days > 0 {
+                        format!("They can be restored from the Quarantine view and are purged after {} days.", self.quarantine_retention_days)
+                    } else {
+                        "They can be restored from the Quarantine view until purged.".to_string()
+                    });
+                    Self::show_selected_items(ui, &items);
+                    ui.horizontal(|ui| {
+                        if ui.button("Quarantine").clicked() {
+                            self.confirm_quarantine = false;
+                            self.quarantine_selected();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_quarantine = false;
+                        }
+                    });
+                });
+        }
+        
+        if self.confirm_move {
+            egui::Window::new("Move to...")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Move {} items ({}) to another folder or volume?", items.len(), format_size(total)));
+                    ui.horizontal(|ui| {
+                        ui.label("Target folder:");
+                        ui.add(egui::TextEdit::singleline(&mut self.move_target)
+                            .desired_width(300.0)
+                            .hint_text("E:\\Archive"));
+                    });
+                    ui.checkbox(&mut self.move_leave_link, if cfg!(windows) {
+                        "Leave a junction (folders) or symlink (files) behind"
+                    } else {
+                        "Leave a symlink behind"
+                    });
+                    
+                    // The target may not exist yet, so check the volume of its nearest existing ancestor
+                    let target = PathBuf::from(self.move_target.trim());
+                    let existing = target.ancestors().find(|dir| !dir.as_os_str().is_empty() && dir.exists());
+                    let needed: u64 = items.iter()
+                        .filter(|(path, _)| existing.is_none_or(|dir| !same_volume(std::path::Path::new(path), dir)))
+                        .map(|(_, size)| size)
+                        .sum();
+                    let available = existing.and_then(free_space);
+                    let fits = available.is_none_or(|available| available >= needed);
+                    if let Some(available) = available {
+                        let text = format!("Needs {} on the target, {} free", format_size(needed), format_size(available));
+                        ui.label(if fits { egui::RichText::new(text) } else { egui::RichText::new(text).color(egui::Color32::RED) });
+                    }
+                    ui.label(egui::RichText::new("Each copy is checked by size and hash before the original is deleted. \
+                        An interrupted move continues where it stopped when started again with the same target.").size(11.0).weak());
+                    Self::show_selected_items(ui, &items);
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!self.move_target.trim().is_empty() && fits, egui::Button::new("Move"))
+                            .on_disabled_hover_text("Choose a target folder with enough free space")
+                            .clicked() {
+                            self.confirm_move = false;
+                            self.move_selected();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_move = false;
+                        }
+                    });
+                });
+        }
+        
+        if self.confirm_compress {
+            egui::Window::new("Compress")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Compress {} items ({}) into archives next to them?", items.len(), format_size(total)));
+                    ui.horizontal(|ui| {
+                        ui.label("Format:");
+                        for format in CompressFormat::ALL {
+                            ui.radio_value(&mut self.compress_format, format, format.label());
+                        }
+                    });
+                    ui.label(egui::RichText::new(match self.compress_format {
+                        CompressFormat::Zip => "ZIP opens anywhere, including Windows Explorer.",
+                        CompressFormat::TarZst => "TAR.ZST compresses best and keeps permissions and links.",
+                    }).size(11.0).weak());
+                    ui.checkbox(&mut self.compress_delete_original, "Delete the originals once their archive is verified");
+                    Self::show_selected_items(ui, &items);
+                    ui.horizontal(|ui| {
+                        if ui.button("Compress").clicked() {
+                            self.confirm_compress = false;
+                            self.compress_selected();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_compress = false;
+                        }
+                    });
+                });
+        }
+    }
+    
+    /// Progress of a background move or compression, with its cancel button
+    fn show_byte_progress(ui: &mut egui::Ui, progress: &Option<(u64, u64, String)>, stop_flag: &Option<Arc<Mutex<bool>>>) {
+        let Some((done, total, path)) = progress else { return };
+        ui.add(egui::ProgressBar::new(*done as f32 / (*total).max(1) as f32)
+            .desired_width(200.0)
+            .text(format!("{} / {}", format_size(*done), format_size(*total))));
+        ui.label(egui::RichText::new(path).size(10.0).weak());
+        if ui.button("⏹ Cancel").clicked() {
+            if let Some(stop_flag) = stop_flag {
+                *stop_flag.lock().unwrap() = true;
+            }
+        }
+    }
+    
+    /// Selection checkbox of a result row, disabled for protected paths, which show a lock in their plan menu
+    fn selection_checkbox(ui: &mut egui::Ui, protection: &ProtectionCache, selected_paths: &mut BTreeMap<String, u64>, path: &str, size: u64) {
+        let mut selected = selected_paths.contains_key(path);
+        let allowed = protection.reason(path).is_none();
+        if ui.add_enabled(allowed, egui::Checkbox::without_text(&mut selected)).changed() {
+            if selected {
+                selected_paths.insert(path.to_string(), size);
+            } else {
+                selected_paths.remove(path);
+            }
+        }
+    }
+    
+    /// Selected roots an action may touch, and a message for each protected one, which is audited
+    fn unprotected_selection(&self, action: &str) -> (Vec<(String, u64)>, BlockedPaths) {
+        let mut allowed = Vec::new();
+        let mut blocked = Vec::new();
+        for (path, size) in self.selected_roots() {
+            match self.protected_paths.guard(action, std::path::Path::new(&path)) {
+                Ok(()) => allowed.push((path, size)),
+                Err(e) => blocked.push((path, e)),
+            }
+        }
+        (allowed, blocked)
+    }
+    
+    /// Item list of a bulk action confirmation
+    fn show_selected_items(ui: &mut egui::Ui, items: &[(String, u64)]) {
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                for (path, size) in items {
+                    ui.label(egui::RichText::new(format!("[{}] {}", format_size(*size), path)).size(11.0));
+                }
+            });
+    }
+    
+    fn move_selected_to_trash(&mut self) {
+        let (items, blocked) = self.unprotected_selection("trash");
+        info!("Moving {} selected items to the {}", items.len(), TRASH_NAME);
+        let paths: Vec<String> = items.iter().map(|(path, _)| path.clone()).collect();
+        let (removed, mut errors) = move_to_trash(&paths);
+        errors.extend(blocked.into_iter().map(|(_, e)| e));
+        let removed: Vec<(String, u64)> = items.into_iter().filter(|(path, _)| removed.contains(path)).collect();
+        
+        let freed = self.remove_from_results(&removed);
+        // Failed items stay selected so they can be retried
+        self.selected_paths.retain(|path, _| !removed.iter().any(|(gone, _)| std::path::Path::new(path).starts_with(gone)));
+        
+        self.scan_status = if errors.is_empty() {
+            format!("Moved {} items ({}) to the {}", removed.len(), format_size(freed), TRASH_NAME)
+        } else {
+            format!("Moved {} items ({}) to the {}, {} failed (see log)", removed.len(), format_size(freed), TRASH_NAME, errors.len())
+        };
+    }
+    
+    /// Drop deleted or moved paths from the results and re-rank what is left without a rescan.
+    /// Sizes come from the retained index when it has the path. Returns the bytes removed.
+    fn remove_from_results(&mut self, removed: &[(String, u64)]) -> u64 {
+        use std::path::Path;
+        
+        // Removed roots as (path, bytes, files below it, is a folder)
+        let mut gone: Vec<(PathBuf, u64, u64, bool)> = Vec::new();
+        for (path, size) in removed {
+            let index = self.scan_index.as_mut().map(Arc::make_mut);
+            let Some((index, node)) = index.and_then(|index| index.find(Path::new(path)).map(|node| (index, node))) else {
+                warn!("{} is not in the retained scan, using its size when selected", path);
+                gone.push((PathBuf::from(path), *size, 0, false));
+                continue;
+            };
+            let mut files = 0;
+            for removed_node in index.remove(node) {
+                if index.nodes[removed_node].is_dir {
+                    continue;
+                }
+                files += 1;
+                let size = index.nodes[removed_node].size;
+                if let Some(stats) = self.category_stats.iter_mut().find(|stats| Some(stats.name.as_str()) == index.category(removed_node)) {
+                    stats.file_count = stats.file_count.saturating_sub(1);
+                    stats.total_size = stats.total_size.saturating_sub(size);
+                }
+            }
+            gone.push((PathBuf::from(path), index.nodes[node].size, files, index.nodes[node].is_dir));
+        }
+        let freed = gone.iter().map(|(_, bytes, _, _)| bytes).sum();
+        info!("Removing {} paths ({}) from the results", gone.len(), format_size(freed));
+        
+        let under = |path: &str| gone.iter().any(|(root, _, _, _)| Path::new(path).starts_with(root));
+        self.fat_files.retain(|file| !under(&file.path));
+        self.stale_files.retain(|file| !under(&file.path));
+        self.sparse_files.retain(|file| !under(&file.path));
+        self.vm_images.retain(|image| !under(&image.path));
+        self.empty_items.folders.retain(|folder| !under(&folder.path));
+        self.empty_items.zero_byte_files.retain(|path| !under(path));
+        for set in &mut self.duplicates {
+            set.paths.retain(|path| !under(path));
+        }
+        self.duplicates.retain(|set| set.paths.len() > 1);
+        for group in &mut self.similar_images {
+            group.images.retain(|image| !under(&image.path));
+        }
+        self.similar_images.retain(|group| group.images.len() > 1);
+        self.category_stats.retain(|stats| stats.file_count > 0);
+        self.category_stats.sort_by_key(|stats| std::cmp::Reverse(stats.total_size));
+        
+        for folders in [&mut self.fat_folders, &mut self.busiest_folders] {
+            folders.retain(|folder| !under(&folder.path));
+            for folder in folders.iter_mut() {
+                for (root, bytes, files, is_dir) in &gone {
+                    if !root.starts_with(&folder.path) {
+                        continue;
+                    }
+                    folder.size = folder.size.saturating_sub(*bytes);
+                    folder.file_count = folder.file_count.map(|count| count.saturating_sub(*files));
+                    if !is_dir && root.parent() == Some(Path::new(&folder.path)) {
+                        folder.direct_file_count = folder.direct_file_count.map(|count| count.saturating_sub(1));
+                    }
+                }
+            }
+        }
+        self.busiest_folders.sort_by_key(|folder| std::cmp::Reverse(folder.file_count));
+        
+        // Entries that were just below the cut move up into the rankings
+        if let Some(index) = &self.scan_index {
+            let mut previous: HashMap<String, ScanResult> = self.fat_folders.drain(..).map(|folder| (folder.path.clone(), folder)).collect();
+            self.fat_folders = index.largest(true, self.folder_limit, |_| true)
+                .into_iter()
+                .map(|node| match previous.remove(&*index.path(node).to_string_lossy()) {
+                    Some(folder) => ScanResult { size: index.nodes[node].size, ..folder },
+                    None => index.to_result(node),
+                })
+                .collect();
+            
+            let mut previous: HashMap<String, ScanResult> = self.fat_files.drain(..).map(|file| (file.path.clone(), file)).collect();
+            let ranked = |node: usize| {
+                self.category_filter.as_deref().is_none_or(|filter| index.category(node) == Some(filter))
+                    && !(self.exclude_system_files && identify_system_file(&index.path(node)).is_some())
+            };
+            self.fat_files = index.largest(false, self.file_limit, ranked)
+                .into_iter()
+                .map(|node| previous.remove(&*index.path(node).to_string_lossy()).unwrap_or_else(|| index.to_result(node)))
+                .collect();
+        }
+        
+        if self.filtered.is_some() {
+            self.apply_result_filter();
+        }
+        if self.query_output.is_some() {
+            self.run_query();
+        }
+        freed
+    }
+    
+    fn move_selected(&mut self) {
+        let target = PathBuf::from(self.move_target.trim());
+        let (mut items, mut blocked) = self.unprotected_selection("move");
+        // Where each item would land has to be outside protected folders as well
+        items.retain(|(path, _)| {
+            let name = std::path::Path::new(path).file_name().unwrap_or_default();
+            match self.protected_paths.guard("move", &target.join(name)) {
+                Ok(()) => true,
+                Err(e) => {
+                    blocked.push((path.clone(), e));
+                    false
+                }
+            }
+        });
+        let leave_link = self.move_leave_link;
+        info!("Moving {} selected items to {}", items.len(), target.display());
+        let stop_flag = Arc::new(Mutex::new(false));
+        let (sender, receiver) = mpsc::channel();
+        self.move_receiver = Some(receiver);
+        self.move_stop_flag = Some(stop_flag.clone());
+        self.move_progress = Some((0, items.iter().map(|(_, size)| size).sum(), String::new()));
+        self.scan_status = format!("Moving {} items to {}...", items.len(), target.display());
+        
+        std::thread::spawn(move || {
+            let mut results: Vec<(String, Result<Relocated, String>)> = blocked.into_iter().map(|(path, e)| (path, Err(e))).collect();
+            for (path, _) in items {
+                if *stop_flag.lock().unwrap() {
+                    results.push((path, Err("cancelled".to_string())));
+                    continue;
+                }
+                let result = relocate(std::path::Path::new(&path), &target, leave_link, |done, total, current| {
+                    let _ = sender.send(RelocateMessage::Progress { done, total, path: current.to_string() });
+                }, &stop_flag);
+                if let Err(e) = &result {
+                    warn!("Failed to move {}: {}", path, e);
+                }
+                results.push((path, result.map_err(|e| e.to_string())));
+            }
+            let _ = sender.send(RelocateMessage::Finished(results));
+        });
+    }
+    
+    fn check_move_progress(&mut self) {
+        let Some(receiver) = &self.move_receiver else { return };
+        let mut finished = None;
+        while let Ok(message) = receiver.try_recv() {
+            match message {
+                RelocateMessage::Progress { done, total, path } => self.move_progress = Some((done, total, path)),
+                RelocateMessage::Finished(results) => finished = Some(results),
+            }
+        }
+        let Some(results) = finished else { return };
+        self.move_receiver = None;
+        self.move_stop_flag = None;
+        self.move_progress = None;
+        
+        let moved: Vec<(String, Relocated)> = results.iter()
+            .filter_map(|(path, result)| result.as_ref().ok().map(|relocated| (path.clone(), relocated.clone())))
+            .collect();
+        for (path, relocated) in &moved {
+            info!("Moved {} to {} ({}, {} resumed from an earlier run)", path, relocated.target.display(),
+                format_size(relocated.bytes), format_size(relocated.resumed_bytes));
+        }
+        let removed: Vec<(String, u64)> = moved.iter().map(|(path, relocated)| (path.clone(), relocated.bytes)).collect();
+        let freed = self.remove_from_results(&removed);
+        // Failed items stay selected so the move can be resumed
+        self.selected_paths.retain(|path, _| !removed.iter().any(|(gone, _)| std::path::Path::new(path).starts_with(gone)));
+        
+        let failed = results.len() - moved.len();
+        let without_link = moved.iter().filter(|(_, relocated)| relocated.link_error.is_some()).count();
+        let mut status = format!("Moved {} items ({}) to {}", moved.len(), format_size(freed), self.move_target.trim());
+        if failed > 0 {
+            status.push_str(&format!(", {} failed or cancelled (move again to resume)", failed));
+        }
+        if without_link > 0 {
+            status.push_str(&format!(", {} without a link (see log)", without_link));
+        }
+        self.scan_status = status;
+    }
+    
+    fn compress_selected(&mut self) {
+        let (items, blocked) = self.unprotected_selection("compress");
+        let format = self.compress_format;
+        let delete_original = self.compress_delete_original;
+        info!("Compressing {} selected items as {}", items.len(), format.label());
+        let stop_flag = Arc::new(Mutex::new(false));
+        let (sender, receiver) = mpsc::channel();
+        self.compress_receiver = Some(receiver);
+        self.compress_stop_flag = Some(stop_flag.clone());
+        self.compress_progress = Some((0, items.iter().map(|(_, size)| size).sum(), String::new()));
+        self.scan_status = format!("Compressing {} items...", items.len());
+        
+        std::thread::spawn(move || {
+            let mut results: Vec<(String, Result<Compressed, String>)> = blocked.into_iter().map(|(path, e)| (path, Err(e))).collect();
+            for (path, _) in items {
+                if *stop_flag.lock().unwrap() {
+                    results.push((path, Err("cancelled".to_string())));
+                    continue;
+                }
+                let result = compress_in_place(std::path::Path::new(&path), format, delete_original, |done, total, current| {
+                    let _ = sender.send(CompressMessage::Progress { done, total, path: current.to_string() });
+                }, &stop_flag);
+                if let Err(e) = &result {
+                    warn!("Failed to compress {}: {}", path, e);
+                }
+                results.push((path, result.map_err(|e| e.to_string())));
+            }
+            let _ = sender.send(CompressMessage::Finished(results));
+        });
+    }
+    
+    fn check_compress_progress(&mut self) {
+        let Some(receiver) = &self.compress_receiver else { return };
+        let mut finished = None;
+        while let Ok(message) = receiver.try_recv() {
+            match message {
+                CompressMessage::Progress { done, total, path } => self.compress_progress = Some((done, total, path)),
+                CompressMessage::Finished(results) => finished = Some(results),
+            }
+        }
+        let Some(results) = finished else { return };
+        self.compress_receiver = None;
+        self.compress_stop_flag = None;
+        self.compress_progress = None;
+        
+        let compressed: Vec<(&String, &Compressed)> = results.iter()
+            .filter_map(|(path, result)| result.as_ref().ok().map(|archive| (path, archive)))
+            .collect();
+        for (_, archive) in &compressed {
+            info!("Compressed into {} ({} files, {} of {})", archive.archive.display(), archive.files,
+                format_size(archive.archive_size), format_size(archive.original_size));
+            self.add_to_results(&archive.archive, archive.archive_size);
+        }
+        // Also re-ranks, so the new archives show up even when every original was kept
+        let removed: Vec<(String, u64)> = compressed.iter()
+            .filter(|(_, archive)| archive.original_deleted)
+            .map(|(path, archive)| ((*path).clone(), archive.original_size))
+            .collect();
+        self.remove_from_results(&removed);
+        self.selected_paths.retain(|path, _| !removed.iter().any(|(gone, _)| std::path::Path::new(path).starts_with(gone)));
+        
+        let saved: u64 = compressed.iter().map(|(_, archive)| archive.saved()).sum();
+        let archived: u64 = compressed.iter().map(|(_, archive)| archive.archive_size).sum();
+        let mut status = if removed.is_empty() {
+            format!("Compressed {} items into {} of archives, originals kept", compressed.len(), format_size(archived))
+        } else {
+            format!("Compressed {} items into {} of archives, saved {}", compressed.len(), format_size(archived), format_size(saved))
+        };
+        let failed = results.len() - compressed.len();
+        if failed > 0 {
+            status.push_str(&format!(", {} failed or cancelled (see log)", failed));
+        }
+        self.scan_status = status;
+    }
+    
+    /// Add a file created since the scan, such as a new archive, to the retained tree and statistics
+    fn add_to_results(&mut self, path: &std::path::Path, size: u64) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return };
+        let name = name.to_string_lossy().to_string();
+        let category = self.categories.categorize(&name).name.clone();
+        let Some(index) = self.scan_index.as_mut().map(Arc::make_mut) else { return };
+        let Some(parent_node) = index.find(parent) else {
+            warn!("{} is not in the retained scan, leaving {} out of the results", parent.display(), name);
+            return;
+        };
+        index.insert(parent_node, &name, size, Some(std::time::SystemTime::now()), Some(&category));
+        
+        match self.category_stats.iter_mut().find(|stats| stats.name == category) {
+            Some(stats) => {
+                stats.file_count += 1;
+                stats.total_size += size;
+            }
+            None => self.category_stats.push(CategoryStats { name: category, file_count: 1, total_size: size }),
+        }
+        for folder in &mut self.busiest_folders {
+            if parent.starts_with(&folder.path) {
+                folder.file_count = folder.file_count.map(|count| count + 1);
+                if parent == std::path::Path::new(&folder.path) {
+                    folder.direct_file_count = folder.direct_file_count.map(|count| count + 1);
+                }
+            }
+        }
+    }
+    
+    fn quarantine_selected(&mut self) {
+        let (items, blocked) = self.unprotected_selection("quarantine");
+        let paths: Vec<String> = items.into_iter().map(|(path, _)| path).collect();
+        info!("Quarantining {} selected items", paths.len());
+        let (sender, receiver) = mpsc::channel();
+        self.quarantine_receiver = Some(receiver);
+        self.scan_status = format!("Hashing and quarantining {} items...", paths.len());
+        
+        std::thread::spawn(move || {
+            let (quarantined, mut errors) = quarantine_items(&paths);
+            errors.extend(blocked.into_iter().map(|(_, e)| e));
+            let _ = sender.send((quarantined, errors));
+        });
+    }
+    
+    fn check_quarantine_result(&mut self) {
+        let Some(receiver) = &self.quarantine_receiver else { return };
+        let Ok((quarantined, errors)) = receiver.try_recv() else { return };
+        self.quarantine_receiver = None;
+        
+        let removed: Vec<(String, u64)> = quarantined.iter().map(|entry| (entry.original_path.clone(), entry.size)).collect();
+        let held = self.remove_from_results(&removed);
+        self.selected_paths.retain(|path, _| !removed.iter().any(|(gone, _)| std::path::Path::new(path).starts_with(gone)));
+        self.quarantine_areas = load_areas();
+        
+        self.scan_status = if errors.is_empty() {
+            format!("Quarantined {} items ({})", quarantined.len(), format_size(held))
+        } else {
+            format!("Quarantined {} items ({}), {} failed (see log)", quarantined.len(), format_size(held), errors.len())
+        };
+    }
+    
+    fn purge_expired_quarantine(&mut self) {
+        let (purged, errors) = purge_expired(self.quarantine_retention_days);
+        self.quarantine_areas = load_areas();
+        if !purged.is_empty() || !errors.is_empty() {
+            let freed: u64 = purged.iter().map(|entry| entry.size).sum();
+            self.scan_status = format!("Purged {} expired quarantined items ({}), {} failed", purged.len(), format_size(freed), errors.len());
+        }
+    }
+    
+    fn show_quarantine(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🛡 Quarantine");
+        ui.separator();
+        
+        let item_count: usize = self.quarantine_areas.iter().map(|area| area.entries.len()).sum();
+        let held: u64 = self.quarantine_areas.iter().map(QuarantineArea::total_size).sum();
+        ui.horizontal(|ui| {
+            ui.label(format!("{} items pending deletion, holding {}", item_count, format_size(held)));
+            ui.separator();
+            ui.label("Purge after");
+            ui.add(egui::DragValue::new(&mut self.quarantine_retention_days).range(0..=3650).suffix(" days"))
+                .on_hover_text("0 keeps items until purged by hand. Set quarantine.retention_days in the config to keep the change.");
+            if ui.button("🧹 Purge expired").clicked() {
+                self.purge_expired_quarantine();
+            }
+            if ui.button("🔄 Refresh").clicked() {
+                self.quarantine_areas = load_areas();
+            }
+        });
+        ui.separator();
+        
+        if item_count == 0 {
+            ui.label("Nothing in quarantine. Select results in the Overview or Query view and choose Quarantine.");
+            return;
+        }
+        
+        let now = std::time::SystemTime::now();
+        let mut clicked_path = None;
+        let mut restore_entry = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for area in &self.quarantine_areas {
+                    if area.entries.is_empty() {
+                        continue;
+                    }
+                    egui::CollapsingHeader::new(format!("📁 {} ({} items, {})", area.root.display(), area.entries.len(), format_size(area.total_size())))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            egui::Grid::new(area.root.to_string_lossy().to_string()).num_columns(6).striped(true).show(ui, |ui| {
+                                for header in ["Original path", "Size", "Quarantined", "Purge", "Hash", ""] {
+                                    ui.label(egui::RichText::new(header).strong());
+                                }
+                                ui.end_row();
+                                for entry in &area.entries {
+                                    let icon = if entry.is_dir { "📁" } else { "📄" };
+                                    if ui.link(egui::RichText::new(format!("{} {}", icon, entry.original_path)).size(11.0))
+                                        .on_hover_text("Show the quarantined copy")
+                                        .clicked() {
+                                        clicked_path = Some(entry.stored_path(&area.root).to_string_lossy().to_string());
+                                    }
+                                    ui.label(format_size(entry.size));
+                                    ui.label(format_date(entry.quarantined_at));
+                                    ui.label(match entry.expires_at(self.quarantine_retention_days) {
+                                        None => "never".to_string(),
+                                        Some(expires) if expires <= now => "due".to_string(),
+                                        Some(expires) => format!("in {} days", age_in_days(expires, now) + 1),
+                                    });
+                                    ui.label(egui::RichText::new(&entry.hash[..12.min(entry.hash.len())]).monospace().size(10.0))
+                                        .on_hover_text(format!("BLAKE3 {}", entry.hash));
+                                    ui.horizontal(|ui| {
+                                        if ui.small_button("↩ Restore").clicked() {
+                                            restore_entry = Some((area.root.clone(), entry.id.clone()));
+                                        }
+                                        if ui.small_button("🗑 Delete now").clicked() {
+                                            self.confirm_purge = Some((area.root.clone(), entry.id.clone()));
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                }
+            });
+        
+        if let Some(path) = clicked_path {
+            info!("Clicked on quarantined item: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        
+        if let Some((root, id)) = restore_entry {
+            match restore(&root, &id) {
+                Ok(entry) => self.scan_status = format!("Restored {} ({}), rescan to rank it again", entry.original_path, format_size(entry.size)),
+                Err(e) => {
+                    error!("Failed to restore quarantined item {} in {}: {}", id, root.display(), e);
+                    self.scan_status = format!("Error restoring: {}", e);
+                }
+            }
+            self.quarantine_areas = load_areas();
+        }
+        
+        if let Some((root, id)) = self.confirm_purge.clone() {
+            let Some(entry) = self.quarantine_areas.iter()
+                .filter(|area| area.root == root)
+                .flat_map(|area| &area.entries)
+                .find(|entry| entry.id == id)
+                .cloned() else {
+                self.confirm_purge = None;
+                return;
+            };
+            egui::Window::new("Delete from quarantine")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    ui.label(format!("Permanently delete {} ({})? It cannot be restored afterwards.", entry.original_path, format_size(entry.size)));
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            self.confirm_purge = None;
+                            let (purged, errors) = purge(&root, std::slice::from_ref(&id));
+                            self.scan_status = match errors.first() {
+                                None => format!("Deleted {} for good, freed {}", entry.original_path, format_size(purged.iter().map(|entry| entry.size).sum())),
+                                Some(e) => format!("Error deleting: {}", e),
+                            };
+                            self.quarantine_areas = load_areas();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_purge = None;
+                        }
+                    });
+                });
+        }
+    }
+    
+    /// Button that marks an entry for the cleanup plan, showing its action once marked
+    /// Plan action menu of a result row, or a lock when the path is protected. Returns the item marked.
+    fn plan_menu(ui: &mut egui::Ui, plan: &CleanupPlan, protection: &ProtectionCache, path: &str, size: u64, estimated_savings: Option<u64>, source: &str) -> Option<PlanItem> {
+        if let Some(reason) = protection.reason(path) {
+            ui.label("🔒").on_hover_text(format!("Protected: {}", reason));
+            return None;
+        }
+        let current = plan.items.iter().find(|item| item.path == path).map(|item| item.action);
+        let mut marked = None;
+        ui.menu_button(current.map_or("📋", |action| action.label()), |ui| {
+            for action in PlanAction::ALL {
+                if ui.selectable_label(current == Some(action), action.label()).clicked() {
+                    marked = Some(PlanItem {
+                        path: path.to_string(),
+                        size,
+                        action,
+                        estimated_savings,
+                        format: CompressFormat::default(),
+                        delete_original: false,
+                        source: source.to_string(),
+                    });
+                    ui.close();
+                }
+            }
+        })
+        .response
+        .on_hover_text("Add to the cleanup plan");
+        marked
+    }
+    
+    fn mark_in_plan(&mut self, marked: Vec<PlanItem>) {
+        if marked.is_empty() {
+            return;
+        }
+        info!("Marking {} items in the cleanup plan", marked.len());
+        for item in marked {
+            self.cleanup_plan.mark(item);
+        }
+        self.scan_status = format!("Cleanup plan now holds {} items", self.cleanup_plan.items.len());
+        self.plan_projection = None;
+        self.protection.clear();
+    }
+    
+    fn show_cleanup_plan(&mut self, ui: &mut egui::Ui) {
+        ui.heading("📋 Cleanup plan");
+        ui.separator();
+        
+        let running = self.plan_receiver.is_some();
+        ui.horizontal(|ui| {
+            ui.label("Plan file:");
+            ui.add(egui::TextEdit::singleline(&mut self.plan_path)
+                .desired_width(300.0)
+                .hint_text("cleanup-plan.json"));
+            if ui.add_enabled(!self.plan_path.is_empty() && !self.cleanup_plan.items.is_empty(), egui::Button::new("💾 Save")).clicked() {
+                match self.cleanup_plan.save(std::path::Path::new(&self.plan_path)) {
+                    Ok(()) => self.scan_status = format!("Saved cleanup plan to {}", self.plan_path),
+                    Err(e) => {
+                        error!("Failed to save cleanup plan {}: {}", self.plan_path, e);
+                        self.scan_status = format!("Error saving plan: {}", e);
+                    }
+                }
+            }
+            if ui.add_enabled(!self.plan_path.is_empty() && !running, egui::Button::new("📂 Load")).clicked() {
+                match CleanupPlan::load(std::path::Path::new(&self.plan_path)) {
+                    Ok(plan) => {
+                        self.scan_status = format!("Loaded cleanup plan with {} items", plan.items.len());
+                        self.cleanup_plan = plan;
+                        self.plan_projection = None;
+                        self.protection.clear();
+                    }
+                    Err(e) => {
+                        error!("Failed to load cleanup plan {}: {}", self.plan_path, e);
+                        self.scan_status = format!("Error loading plan: {}", e);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Move to:");
+            if ui.add(egui::TextEdit::singleline(&mut self.cleanup_plan.move_destination)
+                .desired_width(300.0)
+                .hint_text("E:\\Archive"))
+                .changed() {
+                self.plan_projection = None;
+            }
+            ui.checkbox(&mut self.cleanup_plan.leave_links, "Leave a link behind");
+        });
+        ui.separator();
+        
+        if self.cleanup_plan.items.is_empty() && self.plan_report.is_none() {
+            ui.label("Mark results with 📋 in the Overview, Duplicates, Stale, Cleanup or Query views, or add a selection, to build a plan.");
+            return;
+        }
+        
+        let projection = self.plan_projection.get_or_insert_with(|| self.cleanup_plan.projection()).clone();
+        let total: u64 = projection.iter().map(|volume| volume.net_freed()).sum();
+        ui.label(egui::RichText::new(format!("{} items, {} projected to be freed", self.cleanup_plan.items.len(), format_size(total))).strong());
+        egui::Grid::new("plan_projection").num_columns(4).striped(true).show(ui, |ui| {
+            for header in ["Volume", "Frees", "Receives", "Net"] {
+                ui.label(egui::RichText::new(header).strong());
+            }
+            ui.end_row();
+            for volume in &projection {
+                ui.label(&volume.volume);
+                ui.label(format_size(volume.freed));
+                ui.label(if volume.added > 0 { format_size(volume.added) } else { "-".to_string() });
+                ui.label(if volume.added > volume.freed {
+                    format!("-{}", format_size(volume.added - volume.freed))
+                } else {
+                    format_size(volume.net_freed())
+                });
+                ui.end_row();
+            }
+        });
+        
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!running && !self.is_scanning && !self.cleanup_plan.items.is_empty(), egui::Button::new("▶ Execute plan")).clicked() {
+                self.confirm_execute_plan = true;
+            }
+            if ui.add_enabled(!running, egui::Button::new("Clear plan")).clicked() {
+                self.cleanup_plan.items.clear();
+                self.plan_projection = None;
+            }
+            if let Some((done, total, path)) = &self.plan_progress {
+                ui.add(egui::ProgressBar::new(*done as f32 / (*total).max(1) as f32)
+                    .desired_width(200.0)
+                    .text(format!("{} / {}", done, total)));
+                ui.label(egui::RichText::new(path).size(10.0).weak());
+                if ui.button("⏹ Cancel").clicked() {
+                    if let Some(stop_flag) = &self.plan_stop_flag {
+                        *stop_flag.lock().unwrap() = true;
+                    }
+                }
+            }
+        });
+        ui.separator();
+        
+        let mut removed_item = None;
+        let mut clicked_path = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                if let Some(report) = &self.plan_report {
+                    egui::CollapsingHeader::new(format!("Last run: freed {}, moved {}, {} failed{}", 
+                        format_size(report.freed()), format_size(report.moved()), report.failed(), if report.cancelled { ", cancelled" } else { "" }))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            egui::Grid::new("plan_report_volumes").num_columns(4).striped(true).show(ui, |ui| {
+                                for header in ["Volume", "Free before", "Free after", "Change"] {
+                                    ui.label(egui::RichText::new(header).strong());
+                                }
+                                ui.end_row();
+                                for volume in &report.volumes {
+                                    ui.label(&volume.volume);
+                                    ui.label(volume.before.map(format_size).unwrap_or_else(|| "-".to_string()));
+                                    ui.label(volume.after.map(format_size).unwrap_or_else(|| "-".to_string()));
+                                    ui.label(match (volume.before, volume.after) {
+                                        (Some(before), Some(after)) if after >= before => format!("+{}", format_size(after - before)),
+                                        (Some(before), Some(after)) => format!("-{}", format_size(before - after)),
+                                        _ => "-".to_string(),
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                            ui.separator();
+                            egui::Grid::new("plan_report_items").num_columns(3).striped(true).show(ui, |ui| {
+                                for item in &report.items {
+                                    ui.label(item.action.label());
+                                    ui.label(egui::RichText::new(&item.path).size(11.0));
+                                    match &item.outcome {
+                                        ItemOutcome::Freed(bytes) => ui.label(egui::RichText::new(format!("freed {}", format_size(*bytes))).color(egui::Color32::LIGHT_GREEN)),
+                                        ItemOutcome::Moved(bytes) => ui.label(format!("moved {}, nothing freed", format_size(*bytes))),
+                                        ItemOutcome::Skipped(reason) => ui.label(egui::RichText::new(format!("skipped: {}", reason)).weak()),
+                                        ItemOutcome::Failed(e) => ui.label(egui::RichText::new(e).color(egui::Color32::LIGHT_RED)),
+                                    };
+                                    ui.end_row();
+                                }
+                            });
+                            if ui.button("💾 Save report").clicked() {
+                                let path = match self.plan_path.strip_suffix(".json") {
+                                    Some(stem) => format!("{}-report.json", stem),
+                                    None if !self.plan_path.is_empty() => format!("{}-report.json", self.plan_path),
+                                    None => "cleanup-report.json".to_string(),
+                                };
+                                match report.save(std::path::Path::new(&path)) {
+                                    Ok(()) => self.scan_status = format!("Saved cleanup report to {}", path),
+                                    Err(e) => {
+                                        error!("Failed to save cleanup report {}: {}", path, e);
+                                        self.scan_status = format!("Error saving report: {}", e);
+                                    }
+                                }
+                            }
+                        });
+                    ui.separator();
+                }
+                
+                egui::Grid::new("plan_items").num_columns(7).striped(true).show(ui, |ui| {
+                    for header in ["Action", "Options", "Size", "Frees", "Path", "From", ""] {
+                        ui.label(egui::RichText::new(header).strong());
+                    }
+                    ui.end_row();
+                    for index in 0..self.cleanup_plan.items.len() {
+                        let covered = self.cleanup_plan.covering_item(&self.cleanup_plan.items[index]).is_some();
+                        let protected = self.protection.reason(&self.cleanup_plan.items[index].path);
+                        let item = &mut self.cleanup_plan.items[index];
+                        let before = item.action;
+                        egui::ComboBox::from_id_salt(("plan_action", index))
+                            .selected_text(item.action.label())
+                            .show_ui(ui, |ui| {
+                                for action in PlanAction::ALL {
+                                    ui.selectable_value(&mut item.action, action, action.label());
+                                }
+                            });
+                        let compress_before = (item.format, item.delete_original);
+                        if item.action == PlanAction::Compress {
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_salt(("plan_format", index))
+                                    .selected_text(item.format.label())
+                                    .show_ui(ui, |ui| {
+                                        for format in CompressFormat::ALL {
+                                            ui.selectable_value(&mut item.format, format, format.label());
+                                        }
+                                    });
+                                ui.checkbox(&mut item.delete_original, "Delete original")
+                                    .on_hover_text("Delete the original once the archive is verified, otherwise nothing is freed");
+                            });
+                        } else {
+                            ui.label("");
+                        }
+                        if item.action != before || (item.format, item.delete_original) != compress_before {
+                            self.plan_projection = None;
+                        }
+                        ui.label(format_size(item.size));
+                        if let Some(reason) = &protected {
+                            ui.label("🔒 nothing").on_hover_text(format!("Protected: {}, skipped when the plan runs", reason));
+                        } else {
+                            ui.label(match item.action {
+                                PlanAction::Compress if !item.delete_original => "nothing, original kept".to_string(),
+                                PlanAction::Compress if item.estimated_savings.is_none() => "unknown".to_string(),
+                                PlanAction::Trash => format!("nothing until the {} is emptied", TRASH_NAME),
+                                PlanAction::Move if !covered => format!("{} unless moved within its volume", format_size(item.size)),
+                                _ if covered => "with its folder".to_string(),
+                                _ => format_size(item.projected_savings()),
+                            });
+                        }
+                        if ui.link(egui::RichText::new(&item.path).size(11.0)).clicked() {
+                            clicked_path = Some(item.path.clone());
+                        }
+                        ui.label(egui::RichText::new(&item.source).size(10.0).weak());
+                        if ui.add_enabled(!running, egui::Button::new("✖").small()).on_hover_text("Remove from the plan").clicked() {
+                            removed_item = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        
+        if let Some(index) = removed_item {
+            self.cleanup_plan.items.remove(index);
+            self.plan_projection = None;
+        }
+        if let Some(path) = clicked_path {
+            info!("Clicked on plan item: {}", path);
+            if let Err(e) = open_in_explorer(&path) {
+                error!("Failed to open Explorer for {}: {}", path, e);
+                self.scan_status = format!("Error opening Explorer: {}", e);
+            }
+        }
+        
+        if self.confirm_execute_plan {
+            egui::Window::new("Execute cleanup plan")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ui.ctx(), |ui| {
+                    for action in PlanAction::ALL {
+                        let items: Vec<&PlanItem> = self.cleanup_plan.items.iter().filter(|item| item.action == action).collect();
+                        if !items.is_empty() {
+                            ui.label(format!("{}: {} items, {}", action.label(), items.len(), format_size(items.iter().map(|item| item.size).sum())));
+                        }
+                    }
+                    let deleting_originals = self.cleanup_plan.items.iter()
+                        .filter(|item| item.action == PlanAction::Compress && item.delete_original)
+                        .count();
+                    if deleting_originals > 0 {
+                        ui.label(format!("{} compressed items will have their original deleted once the archive is verified", deleting_originals));
+                    }
+                    ui.label(format!("About {} should be freed. Deleted items cannot be restored.", format_size(total)));
+                    if self.cleanup_plan.items.iter().any(|item| item.action == PlanAction::Move) && self.cleanup_plan.move_destination.is_empty() {
+                        ui.label(egui::RichText::new("No move destination is set, move items will fail").color(egui::Color32::LIGHT_RED));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Execute").clicked() {
+                            self.confirm_execute_plan = false;
+                            self.execute_cleanup_plan();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_execute_plan = false;
+                        }
+                    });
+                });
+        }
+    }
+    
+    fn execute_cleanup_plan(&mut self) {
+        info!("Executing cleanup plan with {} items", self.cleanup_plan.items.len());
+        let plan = self.cleanup_plan.clone();
+        let protected = self.protected_paths.clone();
+        let stop_flag = Arc::new(Mutex::new(false));
+        let (sender, receiver) = mpsc::channel();
+        self.plan_receiver = Some(receiver);
+        self.plan_stop_flag = Some(stop_flag.clone());
+        self.plan_progress = Some((0, plan.items.len(), String::new()));
+        self.scan_status = format!("Executing cleanup plan ({} items)...", plan.items.len());
+        
+        std::thread::spawn(move || {
+            let report = execute_plan(&plan, &protected, |done, total, path| {
+                let _ = sender.send(PlanMessage::Progress { done, total, path: path.to_string() });
+            }, &stop_flag);
+            let _ = sender.send(PlanMessage::Finished(report));
+        });
+    }
+    
+    fn check_plan_progress(&mut self) {
+        let Some(receiver) = &self.plan_receiver else { return };
+        let mut finished = None;
+        while let Ok(message) = receiver.try_recv() {
+            match message {
+                PlanMessage::Progress { done, total, path } => self.plan_progress = Some((done, total, path)),
+                PlanMessage::Finished(report) => finished = Some(report),
+            }
+        }
+        let Some(report) = finished else { return };
+        self.plan_receiver = None;
+        self.plan_stop_flag = None;
+        self.plan_progress = None;
+        
+        let removed: Vec<(String, u64)> = report.items
+            .iter()
+            .filter(|item| matches!(item.outcome, ItemOutcome::Freed(_) | ItemOutcome::Moved(_)))
+            .filter_map(|item| self.cleanup_plan.items.iter().find(|planned| planned.path == item.path))
+            .map(|item| (item.path.clone(), item.size))
+            .collect();
+        self.remove_from_results(&removed);
+        self.selected_paths.retain(|path, _| !removed.iter().any(|(gone, _)| std::path::Path::new(path).starts_with(gone)));
+        
+        self.scan_status = format!("Cleanup plan {}: freed {}, moved {}, {} failed (see the Plan view)", 
+            if report.cancelled { "cancelled" } else { "finished" }, format_size(report.freed()), format_size(report.moved()), report.failed());
+        self.plan_report = Some(report);
+    }    
+    fn show_audit_log(&mut self, ui: &mut egui::Ui) {
+        ui.heading("📜 Audit log");
+        ui.separator();
+        
+        let entries = self.audit_entries.get_or_insert_with(|| {
+            let mut entries = load_audit_log();
+            entries.reverse();
+            entries
+        });
+        let count = |outcome: AuditOutcome| entries.iter().filter(|entry| entry.outcome == outcome).count();
+        let (done, failed, blocked) = (count(AuditOutcome::Done), count(AuditOutcome::Failed), count(AuditOutcome::Blocked));
+        let mut refresh = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("{} actions done, {} failed, {} blocked", done, failed, blocked))
+                .on_hover_ui(|ui| {
+                    ui.label(format!("Recorded in {}, which is never deleted by the application", audit_file().display()));
+                });
+            if ui.button("🔄 Refresh").clicked() {
+                refresh = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Export to:");
+            ui.add(egui::TextEdit::singleline(&mut self.audit_export_path)
+                .desired_width(300.0)
+                .hint_text("audit-export.csv"));
+            let path = std::path::Path::new(&self.audit_export_path);
+            let ready = !self.audit_export_path.is_empty() && !entries.is_empty();
+            let mut exported = None;
+            if ui.add_enabled(ready, egui::Button::new("📄 CSV")).clicked() {
+                exported = Some(export_csv(entries, path));
+            }
+            if ui.add_enabled(ready, egui::Button::new("🧾 JSON")).clicked() {
+                exported = Some(export_json(entries, path));
+            }
+            match exported {
+                Some(Ok(())) => self.scan_status = format!("Exported {} audit entries to {}", entries.len(), self.audit_export_path),
+                Some(Err(e)) => {
+                    error!("Failed to export the audit log to {}: {}", self.audit_export_path, e);
+                    self.scan_status = format!("Error exporting audit log: {}", e);
+                }
+                None => {}
+            }
+        });
+        ui.separator();
+        
+        if entries.is_empty() {
+            ui.label("No file-modifying actions recorded yet.");
+        } else {
+            let mut clicked_path = None;
+            egui::ScrollArea::both()
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    egui::Grid::new("audit_log").num_columns(7).striped(true).show(ui, |ui| {
+                        for header in ["Time (UTC)", "User", "Action", "Outcome", "Size", "Path", "Detail"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for entry in entries.iter() {
+                            ui.label(egui::RichText::new(format_timestamp(entry.time)).monospace().size(11.0));
+                            ui.label(&entry.user);
+                            ui.label(&entry.action);
+                            let color = match entry.outcome {
+                                AuditOutcome::Done => egui::Color32::LIGHT_GREEN,
+                                AuditOutcome::Failed => egui::Color32::LIGHT_RED,
+                                AuditOutcome::Blocked => egui::Color32::YELLOW,
+                            };
+                            ui.label(egui::RichText::new(entry.outcome.label()).color(color));
+                            ui.label(entry.size.map(format_size).unwrap_or_default());
+                            let link = ui.link(egui::RichText::new(&entry.path).size(11.0));
+                            let link = match &entry.hash {
+                                Some(hash) => link.on_hover_text(format!("BLAKE3 {}", hash)),
+                                None => link,
+                            };
+                            if link.clicked() {
+                                clicked_path = Some(entry.path.clone());
+                            }
+                            ui.label(egui::RichText::new(&entry.detail).size(11.0));
+                            ui.end_row();
+                        }
+                    });
+                });
+            
+            if let Some(path) = clicked_path {
+                info!("Clicked on audited path: {}", path);
+                if let Err(e) = open_in_explorer(&path) {
+                    error!("Failed to open Explorer for {}: {}", path, e);
+                    self.scan_status = format!("Error opening Explorer: {}", e);
+                }
+            }
+        }
+        
+        if refresh {
+            self.audit_entries = None;
+        }
+    }
+}
+
+impl Clone for FatFolderDiscoveryApp {
+    fn clone(&self) -> Self {
+        Self {
+            selected_disk: self.selected_disk.clone(),
+            custom_path: self.custom_path.clone(),
+            available_disks: self.available_disks.clone(),
+            file_limit: self.file_limit,
+            folder_limit: self.folder_limit,
+            categories: self.categories.clone(),
+            protected_paths: self.protected_paths.clone(),
+            protection: self.protection.clone(),
+            category_filter: self.category_filter.clone(),
+            exclude_system_files: self.exclude_system_files,
+            content_detection: self.content_detection,
+            detection_threshold_mb: self.detection_threshold_mb,
+            find_duplicates: self.find_duplicates,
+            duplicate_min_size_mb: self.duplicate_min_size_mb,
+            find_similar_images: self.find_similar_images,
+            image_hash: self.image_hash,
+            image_max_distance: self.image_max_distance,
+            stale_after_days: self.stale_after_days,
+            stale_basis: self.stale_basis,
+            cleanup_rules: self.cleanup_rules.clone(),
+            find_build_artifacts: self.find_build_artifacts,
+            analyze_git_repos: self.analyze_git_repos,
+            inspect_archives: self.inspect_archives,
+            estimate_compression: self.estimate_compression,
+            fat_folders: self.fat_folders.clone(),
+            fat_files: self.fat_files.clone(),
+            category_stats: self.category_stats.clone(),
+            duplicates: self.duplicates.clone(),
+            similar_images: self.similar_images.clone(),
+            empty_items: self.empty_items.clone(),
+            age_buckets: self.age_buckets.clone(),
+            stale_files: self.stale_files.clone(),
+            sparse_files: self.sparse_files.clone(),
+            size_histogram: self.size_histogram.clone(),
+            busiest_folders: self.busiest_folders.clone(),
+            cleanup: self.cleanup.clone(),
+            build_projects: self.build_projects.clone(),
+            git_repositories: self.git_repositories.clone(),
+            vm_images: self.vm_images.clone(),
+            archives: self.archives.clone(),
+            compression_by_category: self.compression_by_category.clone(),
+            scan_index: self.scan_index.clone(),
+            filtered: self.filtered.clone(),
+            selected_paths: self.selected_paths.clone(),
+            confirm_trash: false,
+            confirm_quarantine: false,
+            quarantine_areas: self.quarantine_areas.clone(),
+            quarantine_retention_days: self.quarantine_retention_days,
+            quarantine_receiver: None,
+            confirm_purge: None,
+            move_target: self.move_target.clone(),
+            move_leave_link: self.move_leave_link,
+            confirm_move: false,
+            move_receiver: None,
+            move_stop_flag: None,
+            move_progress: None,
+            confirm_compress: false,
+            compress_format: self.compress_format,
+            compress_delete_original: self.compress_delete_original,
+            compress_receiver: None,
+            compress_stop_flag: None,
+            compress_progress: None,
+            cleanup_plan: self.cleanup_plan.clone(),
+            plan_projection: self.plan_projection.clone(),
+            plan_path: self.plan_path.clone(),
+            plan_receiver: None,
+            plan_stop_flag: None,
+            plan_progress: None,
+            plan_report: self.plan_report.clone(),
+            confirm_execute_plan: false,
+            audit_entries: self.audit_entries.clone(),
+            audit_export_path: self.audit_export_path.clone(),
+            current_view: self.current_view,
+            is_scanning: self.is_scanning,
+            scan_progress: self.scan_progress,
+            scan_status: self.scan_status.clone(),
+            thumbnail_textures: self.thumbnail_textures.clone(),
+            confirm_remove_empty: false,
+            histogram_by_bytes: self.histogram_by_bytes,
+            folders_by_file_count: self.folders_by_file_count,
+            confirm_clean_project: None,
+            selected_archive: self.selected_archive,
+            archive_folder: self.archive_folder.clone(),
+            archive_open_path: self.archive_open_path.clone(),
+            archive_receiver: None,
+            filter_bar: self.filter_bar.clone(),
+            filter_error: self.filter_error.clone(),
+            query_text: self.query_text.clone(),
+            query_output: self.query_output.clone(),
+            query_error: self.query_error.clone(),
+            snapshot_path: self.snapshot_path.clone(),
+            scanner: None, // Don't clone scanner
+            scan_stop_flag: None,
+            scan_receiver: None, // Don't clone channels
+            scan_sender: None, // Don't clone channels
+            scan_completion_receiver: None, // Don't clone channels
+        }
+    }
+}
+
+impl Drop for FatFolderDiscoveryApp {
+    fn drop(&mut self) {
+        info!("Application shutting down, cleaning up logs...");
+        cleanup_logs();
+    }
+}
+
+impl eframe::App for FatFolderDiscoveryApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Check for scan results
+        self.check_scan_results();
+        self.check_archive_listing();
+        self.check_quarantine_result();
+        self.check_move_progress();
+        self.check_compress_progress();
+        self.check_plan_progress();
+        
+        // Top panel with controls
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // Disk selector
+                ui.label("Disk:");
+                let old_disk = self.selected_disk.clone();
+                egui::ComboBox::from_id_salt("disk_selector")
+                    .selected_text(&self.selected_disk)
+                    .show_ui(ui, |ui| {
+                        for disk in &self.available_disks {
+                            ui.selectable_value(&mut self.selected_disk, disk.clone(), disk);
+                        }
+                    });
+                
+                // Provide feedback when disk changes
+                if old_disk != self.selected_disk && !self.selected_disk.is_empty() {
+                    self.scan_status = format!("Selected disk: {}", self.selected_disk);
+                }
+                
+                ui.separator();
+                
+                // Path input
+                ui.label("Path:");
+                let old_path = self.custom_path.clone();
+                ui.text_edit_singleline(&mut self.custom_path);
+                
+                // Provide feedback when path changes
+                if old_path != self.custom_path {
+                    if self.custom_path.is_empty() {
+                        self.scan_status = format!("Path cleared - will scan root of {}", self.selected_disk);
+                    } else {
+                        self.scan_status = format!("Custom path: {}", self.custom_path);
+                    }
+                }
+                
+                // Auto-select disk from path
+                if !self.custom_path.is_empty() {
+                    if let Some(disk_letter) = self.custom_path.chars().next() {
+                        let disk_str = format!("{}:", disk_letter.to_uppercase());
+                        if self.available_disks.contains(&disk_str) {
+                            self.selected_disk = disk_str;
+                        }
+                    }
+                }
+                
+                // Scan button
+                let scan_button_text = if self.is_scanning { "Stop" } else { "Scan" };
+                if ui.button(scan_button_text).clicked() {
+                    if self.is_scanning {
+                        self.stop_scan();
+                    } else {
+                        self.start_scan();
+                    }
+                }
+                
+                // Refresh disks button
+                if ui.button("🔄").clicked() {
+                    self.scan_status = "Refreshing disk list...".to_string();
+                    self.refresh_disks();
+                }
+            });
+            
+            // Configuration row
+            ui.horizontal(|ui| {
+                ui.label("File limit:");
+                let old_file_limit = self.file_limit;
+                ui.add(egui::Slider::new(&mut self.file_limit, 5..=20));
+                
+                ui.label("Folder limit:");
+                let old_folder_limit = self.folder_limit;
+                ui.add(egui::Slider::new(&mut self.folder_limit, 5..=20));
+                
+                // Provide feedback when limits change
+                if old_file_limit != self.file_limit || old_folder_limit != self.folder_limit {
+                    self.scan_status = format!("Limits updated: {} files, {} folders", 
+                        self.file_limit, self.folder_limit);
+                }
+                
+                // Category filter for the file ranking
+                ui.label("Category:");
+                let old_filter = self.category_filter.clone();
+                egui::ComboBox::from_id_salt("category_filter")
+                    .selected_text(self.category_filter.as_deref().unwrap_or("All"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.category_filter, None, "All");
+                        for category in self.categories.all() {
+                            ui.selectable_value(
+                                &mut self.category_filter,
+                                Some(category.name.clone()),
+                                format!("{} {}", category.icon, category.name),
+                            );
+                        }
+                    });
+                
+                if old_filter != self.category_filter {
+                    self.scan_status = format!("File ranking filter: {} (applies to next scan)", 
+                        self.category_filter.as_deref().unwrap_or("All"));
+                }
+                
+                if ui.checkbox(&mut self.exclude_system_files, "Hide system files")
+                    .on_hover_text("Leave page, hibernation and swap files out of the file rankings")
+                    .changed()
+                {
+                    self.scan_status = "System file filter updated (applies to next scan)".to_string();
+                }
+                
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(&self.scan_status);
+                    if self.is_scanning {
+                        // Animate progress bar
+                        self.scan_progress = (self.scan_progress + 0.01) % 1.0;
+                        ui.add(egui::ProgressBar::new(self.scan_progress));
+                    }
+                });
+            });
+            
+            // Analysis options row
+            ui.horizontal(|ui| {
+                ui.label("Detect types:");
+                let old_detection = self.content_detection;
+                let threshold = self.detection_threshold_mb * 1024 * 1024;
+                egui::ComboBox::from_id_salt("content_detection")
+                    .selected_text(match self.content_detection {
+                        ContentDetection::Off => "Off",
+                        ContentDetection::TopFiles => "Top files",
+                        ContentDetection::LargerThan(_) => "Large files",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.content_detection, ContentDetection::Off, "Off");
+                        ui.selectable_value(&mut self.content_detection, ContentDetection::TopFiles, "Top files");
+                        ui.selectable_value(&mut self.content_detection, ContentDetection::LargerThan(threshold), "Large files");
+                    });
+                
+                if let ContentDetection::LargerThan(_) = self.content_detection {
+                    ui.label("≥");
+                    ui.add(egui::DragValue::new(&mut self.detection_threshold_mb).range(1..=1_000_000).suffix(" MB"));
+                    self.content_detection = ContentDetection::LargerThan(self.detection_threshold_mb * 1024 * 1024);
+                }
+                
+                if old_detection != self.content_detection {
+                    self.scan_status = "Content type detection updated (applies to next scan)".to_string();
+                }
+                
+                ui.separator();
+                
+                let old_duplicates = (self.find_duplicates, self.duplicate_min_size_mb);
+                ui.checkbox(&mut self.find_duplicates, "Find duplicates");
+                if self.find_duplicates {
+                    ui.label("≥");
+                    ui.add(egui::DragValue::new(&mut self.duplicate_min_size_mb).range(0..=1_000_000).suffix(" MB"));
+                }
+                
+                if old_duplicates != (self.find_duplicates, self.duplicate_min_size_mb) {
+                    self.scan_status = "Duplicate detection updated (applies to next scan)".to_string();
+                }
+                
+                ui.separator();
+                
+                let old_images = (self.find_similar_images, self.image_hash, self.image_max_distance);
+                ui.checkbox(&mut self.find_similar_images, "Similar images");
+                if self.find_similar_images {
+                    egui::ComboBox::from_id_salt("image_hash")
+                        .width(60.0)
+                        .selected_text(self.image_hash.label())
+                        .show_ui(ui, |ui| {
+                            for algorithm in [HashAlgorithm::Average, HashAlgorithm::Difference, HashAlgorithm::Perceptual] {
+                                ui.selectable_value(&mut self.image_hash, algorithm, algorithm.label());
+                            }
+                        });
+                    ui.label("≤");
+                    ui.add(egui::DragValue::new(&mut self.image_max_distance).range(0..=32).suffix(" bits"))
+                        .on_hover_text("Maximum Hamming distance between hashes of similar images");
+                }
+                
+                if old_images != (self.find_similar_images, self.image_hash, self.image_max_distance) {
+                    self.scan_status = "Similar image detection updated (applies to next scan)".to_string();
+                }
+                
+                ui.separator();
+                
+                if ui.checkbox(&mut self.find_build_artifacts, "Build artifacts")
+                    .on_hover_text("Find projects by Cargo.toml, package.json, pyproject.toml, *.csproj or build.gradle and measure their build output")
+                    .changed()
+                {
+                    self.scan_status = "Build artifact detection updated (applies to next scan)".to_string();
+                }
+                
+                if ui.checkbox(&mut self.analyze_git_repos, "Git repos")
+                    .on_hover_text("Break .git folders down into packfiles, loose objects, LFS objects and worktrees")
+                    .changed()
+                {
+                    self.scan_status = "Git repository analysis updated (applies to next scan)".to_string();
+                }
+                
+                if ui.checkbox(&mut self.inspect_archives, "Archives")
+                    .on_hover_text("List the contents of zip, tar, tar.gz and tar.zst files in the file ranking")
+                    .changed()
+                {
+                    self.scan_status = "Archive inspection updated (applies to next scan)".to_string();
+                }
+                
+                if ui.checkbox(&mut self.estimate_compression, "Compressibility")
+                    .on_hover_text("Compress samples of the ranked files and folders to estimate potential savings")
+                    .changed()
+                {
+                    self.scan_status = "Compressibility estimation updated (applies to next scan)".to_string();
+                }
+            });
+        });
+        
+        // Main content area - Fixed size: 800x500 (600 - 60 top - 40 bottom), view tabs on top
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.selectable_value(&mut self.current_view, ResultView::Overview, "📊 Overview");
+                ui.selectable_value(&mut self.current_view, ResultView::Categories, "🗂 Categories");
+                ui.selectable_value(&mut self.current_view, ResultView::Duplicates, "👥 Duplicates");
+                ui.selectable_value(&mut self.current_view, ResultView::SimilarImages, "🖼 Similar images");
+                ui.selectable_value(&mut self.current_view, ResultView::EmptyItems, "🕳 Empty");
+                ui.selectable_value(&mut self.current_view, ResultView::Stale, "⏳ Stale");
+                ui.selectable_value(&mut self.current_view, ResultView::Sparse, "🕸 Sparse");
+                ui.selectable_value(&mut self.current_view, ResultView::SizeHistogram, "📶 Sizes");
+                ui.selectable_value(&mut self.current_view, ResultView::Cleanup, "🧹 Cleanup");
+                ui.selectable_value(&mut self.current_view, ResultView::BuildArtifacts, "🛠 Build artifacts");
+                ui.selectable_value(&mut self.current_view, ResultView::GitRepos, "🌿 Git");
+                ui.selectable_value(&mut self.current_view, ResultView::VmImages, "💽 VM images");
+                ui.selectable_value(&mut self.current_view, ResultView::Archives, "📦 Archives");
+                ui.selectable_value(&mut self.current_view, ResultView::Query, "⌨ Query");
+                ui.selectable_value(&mut self.current_view, ResultView::Quarantine, "🛡 Quarantine");
+                ui.selectable_value(&mut self.current_view, ResultView::Plan, "📋 Plan");
+                if ui.selectable_value(&mut self.current_view, ResultView::Audit, "📜 Audit").clicked() {
+                    self.audit_entries = None;
+                }
+            });
+            ui.separator();
+            
+            match self.current_view {
+                ResultView::Overview => self.show_overview(ui),
+                ResultView::Categories => self.show_categories(ui),
+                ResultView::Duplicates => self.show_duplicates(ui),
+                ResultView::SimilarImages => self.show_similar_images(ui),
+                ResultView::EmptyItems => self.show_empty_items(ui),
+                ResultView::Stale => self.show_stale_files(ui),
+                ResultView::Sparse => self.show_sparse_files(ui),
+                ResultView::SizeHistogram => self.show_size_histogram(ui),
+                ResultView::Cleanup => self.show_cleanup(ui),
+                ResultView::BuildArtifacts => self.show_build_artifacts(ui),
+                ResultView::GitRepos => self.show_git_repositories(ui),
+                ResultView::VmImages => self.show_vm_images(ui),
+                ResultView::Archives => self.show_archives(ui),
+                ResultView::Query => self.show_query(ui),
+                ResultView::Quarantine => self.show_quarantine(ui),
+                ResultView::Plan => self.show_cleanup_plan(ui),
+                ResultView::Audit => self.show_audit_log(ui),
+            }
+        });
+        
+        // Status bar panel
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // Status information
+                ui.label("Status:");
+                ui.label(egui::RichText::new(&self.scan_status).color(egui::Color32::LIGHT_BLUE));
+                
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    // Show current configuration
+                    ui.label(format!("Limits: {} files, {} folders", self.file_limit, self.folder_limit));
+                    ui.separator();
+                    ui.label(format!("Disk: {}", self.selected_disk));
+                    ui.separator();
+                    let protection = if self.protected_paths.policy_error().is_some() {
+                        egui::RichText::new("⚠ Protections unavailable").color(egui::Color32::LIGHT_RED)
+                    } else if self.protected_paths.defaults_only() {
+                        egui::RichText::new("🔒 Default protections only")
+                    } else {
+                        egui::RichText::new("🔒 Protections from config")
+                    };
+                    ui.label(protection).on_hover_text(self.protected_paths.summary());
+                });
+            });
+        });
+        
+        // Bottom panel with close button
+        egui::TopBottomPanel::bottom("bottom").show(ctx, |ui| {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Close").clicked() {
+                    self.stop_scan();
+                    info!("User requested application close, cleaning up...");
+                    cleanup_logs();
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+        
+        // Request repaint for progress updates
+        if self.is_scanning {
+            // More frequent repaints during scanning for real-time updates
+            ctx.request_repaint_after(Duration::from_millis(16)); // ~60 FPS
+        }
+        
+        // Always request repaint to ensure UI updates are visible
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
+}
+
 fn main() -> Result<(), eframe::Error> {
     // Initialize logging
     if let Err(e) = init_logging() {
@@ -476,6 +3419,15 @@ fn main() -> Result<(), eframe::Error> {
         std::process::exit(0);
     }).expect("Error setting Ctrl+C handler");
     
+    // Any argument switches to headless mode (scan, snapshot and query from the command line)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        info!("Running headless with arguments {:?}", args);
+        let code = cli::run(&args);
+        cleanup_logs();
+        std::process::exit(code);
+    }
+    
     info!("Starting Fat Folder Discovery application");
     
     let options = eframe::NativeOptions {
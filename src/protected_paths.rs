@@ -0,0 +1,279 @@
+use std::path::{Component, Path, PathBuf};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::record_blocked;
+use crate::cleanup_rules::{normalize_path, CompiledPattern};
+use crate::config::AppConfig;
+
+/// `protected` section of the user config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProtectedConfig {
+    /// Folders or files that may not be touched, including everything inside them
+    pub paths: Vec<String>,
+    /// Patterns in the cleanup rule syntax (`*`, `**`, `~`, `%VAR%`); matches are protected with their contents
+    pub patterns: Vec<String>,
+}
+
+/// One protected location, normalized like cleanup rule paths
+struct Protection {
+    path: String,
+    /// Everything inside is protected too, otherwise only the location itself
+    contents: bool,
+    reason: String,
+}
+
+/// Locations that no trash, delete, move or compress action may touch: built-in OS roots,
+/// user profiles and program folders plus the user's own paths and patterns.
+/// A protected location also protects every folder above it, since removing those would remove it.
+pub struct ProtectedPaths {
+    protections: Vec<Protection>,
+    patterns: Vec<(CompiledPattern, String)>,
+    /// Why the user's policy could not be read; every path is refused while set
+    policy_error: Option<String>,
+    /// No paths or patterns came from the user's config
+    defaults_only: bool,
+    /// Where the policy came from, for status lines
+    summary: String,
+}
+
+impl ProtectedPaths {
+    pub fn with_user_config(config: &ProtectedConfig) -> Self {
+        let mut protections = builtin_protections();
+        for path in config.paths.iter().filter(|path| !path.trim().is_empty()) {
+            protections.push(Protection {
+                path: normalize_path(&resolve(Path::new(path.trim())).to_string_lossy()),
+                contents: true,
+                reason: format!("protected by the config ({})", path.trim()),
+            });
+        }
+        let patterns: Vec<(CompiledPattern, String)> = config.patterns
+            .iter()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .map(|pattern| (CompiledPattern::new(pattern.trim()), format!("matches protected pattern {}", pattern.trim())))
+            .collect();
+        
+        info!("Protected paths ready: {} locations, {} patterns", protections.len(), patterns.len());
+        let defaults_only = config.paths.is_empty() && config.patterns.is_empty();
+        let summary = if defaults_only {
+            "Protected paths: built-in defaults only".to_string()
+        } else {
+            format!("Protected paths: built-in plus {} paths and {} patterns", config.paths.len(), config.patterns.len())
+        };
+        Self { protections, patterns, policy_error: None, defaults_only, summary }
+    }
+    
+    /// The policy of a loaded config. An unreadable `protected` section blocks everything.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut protected = Self::with_user_config(&config.protected);
+        protected.summary = match (&config.protected_error, &config.source) {
+            (Some(e), _) => {
+                warn!("Protected-path policy unavailable, refusing all destructive actions: {}", e);
+                protected.policy_error = Some(e.clone());
+                format!("Protected-path policy unreadable, destructive actions refused: {}", e)
+            }
+            (None, Some(source)) => format!("{} (from {})", protected.summary, source.display()),
+            (None, None) => format!("{} (no config file)", protected.summary),
+        };
+        protected
+    }
+    
+    /// One line saying where the policy came from
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+    
+    pub fn policy_error(&self) -> Option<&str> {
+        self.policy_error.as_deref()
+    }
+    
+    pub fn defaults_only(&self) -> bool {
+        self.defaults_only
+    }
+    
+    /// Why `path` may not be touched, `None` when it may. The path is checked as written, where it
+    /// really is once `..` and linked folders are resolved, and, for a link, where it points.
+    pub fn reason(&self, path: &Path) -> Option<String> {
+        if let Some(e) = &self.policy_error {
+            return Some(format!("the protected-path policy is unavailable ({})", e));
+        }
+        resolved_forms(path).iter().find_map(|form| self.reason_for(form))
+    }
+    
+    fn reason_for(&self, path: &Path) -> Option<String> {
+        if path.parent().is_none() {
+            return Some("volume root".to_string());
+        }
+        let normalized = normalize_path(&path.to_string_lossy());
+        let inside = |parent: &str, child: &str| child.len() > parent.len() && child.starts_with(parent) && child.as_bytes()[parent.len()] == b'/';
+        
+        for protection in &self.protections {
+            if normalized == protection.path
+                || inside(&normalized, &protection.path)
+                || (protection.contents && inside(&protection.path, &normalized)) {
+                return Some(protection.reason.clone());
+            }
+        }
+        
+        // Patterns protect matching paths and their contents, so the path and each folder above it are checked
+        let segments: Vec<&str> = normalized.split('/').collect();
+        (1..=segments.len())
+            .find_map(|length| self.patterns.iter().find(|(pattern, _)| pattern.matches(&segments[..length])))
+            .map(|(_, reason)| reason.clone())
+    }
+    
+    /// Refuse an action on a protected path, recording the attempt in the audit log
+    pub fn guard(&self, action: &str, path: &Path) -> Result<(), String> {
+        match self.reason(path) {
+            Some(reason) => {
+                warn!("Blocked {} of protected path {}: {}", action, path.display(), reason);
+                record_blocked(action, &path.to_string_lossy(), &reason);
+                Err(format!("{}: protected, {}", path.display(), reason))
+            }
+            None => Ok(()),
+        }
+    }
+    
+    /// Split paths into those an action may touch and one message per blocked path
+    pub fn check(&self, action: &str, paths: &[String]) -> (Vec<String>, Vec<String>) {
+        let mut allowed = Vec::new();
+        let mut blocked = Vec::new();
+        for path in paths {
+            match self.guard(action, Path::new(path)) {
+                Ok(()) => allowed.push(path.clone()),
+                Err(e) => blocked.push(e),
+            }
+        }
+        (allowed, blocked)
+    }
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self::with_user_config(&ProtectedConfig::default())
+    }
+}
+
+/// Absolute forms of `path` to check: as written without `..`, with its folder resolved
+/// (where a link itself lives) and fully resolved (where a link points)
+fn resolved_forms(path: &Path) -> Vec<PathBuf> {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let location = match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => resolve(parent).join(name),
+        _ => resolve(&absolute),
+    };
+    let mut forms = vec![lexically_normal(&absolute), location, resolve(&absolute)];
+    forms.dedup();
+    forms
+}
+
+/// Canonical path, or for a path that does not exist, its nearest existing folder
+/// canonicalized with the rest appended
+fn resolve(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let lexical = lexically_normal(&absolute);
+    for existing in lexical.ancestors() {
+        if let Ok(canonical) = std::fs::canonicalize(existing) {
+            let rest = lexical.strip_prefix(existing).unwrap_or(Path::new(""));
+            return without_verbatim_prefix(canonical).join(rest);
+        }
+    }
+    lexical
+}
+
+/// Drop `.` and apply `..` without touching the file system
+fn lexically_normal(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    normal
+}
+
+/// `canonicalize` gives `\\?\C:\...` on Windows, which would never match a protection
+fn without_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let text = path.to_string_lossy();
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        PathBuf::from(format!(r"\\{}", rest))
+    } else if let Some(rest) = text.strip_prefix(r"\\?\") {
+        PathBuf::from(rest)
+    } else {
+        path
+    }
+}
+
+/// Protections are resolved like checked paths, so a linked system folder is matched where it really is
+fn protection(path: &str, contents: bool, reason: &str) -> Protection {
+    Protection {
+        path: normalize_path(&resolve(Path::new(path)).to_string_lossy()),
+        contents,
+        reason: reason.to_string(),
+    }
+}
+
+/// Each folder in `parent` as its own location, such as every user profile
+fn each_folder_in(parent: &str, reason: &str) -> Vec<Protection> {
+    std::fs::read_dir(parent)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .map(|entry| protection(&entry.path().to_string_lossy(), false, reason))
+            .collect())
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn builtin_protections() -> Vec<Protection> {
+    let variable = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let system_drive = variable("SystemDrive").unwrap_or_else(|| "C:".to_string());
+    let users = format!("{}\\Users", system_drive);
+    
+    let mut protections = vec![
+        protection(&variable("SystemRoot").unwrap_or_else(|| format!("{}\\Windows", system_drive)), true, "Windows system folder"),
+        protection(&users, false, "user profiles folder"),
+        protection(&variable("ProgramData").unwrap_or_else(|| format!("{}\\ProgramData", system_drive)), false, "shared application data"),
+    ];
+    for name in ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"] {
+        if let Some(folder) = variable(name) {
+            protections.push(protection(&folder, true, "installed programs"));
+        }
+    }
+    protections.extend(each_folder_in(&users, "user profile"));
+    if let Some(profile) = variable("USERPROFILE") {
+        protections.push(protection(&profile, false, "user profile"));
+    }
+    protections
+}
+
+#[cfg(unix)]
+fn builtin_protections() -> Vec<Protection> {
+    let mut protections: Vec<Protection> = [
+        "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/libx32", "/proc", "/run", "/sbin", "/sys", "/usr",
+        "/System", "/private/etc", "/private/var/db",
+    ]
+    .iter()
+    .map(|path| protection(path, true, "operating system folder"))
+    .collect();
+    for path in ["/opt", "/snap", "/Applications", "/Library"] {
+        protections.push(protection(path, true, "installed programs"));
+    }
+    for path in ["/var/lib/dpkg", "/var/lib/rpm", "/var/lib/pacman"] {
+        protections.push(protection(path, true, "package database"));
+    }
+    for path in ["/home", "/Users", "/root", "/var", "/srv", "/mnt", "/media", "/tmp"] {
+        protections.push(protection(path, false, "top-level system folder"));
+    }
+    protections.extend(each_folder_in("/home", "user profile"));
+    protections.extend(each_folder_in("/Users", "user profile"));
+    if let Some(home) = std::env::var_os("HOME") {
+        protections.push(protection(&home.to_string_lossy(), false, "user profile"));
+    }
+    protections
+}
@@ -0,0 +1,323 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{record_done, record_failed};
+use crate::config::data_file;
+use crate::file_age::SECONDS_PER_DAY;
+use crate::file_utils::{content_hash, total_size, volume_root};
+
+/// Folder created at the top of each volume that holds quarantined items
+pub const QUARANTINE_FOLDER: &str = ".fat-folder-quarantine";
+const MANIFEST_FILE: &str = "manifest.json";
+const ITEMS_FOLDER: &str = "items";
+/// Quarantine folders in use, kept in the per-user data folder so every volume's quarantine can be listed
+const QUARANTINE_REGISTRY: &str = "fat-folder-quarantine.json";
+
+/// `quarantine` section of the user config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuarantineConfig {
+    /// Days before quarantined items are purged, 0 keeps them until purged by hand
+    pub retention_days: u64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+/// One quarantined file or folder as recorded in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub id: String,
+    pub original_path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    /// `content_hash` taken before the move, checked again on restore
+    pub hash: String,
+    pub quarantined_at: SystemTime,
+}
+
+impl QuarantineEntry {
+    /// When the entry becomes due for purging, `None` if retention is off
+    pub fn expires_at(&self, retention_days: u64) -> Option<SystemTime> {
+        (retention_days > 0).then(|| self.quarantined_at + Duration::from_secs(retention_days * SECONDS_PER_DAY))
+    }
+    
+    /// Where the item lives while quarantined
+    pub fn stored_path(&self, root: &Path) -> PathBuf {
+        let name = Path::new(&self.original_path).file_name().map(|name| name.to_os_string()).unwrap_or_else(|| self.id.clone().into());
+        root.join(ITEMS_FOLDER).join(&self.id).join(name)
+    }
+}
+
+/// The quarantine folder of one volume and its manifest
+#[derive(Debug, Clone, Default)]
+pub struct QuarantineArea {
+    pub root: PathBuf,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+impl QuarantineArea {
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.size).sum()
+    }
+}
+
+/// Quarantine folder at the top of the path's volume, so moving there never copies data.
+/// On Unix the home folder is used instead when it shares the volume.
+pub fn quarantine_root(path: &Path) -> PathBuf {
+    #[cfg(unix)]
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        if crate::file_utils::same_volume(&home, path) {
+            return home.join(QUARANTINE_FOLDER);
+        }
+    }
+    volume_root(path).join(QUARANTINE_FOLDER)
+}
+
+/// Move items into their volume's quarantine, recording each in the manifest.
+/// Returns the new manifest entries and one message per failure.
+pub fn quarantine_items(paths: &[String]) -> (Vec<QuarantineEntry>, Vec<String>) {
+    let mut quarantined = Vec::new();
+    let mut errors = Vec::new();
+    
+    for path in paths {
+        match quarantine_one(Path::new(path)) {
+            Ok(entry) => {
+                record_done("quarantine", path, Some(entry.size), Some(&entry.hash), &format!("held as {}", entry.id));
+                quarantined.push(entry);
+            }
+            Err(e) => {
+                record_failed("quarantine", path, total_size(Path::new(path)).ok(), &e.to_string());
+                errors.push(format!("{}: {}", path, e));
+            }
+        }
+    }
+    
+    info!("Quarantined {} items, {} errors", quarantined.len(), errors.len());
+    for error in &errors {
+        warn!("Failed to quarantine: {}", error);
+    }
+    
+    (quarantined, errors)
+}
+
+fn quarantine_one(path: &Path) -> Result<QuarantineEntry, Box<dyn std::error::Error>> {
+    let metadata = fs::symlink_metadata(path)?;
+    let root = quarantine_root(path);
+    if path.starts_with(&root) {
+        return Err("already in quarantine".into());
+    }
+    let size = total_size(path)?;
+    info!("Hashing {} ({} bytes) before quarantine", path.display(), size);
+    let hash = content_hash(path)?;
+    
+    let items = root.join(ITEMS_FOLDER);
+    fs::create_dir_all(&items)?;
+    register_area(&root);
+    
+    // A fresh folder per item keeps the original name and avoids collisions
+    let now = SystemTime::now();
+    let mut stamp = now.duration_since(UNIX_EPOCH).map(|age| age.as_millis()).unwrap_or(0);
+    let id = loop {
+        let id = format!("{:x}", stamp);
+        match fs::create_dir(items.join(&id)) {
+            Ok(()) => break id,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => stamp += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    
+    let entry = QuarantineEntry {
+        id,
+        original_path: std::path::absolute(path)?.to_string_lossy().to_string(),
+        size,
+        is_dir: metadata.is_dir(),
+        hash,
+        quarantined_at: now,
+    };
+    // An unreadable manifest must stop the move, or the item could not be found again
+    let mut entries = match load_manifest(&root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            let _ = fs::remove_dir(items.join(&entry.id));
+            return Err(e);
+        }
+    };
+    if let Err(e) = fs::rename(path, entry.stored_path(&root)) {
+        let _ = fs::remove_dir(items.join(&entry.id));
+        return Err(e.into());
+    }
+    
+    entries.push(entry.clone());
+    if let Err(e) = save_manifest(&root, &entries) {
+        // Without a manifest entry the item could not be found again, so put it back
+        error!("Failed to record {} in the quarantine manifest: {}", entry.original_path, e);
+        let _ = fs::rename(entry.stored_path(&root), path);
+        let _ = fs::remove_dir(items.join(&entry.id));
+        return Err(e);
+    }
+    info!("Quarantined {} as {}", entry.original_path, entry.id);
+    Ok(entry)
+}
+
+/// Move an item back to where it came from after checking it is unchanged
+pub fn restore(root: &Path, id: &str) -> Result<QuarantineEntry, Box<dyn std::error::Error>> {
+    let result = restore_verified(root, id);
+    match &result {
+        Ok(entry) => record_done("restore", &entry.original_path, Some(entry.size), Some(&entry.hash), &format!("from {}", root.display())),
+        Err(e) => record_failed("restore", &format!("{} in {}", id, root.display()), None, &e.to_string()),
+    }
+    result
+}
+
+fn restore_verified(root: &Path, id: &str) -> Result<QuarantineEntry, Box<dyn std::error::Error>> {
+    let mut entries = load_manifest(root)?;
+    let position = entries.iter().position(|entry| entry.id == id).ok_or("not in the quarantine manifest")?;
+    let entry = entries[position].clone();
+    let original = Path::new(&entry.original_path);
+    if fs::symlink_metadata(original).is_ok() {
+        return Err(format!("{} already exists", entry.original_path).into());
+    }
+    
+    let stored = entry.stored_path(root);
+    if content_hash(&stored)? != entry.hash {
+        return Err("content changed while in quarantine".into());
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&stored, original)?;
+    let _ = fs::remove_dir(root.join(ITEMS_FOLDER).join(&entry.id));
+    
+    entries.remove(position);
+    save_manifest(root, &entries)?;
+    info!("Restored {} from quarantine", entry.original_path);
+    Ok(entry)
+}
+
+/// Permanently delete quarantined items. Returns the purged entries and one message per failure.
+pub fn purge(root: &Path, ids: &[String]) -> (Vec<QuarantineEntry>, Vec<String>) {
+    let mut purged = Vec::new();
+    let mut errors = Vec::new();
+    let mut entries = match load_manifest(root) {
+        Ok(entries) => entries,
+        Err(e) => return (purged, vec![format!("{}: {}", root.display(), e)]),
+    };
+    
+    entries.retain(|entry| {
+        if !ids.contains(&entry.id) {
+            return true;
+        }
+        match fs::remove_dir_all(root.join(ITEMS_FOLDER).join(&entry.id)) {
+            Ok(()) => {
+                record_done("purge", &entry.original_path, Some(entry.size), Some(&entry.hash), "deleted from quarantine");
+                purged.push(entry.clone());
+                false
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                record_done("purge", &entry.original_path, Some(entry.size), Some(&entry.hash), "already gone from quarantine");
+                purged.push(entry.clone());
+                false
+            }
+            Err(e) => {
+                record_failed("purge", &entry.original_path, Some(entry.size), &e.to_string());
+                errors.push(format!("{}: {}", entry.original_path, e));
+                true
+            }
+        }
+    });
+    if let Err(e) = save_manifest(root, &entries) {
+        errors.push(format!("{}: {}", root.display(), e));
+    }
+    
+    info!("Purged {} quarantined items from {}, {} errors", purged.len(), root.display(), errors.len());
+    for error in &errors {
+        warn!("Failed to purge quarantined item: {}", error);
+    }
+    (purged, errors)
+}
+
+/// Purge every item older than the retention period, across all volumes
+pub fn purge_expired(retention_days: u64) -> (Vec<QuarantineEntry>, Vec<String>) {
+    let mut purged = Vec::new();
+    let mut errors = Vec::new();
+    if retention_days == 0 {
+        return (purged, errors);
+    }
+    
+    let now = SystemTime::now();
+    for area in load_areas() {
+        let expired: Vec<String> = area.entries
+            .iter()
+            .filter(|entry| entry.expires_at(retention_days).is_some_and(|expires| expires <= now))
+            .map(|entry| entry.id.clone())
+            .collect();
+        if expired.is_empty() {
+            continue;
+        }
+        info!("{} quarantined items in {} are older than {} days", expired.len(), area.root.display(), retention_days);
+        let (area_purged, area_errors) = purge(&area.root, &expired);
+        purged.extend(area_purged);
+        errors.extend(area_errors);
+    }
+    (purged, errors)
+}
+
+/// Every known quarantine folder with its manifest
+pub fn load_areas() -> Vec<QuarantineArea> {
+    let mut areas = Vec::new();
+    for root in load_registry() {
+        if !root.exists() {
+            continue;
+        }
+        match load_manifest(&root) {
+            Ok(entries) => areas.push(QuarantineArea { root, entries }),
+            Err(e) => warn!("Failed to read quarantine manifest in {}: {}", root.display(), e),
+        }
+    }
+    areas
+}
+
+fn load_manifest(root: &Path) -> Result<Vec<QuarantineEntry>, Box<dyn std::error::Error>> {
+    let path = root.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Written to a temporary file first so a crash never leaves a truncated manifest
+fn save_manifest(root: &Path, entries: &[QuarantineEntry]) -> Result<(), Box<dyn std::error::Error>> {
+    let temporary = root.join(format!("{}.tmp", MANIFEST_FILE));
+    fs::write(&temporary, serde_json::to_string_pretty(entries)?)?;
+    fs::rename(&temporary, root.join(MANIFEST_FILE))?;
+    Ok(())
+}
+
+fn load_registry() -> Vec<PathBuf> {
+    fs::read_to_string(data_file(QUARANTINE_REGISTRY))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn register_area(root: &Path) {
+    let mut roots = load_registry();
+    if roots.iter().any(|known| known == root) {
+        return;
+    }
+    roots.push(root.to_path_buf());
+    let result = serde_json::to_string_pretty(&roots)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(data_file(QUARANTINE_REGISTRY), content).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => info!("Registered quarantine folder {}", root.display()),
+        Err(e) => warn!("Failed to register quarantine folder {}: {}", root.display(), e),
+    }
+}
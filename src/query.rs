@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::info;
+use regex::Regex;
+
+use crate::disk_scanner::ScanResult;
+use crate::file_age::format_date;
+use crate::file_utils::format_size;
+use crate::result_filter::{parse_date, parse_size_range, EntryKind, NameMatch, ResultFilter, SizeRange};
+use crate::scan_index::{ScanIndex, NO_PARENT};
+
+/// Rows returned when the query has no `limit:`
+const DEFAULT_LIMIT: usize = 100;
+const DAY: Duration = Duration::from_secs(86400);
+
+/// Parse error with the byte offset of the offending term
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at column {})", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Size,
+    Name,
+    Modified,
+    Path,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupKey {
+    Extension,
+    Category,
+    Folder,
+    Type,
+}
+
+/// Value computed per group, in the order the query lists them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Count,
+    SumSize,
+    AverageSize,
+    MaxSize,
+}
+
+impl Aggregate {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::SumSize => "sum:size",
+            Aggregate::AverageSize => "avg:size",
+            Aggregate::MaxSize => "max:size",
+        }
+    }
+}
+
+/// A parsed query: a filter plus sorting and either a row limit or a grouping
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub filter: ResultFilter,
+    pub sort: SortKey,
+    pub descending: bool,
+    pub limit: usize,
+    pub group: Option<GroupKey>,
+    pub aggregates: Vec<Aggregate>,
+}
+
+/// One `key:value`, `key>value` or bare term
+struct Term {
+    key: Option<String>,
+    operator: &'static str,
+    value: String,
+    position: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Term>, QueryError> {
+    let mut terms = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        
+        let mut key = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if !(c.is_alphanumeric() || c == '_') {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        let operator = match chars.peek().map(|&(_, c)| c) {
+            Some(':') => ":",
+            Some('=') => "=",
+            Some('>') => ">",
+            Some('<') => "<",
+            _ => "",
+        };
+        let (key, operator) = if operator.is_empty() || key.is_empty() {
+            // A bare word: whatever was read so far is the start of its value
+            (None, "")
+        } else {
+            chars.next();
+            let operator = match (operator, chars.peek().map(|&(_, c)| c)) {
+                (">", Some('=')) => {
+                    chars.next();
+                    ">="
+                }
+                ("<", Some('=')) => {
+                    chars.next();
+                    "<="
+                }
+                _ => operator,
+            };
+            (Some(key.to_lowercase()), operator)
+        };
+        
+        let mut value = match key {
+            None => text[position..chars.peek().map_or(text.len(), |&(offset, _)| offset)].to_string(),
+            Some(_) => String::new(),
+        };
+        if value.is_empty() && chars.peek().is_some_and(|&(_, c)| c == '"') {
+            let (quote_position, _) = chars.next().unwrap();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) if chars.peek().is_some_and(|&(_, c)| c == '"' || c == '\\') => {
+                        value.push(chars.next().unwrap().1);
+                    }
+                    Some((_, c)) => value.push(c),
+                    None => return Err(QueryError { message: "Unterminated quote".to_string(), position: quote_position }),
+                }
+            }
+        } else {
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        terms.push(Term { key, operator, value, position });
+    }
+    Ok(terms)
+}
+
+/// Parse a query such as `type:file ext:iso,vhdx size>4G mtime<2023-01-01 path:"D:\\Archive" sort:size limit:50`
+/// or `group:ext sum:size`. Bare words match names; only files match unless `type:dir` or `type:any` is given.
+pub fn parse_query(text: &str) -> Result<Query, QueryError> {
+    let mut query = Query {
+        filter: ResultFilter::default(),
+        sort: SortKey::Size,
+        descending: true,
+        limit: DEFAULT_LIMIT,
+        group: None,
+        aggregates: Vec::new(),
+    };
+    let mut order = None;
+    let mut names = Vec::new();
+    // Folder sizes include their files, so files only unless asked otherwise
+    let mut kind = Some(EntryKind::File);
+    
+    for term in tokenize(text)? {
+        let error = |message: String| QueryError { message, position: term.position };
+        let value = term.value.as_str();
+        let Some(key) = term.key.as_deref() else {
+            if value.eq_ignore_ascii_case("count") {
+                query.aggregates.push(Aggregate::Count);
+            } else {
+                names.push(value.to_lowercase());
+            }
+            continue;
+        };
+        if value.is_empty() {
+            return Err(error(format!("Missing value after \"{}{}\"", key, term.operator)));
+        }
+        let expect_colon = || if term.operator == ":" || term.operator == "=" {
+            Ok(())
+        } else {
+            Err(error(format!("\"{}\" does not support \"{}\"", key, term.operator)))
+        };
+        
+        match key {
+            "type" => {
+                expect_colon()?;
+                kind = match value.to_lowercase().as_str() {
+                    "file" | "f" => Some(EntryKind::File),
+                    "dir" | "folder" | "d" => Some(EntryKind::Folder),
+                    "any" => None,
+                    _ => return Err(error(format!("Unknown type \"{}\", expected file, dir or any", value))),
+                };
+            }
+            "ext" => {
+                expect_colon()?;
+                query.filter.extensions.extend(value
+                    .split(',')
+                    .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|extension| !extension.is_empty()));
+            }
+            "name" => {
+                expect_colon()?;
+                names.push(value.to_lowercase());
+            }
+            "re" | "regex" => {
+                expect_colon()?;
+                let regex = Regex::new(&format!("(?i){}", value)).map_err(|e| error(format!("Invalid regex: {}", e)))?;
+                query.filter.name = Some(NameMatch::Regex(regex));
+            }
+            "cat" | "category" => {
+                expect_colon()?;
+                query.filter.category = Some(value.to_string());
+            }
+            "path" | "under" => {
+                expect_colon()?;
+                query.filter.path_prefix = Some(value.to_string());
+            }
+            "size" => {
+                let range = match term.operator {
+                    ":" | "=" => parse_size_range(value),
+                    operator => parse_size_range(&format!("{}{}", operator, value)),
+                };
+                query.filter.size = intersect(query.filter.size, range.map_err(error)?);
+            }
+            "mtime" | "modified" => {
+                let (after, before) = parse_date_bound(term.operator, value).map_err(error)?;
+                if after.is_some() {
+                    query.filter.modified_after = after;
+                }
+                if before.is_some() {
+                    query.filter.modified_before = before;
+                }
+            }
+            "sort" => {
+                expect_colon()?;
+                query.sort = match value.to_lowercase().as_str() {
+                    "size" => SortKey::Size,
+                    "name" => SortKey::Name,
+                    "mtime" | "modified" => SortKey::Modified,
+                    "path" => SortKey::Path,
+                    _ => return Err(error(format!("Unknown sort key \"{}\", expected size, name, mtime or path", value))),
+                };
+            }
+            "order" => {
+                expect_colon()?;
+                order = Some(match value.to_lowercase().as_str() {
+                    "asc" => false,
+                    "desc" => true,
+                    _ => return Err(error(format!("Unknown order \"{}\", expected asc or desc", value))),
+                });
+            }
+            "limit" => {
+                expect_colon()?;
+                query.limit = value.parse().map_err(|_| error(format!("Invalid limit \"{}\"", value)))?;
+            }
+            "group" => {
+                expect_colon()?;
+                query.group = Some(match value.to_lowercase().as_str() {
+                    "ext" => GroupKey::Extension,
+                    "cat" | "category" => GroupKey::Category,
+                    "folder" | "dir" => GroupKey::Folder,
+                    "type" => GroupKey::Type,
+                    _ => return Err(error(format!("Unknown group \"{}\", expected ext, category, folder or type", value))),
+                });
+            }
+            "sum" | "avg" | "max" => {
+                expect_colon()?;
+                if !value.eq_ignore_ascii_case("size") {
+                    return Err(error(format!("Only size can be aggregated, not \"{}\"", value)));
+                }
+                query.aggregates.push(match key {
+                    "sum" => Aggregate::SumSize,
+                    "avg" => Aggregate::AverageSize,
+                    _ => Aggregate::MaxSize,
+                });
+            }
+            _ => return Err(error(format!("Unknown key \"{}\"", key))),
+        }
+    }
+    
+    query.filter.kind = kind;
+    if !names.is_empty() && query.filter.name.is_some() {
+        return Err(QueryError { message: "Use either name words or re:, not both".to_string(), position: 0 });
+    }
+    if !names.is_empty() {
+        query.filter.name = Some(NameMatch::Contains(names));
+    }
+    if query.group.is_some() && query.aggregates.is_empty() {
+        query.aggregates = vec![Aggregate::Count, Aggregate::SumSize];
+    }
+    // Sizes and dates read best largest or newest first, names and paths alphabetically
+    query.descending = order.unwrap_or(matches!(query.sort, SortKey::Size | SortKey::Modified));
+    Ok(query)
+}
+
+fn intersect(a: SizeRange, b: SizeRange) -> SizeRange {
+    SizeRange {
+        min: a.min.max(b.min),
+        max: match (a.max, b.max) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        },
+    }
+}
+
+type DateBounds = (Option<SystemTime>, Option<SystemTime>);
+
+/// `mtime<D` is before D, `mtime<=D` includes D, `mtime:D` is that day and `mtime:D1..D2` a range
+fn parse_date_bound(operator: &str, value: &str) -> Result<DateBounds, String> {
+    match operator {
+        "<" => Ok((None, Some(parse_date(value)?))),
+        "<=" => Ok((None, Some(parse_date(value)? + DAY))),
+        ">" => Ok((Some(parse_date(value)? + DAY), None)),
+        ">=" => Ok((Some(parse_date(value)?), None)),
+        _ => match value.split_once("..") {
+            Some((from, to)) => Ok((Some(parse_date(from)?), Some(parse_date(to)? + DAY))),
+            None => {
+                let day = parse_date(value)?;
+                Ok((Some(day), Some(day + DAY)))
+            }
+        },
+    }
+}
+
+/// Totals for one group of matching entries
+#[derive(Debug, Clone, Default)]
+pub struct QueryGroup {
+    pub key: String,
+    pub count: u64,
+    pub total_size: u64,
+    pub max_size: u64,
+}
+
+impl QueryGroup {
+    pub fn value(&self, aggregate: Aggregate) -> u64 {
+        match aggregate {
+            Aggregate::Count => self.count,
+            Aggregate::SumSize => self.total_size,
+            Aggregate::AverageSize => self.total_size.checked_div(self.count).unwrap_or(0),
+            Aggregate::MaxSize => self.max_size,
+        }
+    }
+    
+    pub fn format_value(&self, aggregate: Aggregate) -> String {
+        match aggregate {
+            Aggregate::Count => self.count.to_string(),
+            _ => format_size(self.value(aggregate)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum QueryRows {
+    Entries(Vec<ScanResult>),
+    Groups(Vec<QueryGroup>),
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryOutput {
+    pub matched: u64,
+    pub matched_bytes: u64,
+    pub aggregates: Vec<Aggregate>,
+    pub rows: QueryRows,
+}
+
+/// Evaluate a query over every retained entry
+pub fn run_query(index: &ScanIndex, query: &Query) -> QueryOutput {
+    let started = std::time::Instant::now();
+    let within = query.filter.within_prefix(index);
+    let matching: Vec<usize> = (0..index.nodes.len())
+        .filter(|&node_index| within[node_index] && query.filter.matches(index, node_index))
+        .collect();
+    let matched_bytes = matching.iter().map(|&node_index| index.nodes[node_index].size).sum();
+    
+    let rows = if query.group.is_some() || !query.aggregates.is_empty() {
+        QueryRows::Groups(group_entries(index, query, &matching))
+    } else {
+        QueryRows::Entries(sorted_entries(index, query, matching.clone()))
+    };
+    info!("Query matched {} entries in {:?}", matching.len(), started.elapsed());
+    QueryOutput {
+        matched: matching.len() as u64,
+        matched_bytes,
+        aggregates: query.aggregates.clone(),
+        rows,
+    }
+}
+
+fn sorted_entries(index: &ScanIndex, query: &Query, mut matching: Vec<usize>) -> Vec<ScanResult> {
+    let nodes = &index.nodes;
+    match query.sort {
+        SortKey::Size => matching.sort_by_key(|&node_index| nodes[node_index].size),
+        SortKey::Modified => matching.sort_by_key(|&node_index| nodes[node_index].modified),
+        SortKey::Name => matching.sort_by_cached_key(|&node_index| nodes[node_index].name.to_lowercase()),
+        SortKey::Path => matching.sort_by_cached_key(|&node_index| index.path(node_index).to_string_lossy().to_lowercase()),
+    }
+    if query.descending {
+        matching.reverse();
+    }
+    matching.truncate(query.limit);
+    matching.into_iter().map(|node_index| index.to_result(node_index)).collect()
+}
+
+fn group_entries(index: &ScanIndex, query: &Query, matching: &[usize]) -> Vec<QueryGroup> {
+    let mut groups: HashMap<String, QueryGroup> = HashMap::new();
+    let mut folder_paths: HashMap<u32, String> = HashMap::new();
+    for &node_index in matching {
+        let node = &index.nodes[node_index];
+        let key = match query.group {
+            None => "all".to_string(),
+            Some(GroupKey::Extension) if node.is_dir => "(folder)".to_string(),
+            Some(GroupKey::Extension) => node.name
+                .rsplit_once('.')
+                .filter(|(stem, _)| !stem.is_empty())
+                .map(|(_, extension)| extension.to_lowercase())
+                .unwrap_or_else(|| "(none)".to_string()),
+            Some(GroupKey::Category) => index.category(node_index).unwrap_or("(folder)").to_string(),
+            Some(GroupKey::Type) => if node.is_dir { "folder" } else { "file" }.to_string(),
+            Some(GroupKey::Folder) if node.parent == NO_PARENT => "(root)".to_string(),
+            Some(GroupKey::Folder) => folder_paths
+                .entry(node.parent)
+                .or_insert_with(|| index.path(node.parent as usize).to_string_lossy().to_string())
+                .clone(),
+        };
+        let group = groups.entry(key).or_default();
+        group.count += 1;
+        group.total_size += node.size;
+        group.max_size = group.max_size.max(node.size);
+    }
+    
+    let mut groups: Vec<QueryGroup> = groups
+        .into_iter()
+        .map(|(key, group)| QueryGroup { key, ..group })
+        .collect();
+    // Groups are ranked by the first aggregate
+    let aggregate = query.aggregates.first().copied().unwrap_or(Aggregate::Count);
+    groups.sort_by_key(|group| std::cmp::Reverse(group.value(aggregate)));
+    groups.truncate(query.limit);
+    groups
+}
+
+/// Plain text table for the command line
+pub fn format_table(output: &QueryOutput) -> String {
+    let mut lines = Vec::new();
+    match &output.rows {
+        QueryRows::Entries(entries) => {
+            for entry in entries {
+                let modified = entry.modified.map(format_date).unwrap_or_else(|| "-".to_string());
+                lines.push(format!("{:>10}  {}  {}", format_size(entry.size), modified, entry.path));
+            }
+        }
+        QueryRows::Groups(groups) => {
+            let header: Vec<String> = output.aggregates.iter().map(|aggregate| format!("{:>10}", aggregate.label())).collect();
+            lines.push(format!("{}  group", header.join("  ")));
+            for group in groups {
+                let values: Vec<String> = output.aggregates.iter().map(|&aggregate| format!("{:>10}", group.format_value(aggregate))).collect();
+                lines.push(format!("{}  {}", values.join("  "), group.key));
+            }
+        }
+    }
+    lines.push(format!("{} matching entries, {}", output.matched, format_size(output.matched_bytes)));
+    lines.join("\n")
+}
+
+/// JSON rows for scripting
+pub fn to_json(output: &QueryOutput) -> serde_json::Value {
+    let rows: Vec<serde_json::Value> = match &output.rows {
+        QueryRows::Entries(entries) => entries
+            .iter()
+            .map(|entry| serde_json::json!({
+                "path": entry.path,
+                "size": entry.size,
+                "category": entry.category,
+                "modified": entry.modified.and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|age| age.as_secs()),
+            }))
+            .collect(),
+        QueryRows::Groups(groups) => groups
+            .iter()
+            .map(|group| {
+                let mut row = serde_json::json!({ "group": group.key });
+                for &aggregate in &output.aggregates {
+                    row[aggregate.label()] = group.value(aggregate).into();
+                }
+                row
+            })
+            .collect(),
+    };
+    serde_json::json!({ "matched": output.matched, "matched_bytes": output.matched_bytes, "rows": rows })
+}
@@ -0,0 +1,296 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use walkdir::WalkDir;
+
+use crate::audit::{record_done, record_failed};
+use crate::file_utils::{content_hash, format_size, free_space, same_volume, total_size};
+
+const COPY_BUFFER: usize = 1024 * 1024;
+/// Progress is reported at most once per this many bytes
+const PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+const PARTIAL_SUFFIX: &str = ".partial";
+
+#[derive(Debug, Clone)]
+pub struct Relocated {
+    pub target: PathBuf,
+    pub bytes: u64,
+    /// Bytes found already copied by an earlier, interrupted run
+    pub resumed_bytes: u64,
+    /// `content_hash` the copy was verified against, absent for a same-volume rename
+    pub hash: Option<String>,
+    /// Why the requested link could not be created; the data was still moved
+    pub link_error: Option<String>,
+}
+
+/// Messages from moves running in the background
+pub enum RelocateMessage {
+    Progress { done: u64, total: u64, path: String },
+    Finished(Vec<(String, Result<Relocated, String>)>),
+}
+
+/// Move a file or folder into `target_dir`, copying and verifying by size and hash before the
+/// source is deleted. An interrupted move resumes from what was already copied when run again.
+pub fn relocate(
+    source: &Path,
+    target_dir: &Path,
+    leave_link: bool,
+    progress: impl FnMut(u64, u64, &str),
+    should_stop: &Arc<Mutex<bool>>,
+) -> Result<Relocated, Box<dyn std::error::Error>> {
+    let size = total_size(source).ok();
+    let result = relocate_verified(source, target_dir, leave_link, progress, should_stop);
+    let path = source.to_string_lossy();
+    match &result {
+        Ok(relocated) => record_done("move", &path, size, relocated.hash.as_deref(), &format!("to {}", relocated.target.display())),
+        Err(e) => record_failed("move", &path, size, &e.to_string()),
+    }
+    result
+}
+
+fn relocate_verified(
+    source: &Path,
+    target_dir: &Path,
+    leave_link: bool,
+    mut progress: impl FnMut(u64, u64, &str),
+    should_stop: &Arc<Mutex<bool>>,
+) -> Result<Relocated, Box<dyn std::error::Error>> {
+    let name = source.file_name().ok_or("path has no file name")?;
+    let is_dir = fs::symlink_metadata(source)?.is_dir();
+    let target = target_dir.join(name);
+    if target.starts_with(source) {
+        return Err("the target is inside the item being moved".into());
+    }
+    fs::create_dir_all(target_dir)?;
+    
+    // Marks an unfinished copy, so the next run resumes it instead of refusing an existing target
+    let marker = target_dir.join(format!(".{}.relocating", name.to_string_lossy()));
+    let resuming = fs::read_to_string(&marker).is_ok_and(|recorded| Path::new(recorded.trim()) == source);
+    if fs::symlink_metadata(&target).is_ok() && !resuming {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+    
+    let total = total_size(source)?;
+    // A marker means a partial copy from an interrupted move is there, which a rename would fail on or overwrite
+    if !resuming && same_volume(source, target_dir) {
+        info!("{} and {} are on the same volume, renaming", source.display(), target_dir.display());
+        fs::rename(source, &target)?;
+        let link_error = leave_link.then(|| create_link(&target, source, is_dir).err().map(|e| e.to_string())).flatten();
+        return Ok(Relocated { target, bytes: total, resumed_bytes: 0, hash: None, link_error });
+    }
+    
+    let resumed_bytes = if resuming { copied_so_far(&target) } else { 0 };
+    let needed = total.saturating_sub(resumed_bytes);
+    if let Some(available) = free_space(target_dir) {
+        if available < needed {
+            return Err(format!("{} needs {} but only {} is free", target_dir.display(), format_size(needed), format_size(available)).into());
+        }
+    }
+    fs::write(&marker, source.to_string_lossy().as_bytes())?;
+    info!("Moving {} ({}) to {}{}", source.display(), format_size(total), target.display(),
+        if resuming { format!(", resuming after {}", format_size(resumed_bytes)) } else { String::new() });
+    
+    let mut done = 0;
+    let mut reported = 0;
+    for entry in WalkDir::new(source).follow_links(false).sort_by_file_name() {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?;
+        let copy = if relative.as_os_str().is_empty() { target.clone() } else { target.join(relative) };
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&copy)?;
+        } else if entry.file_type().is_symlink() {
+            copy_link(entry.path(), &copy)?;
+        } else {
+            progress(done, total, &entry.path().to_string_lossy());
+            done += copy_file(entry.path(), &copy, should_stop, |copied| {
+                if done + copied >= reported + PROGRESS_INTERVAL {
+                    reported = done + copied;
+                    progress(reported, total, &entry.path().to_string_lossy());
+                }
+            })?;
+        }
+    }
+    
+    progress(total, total, &format!("Verifying {}", target.display()));
+    let copied = total_size(&target)?;
+    let hash = content_hash(source)?;
+    let mismatch = if copied != total {
+        Some(format!("copy holds {} bytes, the source {}", copied, total))
+    } else if hash != content_hash(&target)? {
+        Some("copy does not match the source".to_string())
+    } else {
+        None
+    };
+    if let Some(mismatch) = mismatch {
+        // A bad copy would be resumed again, so start over next time
+        warn!("Verifying {} failed: {}", target.display(), mismatch);
+        let _ = if is_dir { fs::remove_dir_all(&target) } else { fs::remove_file(&target) };
+        let _ = fs::remove_file(&marker);
+        return Err(format!("{}, the copy was removed and the source kept", mismatch).into());
+    }
+    info!("Verified copy of {} at {}", source.display(), target.display());
+    
+    if is_dir {
+        fs::remove_dir_all(source)?;
+    } else {
+        fs::remove_file(source)?;
+    }
+    let _ = fs::remove_file(&marker);
+    let link_error = leave_link.then(|| create_link(&target, source, is_dir).err().map(|e| e.to_string())).flatten();
+    if let Some(e) = &link_error {
+        warn!("Moved {} but could not leave a link: {}", source.display(), e);
+    }
+    Ok(Relocated { target, bytes: total, resumed_bytes, hash: Some(hash), link_error })
+}
+
+/// Bytes already at the target, finished files and partial ones alike
+fn copied_so_far(target: &Path) -> u64 {
+    WalkDir::new(target)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Copy one file through a `.partial` file, continuing a partial copy where it stopped.
+/// Returns the file's size.
+fn copy_file(source: &Path, target: &Path, should_stop: &Arc<Mutex<bool>>, mut progress: impl FnMut(u64)) -> Result<u64, Box<dyn std::error::Error>> {
+    let metadata = fs::metadata(source)?;
+    let length = metadata.len();
+    if fs::metadata(target).is_ok_and(|existing| existing.len() == length) {
+        // Finished by an earlier run, files only get their name once complete
+        return Ok(length);
+    }
+    
+    let mut partial = target.as_os_str().to_os_string();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+    let mut output = OpenOptions::new().create(true).append(true).open(&partial)?;
+    let mut copied = output.metadata()?.len().min(length);
+    let mut input = File::open(source)?;
+    input.seek(SeekFrom::Start(copied))?;
+    
+    let mut buffer = vec![0u8; COPY_BUFFER];
+    loop {
+        if *should_stop.lock().unwrap() {
+            return Err("cancelled, move again to resume".into());
+        }
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        output.write_all(&buffer[..read])?;
+        copied += read as u64;
+        progress(copied);
+    }
+    output.sync_all()?;
+    if let Ok(modified) = metadata.modified() {
+        let _ = output.set_modified(modified);
+    }
+    drop(output);
+    fs::rename(&partial, target)?;
+    Ok(length)
+}
+
+#[cfg(unix)]
+fn copy_link(source: &Path, target: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(target).is_ok() {
+        return Ok(());
+    }
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn copy_link(source: &Path, _target: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::other(format!("{} is a link, which is not moved", source.display())))
+}
+
+/// Leave a symlink (or a junction for Windows folders) where the item was
+#[cfg(unix)]
+fn create_link(target: &Path, link: &Path, _is_dir: bool) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Leave a symlink (or a junction for Windows folders) where the item was
+#[cfg(windows)]
+fn create_link(target: &Path, link: &Path, is_dir: bool) -> std::io::Result<()> {
+    if !is_dir {
+        // File symlinks need Developer Mode or an elevated process
+        return std::os::windows::fs::symlink_file(target, link);
+    }
+    // Junctions work without special rights, unlike directory symlinks
+    fs::create_dir(link)?;
+    let result = set_junction(target, link);
+    if result.is_err() {
+        let _ = fs::remove_dir(link);
+    }
+    result
+}
+
+/// Turn the empty folder `link` into a junction to `target` with a mount point reparse buffer
+#[cfg(windows)]
+fn set_junction(target: &Path, link: &Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winbase::{FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT};
+    use winapi::um::winioctl::FSCTL_SET_REPARSE_POINT;
+    use winapi::um::winnt::{GENERIC_WRITE, IO_REPARSE_TAG_MOUNT_POINT};
+    
+    let target = std::path::absolute(target)?;
+    let target = target.to_string_lossy();
+    let target = target.strip_prefix(r"\\?\").unwrap_or(&target);
+    let substitute: Vec<u16> = format!(r"\??\{}", target).encode_utf16().collect();
+    let print: Vec<u16> = target.encode_utf16().collect();
+    
+    // Both names are null-terminated in the buffer, their lengths are in bytes without the null
+    let substitute_bytes = (substitute.len() * 2) as u16;
+    let print_bytes = (print.len() * 2) as u16;
+    let data_length = 8 + substitute_bytes + 2 + print_bytes + 2;
+    let mut buffer: Vec<u8> = Vec::with_capacity(8 + data_length as usize);
+    buffer.extend_from_slice(&IO_REPARSE_TAG_MOUNT_POINT.to_le_bytes());
+    buffer.extend_from_slice(&data_length.to_le_bytes());
+    buffer.extend_from_slice(&0u16.to_le_bytes());
+    buffer.extend_from_slice(&0u16.to_le_bytes());
+    buffer.extend_from_slice(&substitute_bytes.to_le_bytes());
+    buffer.extend_from_slice(&(substitute_bytes + 2).to_le_bytes());
+    buffer.extend_from_slice(&print_bytes.to_le_bytes());
+    for unit in substitute.iter().chain([&0]).chain(print.iter()).chain([&0]) {
+        buffer.extend_from_slice(&unit.to_le_bytes());
+    }
+    
+    let wide: Vec<u16> = link.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    unsafe {
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            GENERIC_WRITE,
+            0,
+            std::ptr::null_mut(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT,
+            std::ptr::null_mut(),
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut returned = 0;
+        let succeeded = DeviceIoControl(
+            handle,
+            FSCTL_SET_REPARSE_POINT,
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as u32,
+            std::ptr::null_mut(),
+            0,
+            &mut returned,
+            std::ptr::null_mut(),
+        ) != 0;
+        let error = std::io::Error::last_os_error();
+        CloseHandle(handle);
+        if succeeded { Ok(()) } else { Err(error) }
+    }
+}
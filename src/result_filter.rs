@@ -0,0 +1,343 @@
+use std::collections::BinaryHeap;
+use std::cmp::Reverse;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::info;
+use regex::Regex;
+
+use crate::disk_scanner::ScanResult;
+use crate::scan_index::{ScanIndex, NO_PARENT};
+
+/// How names are matched
+#[derive(Debug, Clone)]
+pub enum NameMatch {
+    /// Case-insensitive substrings, all of which have to occur
+    Contains(Vec<String>),
+    Regex(Regex),
+}
+
+impl NameMatch {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NameMatch::Contains(words) => {
+                let name = name.to_lowercase();
+                words.iter().all(|word| name.contains(word.as_str()))
+            }
+            NameMatch::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Whether only files or only folders match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    File,
+    Folder,
+}
+
+/// Inclusive size bounds in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SizeRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl SizeRange {
+    pub fn contains(&self, size: u64) -> bool {
+        self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+    }
+}
+
+/// Criteria applied to the retained scan. Every set criterion has to match.
+#[derive(Debug, Clone, Default)]
+pub struct ResultFilter {
+    pub kind: Option<EntryKind>,
+    pub name: Option<NameMatch>,
+    /// Lowercase extensions without the dot; compound ones like `tar.gz` work too
+    pub extensions: Vec<String>,
+    pub category: Option<String>,
+    pub size: SizeRange,
+    pub modified_after: Option<SystemTime>,
+    pub modified_before: Option<SystemTime>,
+    /// Only entries at or below this path
+    pub path_prefix: Option<String>,
+}
+
+impl ResultFilter {
+    /// Extension and category criteria only make sense for files
+    pub fn files_only(&self) -> bool {
+        !self.extensions.is_empty() || self.category.is_some() || self.kind == Some(EntryKind::File)
+    }
+    
+    pub fn matches(&self, index: &ScanIndex, node_index: usize) -> bool {
+        let node = &index.nodes[node_index];
+        if node.removed {
+            return false;
+        }
+        if (node.is_dir && self.files_only()) || (!node.is_dir && self.kind == Some(EntryKind::Folder)) {
+            return false;
+        }
+        if let Some(name) = &self.name {
+            if !name.matches(&node.name) {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let name = node.name.to_lowercase();
+            let has_extension = self.extensions.iter().any(|extension| {
+                name.strip_suffix(extension.as_str()).is_some_and(|stem| stem.ends_with('.'))
+            });
+            if !has_extension {
+                return false;
+            }
+        }
+        if let Some(category) = &self.category {
+            if !index.category(node_index).is_some_and(|name| name.eq_ignore_ascii_case(category)) {
+                return false;
+            }
+        }
+        if !self.size.contains(node.size) {
+            return false;
+        }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Some(modified) = node.modified else { return false };
+            if self.modified_after.is_some_and(|after| modified < after)
+                || self.modified_before.is_some_and(|before| modified >= before)
+            {
+                return false;
+            }
+        }
+        true
+    }
+    
+    /// Which nodes lie at or below `path_prefix`, in a single pass over the index.
+    /// Paths are only built for folders on the way to the prefix.
+    pub fn within_prefix(&self, index: &ScanIndex) -> Vec<bool> {
+        let Some(prefix) = &self.path_prefix else {
+            return vec![true; index.nodes.len()];
+        };
+        let prefix = normalize_path(prefix);
+        let mut within = vec![false; index.nodes.len()];
+        let mut on_route = vec![false; index.nodes.len()];
+        for (node_index, node) in index.nodes.iter().enumerate() {
+            let parent = node.parent as usize;
+            if node.parent != NO_PARENT && within[parent] {
+                within[node_index] = true;
+            } else if node.parent == NO_PARENT || (node.is_dir && on_route[parent]) {
+                let path = normalize_path(&index.path(node_index).to_string_lossy());
+                within[node_index] = prefix == path || path.strip_prefix(&prefix).is_some_and(|rest| rest.starts_with('/'));
+                on_route[node_index] = prefix.strip_prefix(&path).is_some_and(|rest| rest.starts_with('/'));
+            }
+        }
+        within
+    }
+}
+
+/// Lowercase, `/`-separated and without a trailing separator, so prefixes compare as folders
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+/// Text fields of the filter bar, compiled into a `ResultFilter` when applied
+#[derive(Debug, Clone, Default)]
+pub struct FilterBar {
+    pub name: String,
+    pub use_regex: bool,
+    pub extensions: String,
+    pub category: Option<String>,
+    pub size: String,
+    pub modified_from: String,
+    pub modified_to: String,
+    pub path_prefix: String,
+}
+
+impl FilterBar {
+    pub fn compile(&self) -> Result<ResultFilter, String> {
+        let name = self.name.trim();
+        let name = match (name.is_empty(), self.use_regex) {
+            (true, _) => None,
+            (false, true) => Some(NameMatch::Regex(
+                Regex::new(&format!("(?i){}", name)).map_err(|e| format!("Invalid name pattern: {}", e))?,
+            )),
+            (false, false) => Some(NameMatch::Contains(vec![name.to_lowercase()])),
+        };
+        
+        let extensions = self.extensions
+            .split([',', ' ', ';'])
+            .map(|extension| extension.trim().trim_start_matches("*.").trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+        
+        let parse_optional_date = |text: &str, end_of_day: bool| -> Result<Option<SystemTime>, String> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            let date = parse_date(text)?;
+            Ok(Some(if end_of_day { date + Duration::from_secs(86400) } else { date }))
+        };
+        
+        let path_prefix = self.path_prefix.trim().trim_matches('"');
+        Ok(ResultFilter {
+            kind: None,
+            name,
+            extensions,
+            category: self.category.clone(),
+            size: parse_size_range(&self.size)?,
+            modified_after: parse_optional_date(&self.modified_from, false)?,
+            // The "to" date is inclusive
+            modified_before: parse_optional_date(&self.modified_to, true)?,
+            path_prefix: (!path_prefix.is_empty()).then(|| path_prefix.to_string()),
+        })
+    }
+}
+
+/// Parse a size such as `4G`, `2GB`, `1.5 TB` or `512` (bytes), in powers of 1024
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("Invalid size \"{}\"", text))?;
+    let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        "T" | "TB" | "TIB" => 1024 * 1024 * 1024 * 1024,
+        other => return Err(format!("Unknown size unit \"{}\"", other)),
+    };
+    // The cast would quietly saturate, so anything beyond u64 is refused
+    let bytes = number * multiplier as f64;
+    if !bytes.is_finite() || bytes >= u64::MAX as f64 {
+        return Err(format!("Size \"{}\" is too large", text));
+    }
+    Ok(bytes as u64)
+}
+
+/// Parse `>2GB`, `>=2GB`, `<500MB`, `1G-5G` or `1G..5G`. A bare size means at least that size.
+pub fn parse_size_range(text: &str) -> Result<SizeRange, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(SizeRange::default());
+    }
+    if let Some(rest) = text.strip_prefix(">=") {
+        return Ok(SizeRange { min: Some(parse_size(rest)?), max: None });
+    }
+    if let Some(rest) = text.strip_prefix("<=") {
+        return Ok(SizeRange { min: None, max: Some(parse_size(rest)?) });
+    }
+    if let Some(rest) = text.strip_prefix('>') {
+        let above = parse_size(rest)?.checked_add(1).ok_or_else(|| format!("Size \"{}\" is too large", rest.trim()))?;
+        return Ok(SizeRange { min: Some(above), max: None });
+    }
+    if let Some(rest) = text.strip_prefix('<') {
+        // Nothing is smaller than 0 bytes, and `max: Some(0)` would still match empty files
+        let below = parse_size(rest)?;
+        if below == 0 {
+            return Err(format!("\"{}\" matches nothing, sizes start at 0 bytes", text));
+        }
+        return Ok(SizeRange { min: None, max: Some(below - 1) });
+    }
+    if let Some((low, high)) = text.split_once("..").or_else(|| text.split_once('-')) {
+        let (min, max) = (parse_size(low)?, parse_size(high)?);
+        if min > max {
+            return Err(format!("Size range \"{}\" ends before it starts", text));
+        }
+        return Ok(SizeRange { min: Some(min), max: Some(max) });
+    }
+    Ok(SizeRange { min: Some(parse_size(text)?), max: None })
+}
+
+/// Parse a `YYYY-MM-DD` date between 1970 and 9999 as midnight UTC
+pub fn parse_date(text: &str) -> Result<SystemTime, String> {
+    let invalid = || format!("Invalid date \"{}\", expected YYYY-MM-DD", text);
+    let mut parts = text.trim().splitn(3, '-');
+    let mut next = || parts.next().and_then(|part| part.parse::<i64>().ok()).ok_or_else(invalid);
+    let (year, month, day) = (next()?, next()?, next()?);
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    let (year, month) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    (days as u64).checked_mul(86400)
+        .and_then(|seconds| UNIX_EPOCH.checked_add(Duration::from_secs(seconds)))
+        .ok_or_else(invalid)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Filtered rankings recomputed over the whole retained scan
+#[derive(Debug, Clone, Default)]
+pub struct FilteredResults {
+    pub files: Vec<ScanResult>,
+    /// Matching folders, or for extension and category filters the folders holding the most matching bytes
+    pub folders: Vec<ScanResult>,
+    pub matched_files: u64,
+    pub matched_bytes: u64,
+}
+
+pub fn apply_filter(index: &ScanIndex, filter: &ResultFilter, file_limit: usize, folder_limit: usize) -> FilteredResults {
+    let started = std::time::Instant::now();
+    let within = filter.within_prefix(index);
+    let mut matched_below = vec![0u64; index.nodes.len()];
+    let mut files: BinaryHeap<Reverse<(u64, usize)>> = BinaryHeap::new();
+    let mut folders: BinaryHeap<Reverse<(u64, usize)>> = BinaryHeap::new();
+    let mut results = FilteredResults::default();
+    
+    let keep = |heap: &mut BinaryHeap<Reverse<(u64, usize)>>, size: u64, node_index: usize, limit: usize| {
+        heap.push(Reverse((size, node_index)));
+        if heap.len() > limit {
+            heap.pop();
+        }
+    };
+    
+    // Reverse order visits children before their parent, so matching bytes roll up in one pass
+    for node_index in (0..index.nodes.len()).rev() {
+        let node = &index.nodes[node_index];
+        if !within[node_index] || node.removed {
+            continue;
+        }
+        if node.is_dir {
+            if filter.files_only() {
+                if matched_below[node_index] > 0 {
+                    keep(&mut folders, matched_below[node_index], node_index, folder_limit);
+                }
+            } else if filter.matches(index, node_index) {
+                keep(&mut folders, node.size, node_index, folder_limit);
+            }
+        } else if filter.matches(index, node_index) {
+            results.matched_files += 1;
+            results.matched_bytes += node.size;
+            keep(&mut files, node.size, node_index, file_limit);
+            if node.parent != NO_PARENT {
+                matched_below[node.parent as usize] += node.size;
+            }
+        }
+        if node.is_dir && node.parent != NO_PARENT {
+            matched_below[node.parent as usize] += matched_below[node_index];
+        }
+    }
+    
+    let to_results = |heap: BinaryHeap<Reverse<(u64, usize)>>| -> Vec<ScanResult> {
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, node_index))| ScanResult { size, ..index.to_result(node_index) })
+            .collect()
+    };
+    results.files = to_results(files);
+    results.folders = to_results(folders);
+    info!("Filter matched {} files ({} bytes) in {:?}", results.matched_files, results.matched_bytes, started.elapsed());
+    results
+}
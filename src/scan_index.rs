@@ -0,0 +1,231 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::disk_scanner::ScanResult;
+
+/// Parent of the root node
+pub const NO_PARENT: u32 = u32::MAX;
+
+/// One scanned file or folder. Paths are rebuilt from the parent chain to keep nodes small.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexNode {
+    /// File or folder name; the root node holds the full scan root path
+    pub name: Box<str>,
+    pub parent: u32,
+    pub is_dir: bool,
+    /// Index into the category table, `None` for folders
+    pub category: Option<u16>,
+    /// File length, or the total of every file below a folder
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Deleted or moved away since the scan; skipped by filters and queries
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// Every entry of the last scan, kept so results can be filtered and re-ranked without rescanning.
+/// Parents always come before their children.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanIndex {
+    pub nodes: Vec<IndexNode>,
+    categories: Vec<String>,
+}
+
+impl ScanIndex {
+    pub fn path(&self, index: usize) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = index as u32;
+        while current != NO_PARENT {
+            let node = &self.nodes[current as usize];
+            names.push(&*node.name);
+            current = node.parent;
+        }
+        names.iter().rev().collect()
+    }
+    
+    pub fn category(&self, index: usize) -> Option<&str> {
+        self.nodes[index].category.map(|id| self.categories[id as usize].as_str())
+    }
+    
+    /// Node of a path inside the scan, if it was retained
+    pub fn find(&self, path: &Path) -> Option<usize> {
+        let root = Path::new(&*self.nodes.first()?.name);
+        let mut names = path.strip_prefix(root).ok()?.iter();
+        let mut current = 0;
+        let Some(mut wanted) = names.next() else { return Some(0) };
+        // Children come after their parent, so one forward pass follows the path down
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            if node.parent as usize == current && !node.removed && *node.name == *wanted.to_string_lossy() {
+                current = index;
+                match names.next() {
+                    Some(name) => wanted = name,
+                    None => return Some(index),
+                }
+            }
+        }
+        None
+    }
+    
+    /// Mark a node and everything below it as removed and take its size off its folders.
+    /// Returns the nodes that were newly removed, `index` first.
+    pub fn remove(&mut self, index: usize) -> Vec<usize> {
+        if self.nodes[index].removed {
+            return Vec::new();
+        }
+        let mut removed = vec![index];
+        self.nodes[index].removed = true;
+        for child in index + 1..self.nodes.len() {
+            let parent = self.nodes[child].parent;
+            if parent != NO_PARENT && self.nodes[parent as usize].removed && !self.nodes[child].removed {
+                self.nodes[child].removed = true;
+                removed.push(child);
+            }
+        }
+        let size = self.nodes[index].size;
+        let mut current = self.nodes[index].parent;
+        while current != NO_PARENT {
+            let node = &mut self.nodes[current as usize];
+            node.size = node.size.saturating_sub(size);
+            current = node.parent;
+        }
+        removed
+    }
+    
+    /// Add a file created since the scan below `parent` and count its size in every folder above.
+    /// Returns the new node.
+    pub fn insert(&mut self, parent: usize, name: &str, size: u64, modified: Option<SystemTime>, category: Option<&str>) -> usize {
+        let category = category.map(|category| match self.categories.iter().position(|known| known == category) {
+            Some(id) => id as u16,
+            None => {
+                self.categories.push(category.to_string());
+                (self.categories.len() - 1) as u16
+            }
+        });
+        // Appending keeps every parent ahead of its children
+        let index = self.nodes.len();
+        self.nodes.push(IndexNode {
+            name: name.into(),
+            parent: parent as u32,
+            is_dir: false,
+            category,
+            size,
+            modified,
+            removed: false,
+        });
+        let mut current = parent as u32;
+        while current != NO_PARENT {
+            let node = &mut self.nodes[current as usize];
+            node.size += size;
+            current = node.parent;
+        }
+        index
+    }
+    
+    /// The `limit` largest remaining files or folders accepted by `keep`, largest first.
+    /// `keep` only sees entries big enough to make the list.
+    pub fn largest(&self, is_dir: bool, limit: usize, keep: impl Fn(usize) -> bool) -> Vec<usize> {
+        let mut heap = BinaryHeap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.is_dir != is_dir || node.removed {
+                continue;
+            }
+            // Only entries that would make the list are worth checking with `keep`
+            if heap.len() >= limit && heap.peek().is_some_and(|&Reverse((smallest, _))| node.size <= smallest) {
+                continue;
+            }
+            if !keep(index) {
+                continue;
+            }
+            heap.push(Reverse((node.size, index)));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec().into_iter().map(|Reverse((_, index))| index).collect()
+    }
+    
+    /// The node as a ranking entry
+    pub fn to_result(&self, index: usize) -> ScanResult {
+        let node = &self.nodes[index];
+        ScanResult {
+            name: node.name.to_string(),
+            path: self.path(index).to_string_lossy().to_string(),
+            size: node.size,
+            category: self.category(index).map(str::to_string),
+            modified: node.modified,
+            ..Default::default()
+        }
+    }
+}
+
+/// Collects walk entries in WalkDir order and links each one to its parent folder
+#[derive(Default)]
+pub struct IndexBuilder {
+    index: ScanIndex,
+    category_ids: HashMap<String, u16>,
+    /// Folders on the path to the current entry as (depth, node)
+    open_folders: Vec<(usize, u32)>,
+}
+
+impl IndexBuilder {
+    /// Add an entry at `depth` below the scan root. Entries whose parent was
+    /// skipped are left out, since their sizes do not count anywhere else either.
+    pub fn add(&mut self, path: &Path, depth: usize, is_dir: bool, size: u64, modified: Option<SystemTime>, category: Option<&str>) {
+        while self.open_folders.last().is_some_and(|&(open_depth, _)| open_depth >= depth) {
+            self.open_folders.pop();
+        }
+        let (parent, name) = if depth == 0 {
+            (NO_PARENT, path.to_string_lossy().to_string())
+        } else {
+            match self.open_folders.last() {
+                Some(&(open_depth, parent)) if open_depth + 1 == depth => {
+                    (parent, path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default())
+                }
+                _ => return,
+            }
+        };
+        
+        let category = category.map(|category| self.category_id(category));
+        let index = self.index.nodes.len() as u32;
+        self.index.nodes.push(IndexNode {
+            name: name.into_boxed_str(),
+            parent,
+            is_dir,
+            category,
+            size: if is_dir { 0 } else { size },
+            modified,
+            removed: false,
+        });
+        if is_dir {
+            self.open_folders.push((depth, index));
+        }
+    }
+    
+    fn category_id(&mut self, category: &str) -> u16 {
+        if let Some(&id) = self.category_ids.get(category) {
+            return id;
+        }
+        let id = self.index.categories.len() as u16;
+        self.index.categories.push(category.to_string());
+        self.category_ids.insert(category.to_string(), id);
+        id
+    }
+    
+    /// Roll file sizes up into their folders
+    pub fn finish(mut self) -> ScanIndex {
+        let nodes = &mut self.index.nodes;
+        // Children always come after their parent, so a reverse pass sees them first
+        for index in (0..nodes.len()).rev() {
+            let parent = nodes[index].parent;
+            if parent != NO_PARENT {
+                nodes[parent as usize].size += nodes[index].size;
+            }
+        }
+        info!("Retained {} scan entries for filtering", self.index.nodes.len());
+        self.index
+    }
+}
@@ -0,0 +1,288 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView};
+use log::{info, warn};
+
+/// Longest side of the thumbnails kept for the UI
+const THUMBNAIL_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HashAlgorithm {
+    /// Mean of an 8x8 grayscale thumbnail, fastest
+    Average,
+    /// Horizontal gradients of a 9x8 thumbnail, robust to brightness changes
+    #[default]
+    Difference,
+    /// Low frequencies of a 32x32 DCT, most robust to re-encoding
+    Perceptual,
+}
+
+impl HashAlgorithm {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Average => "aHash",
+            HashAlgorithm::Difference => "dHash",
+            HashAlgorithm::Perceptual => "pHash",
+        }
+    }
+}
+
+/// RGBA pixels of a small preview image
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilarImage {
+    pub path: String,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    pub hash: u64,
+    pub thumbnail: Arc<Thumbnail>,
+}
+
+/// Images whose hashes are within the distance threshold of each other,
+/// best copy first (most pixels, then largest file)
+#[derive(Debug, Clone)]
+pub struct SimilarImageGroup {
+    pub images: Vec<SimilarImage>,
+}
+
+impl SimilarImageGroup {
+    pub fn total_size(&self) -> u64 {
+        self.images.iter().map(|image| image.size).sum()
+    }
+    
+    /// Bytes freed by keeping only the best copy
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.total_size() - self.images.first().map(|image| image.size).unwrap_or(0)
+    }
+}
+
+/// Decode `candidates` (path, size), hash them and group near-duplicates whose
+/// Hamming distance is at most `max_distance` bits
+pub fn find_similar_images<F>(
+    candidates: Vec<(String, u64)>,
+    algorithm: HashAlgorithm,
+    max_distance: u32,
+    should_stop: &Arc<Mutex<bool>>,
+    mut progress: F,
+) -> Vec<SimilarImageGroup>
+where
+    F: FnMut(String),
+{
+    info!("Similar image search over {} images using {} (max distance {})",
+        candidates.len(), algorithm.label(), max_distance);
+    
+    let total = candidates.len();
+    let mut images = Vec::with_capacity(total);
+    for (index, (path, size)) in candidates.into_iter().enumerate() {
+        if *should_stop.lock().unwrap() {
+            warn!("Similar image search stopped by user request");
+            return Vec::new();
+        }
+        if index.is_multiple_of(20) {
+            progress(format!("Finding similar images: decoding {}/{}", index + 1, total));
+        }
+        
+        match hash_image(&path, size, algorithm) {
+            Ok(image) => images.push(image),
+            Err(e) => warn!("Could not decode image {}: {}", path, e),
+        }
+    }
+    
+    progress(format!("Finding similar images: comparing {} hashes", images.len()));
+    
+    // Union-find over the pairs within the distance threshold, found through a BK-tree
+    // so each image is only compared against the branches that can hold a match
+    let mut tree = BkTree::default();
+    for (index, image) in images.iter().enumerate() {
+        tree.insert(image.hash, index);
+    }
+    let mut parent: Vec<usize> = (0..images.len()).collect();
+    for i in 0..images.len() {
+        if *should_stop.lock().unwrap() {
+            warn!("Similar image search stopped by user request");
+            return Vec::new();
+        }
+        if i > 0 && i.is_multiple_of(1000) {
+            progress(format!("Finding similar images: comparing {}/{}", i, images.len()));
+        }
+        for j in tree.within(images[i].hash, max_distance) {
+            let (root_i, root_j) = (find_root(&mut parent, i), find_root(&mut parent, j));
+            if root_i != root_j {
+                parent[root_j] = root_i;
+            }
+        }
+    }
+    
+    let mut groups: Vec<Vec<SimilarImage>> = vec![Vec::new(); images.len()];
+    for (index, image) in images.into_iter().enumerate() {
+        let root = find_root(&mut parent, index);
+        groups[root].push(image);
+    }
+    
+    let mut groups: Vec<SimilarImageGroup> = groups
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|mut images| {
+            images.sort_by_key(|image| std::cmp::Reverse((image.width as u64 * image.height as u64, image.size)));
+            SimilarImageGroup { images }
+        })
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.total_size()));
+    
+    info!("Found {} groups of similar images", groups.len());
+    groups
+}
+
+/// Hashes arranged by Hamming distance, the first node is the root
+#[derive(Default)]
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+struct BkNode {
+    hash: u64,
+    /// Image the hash belongs to
+    index: usize,
+    /// Child nodes with their distance to this node's hash
+    children: Vec<(u32, usize)>,
+}
+
+impl BkTree {
+    fn insert(&mut self, hash: u64, index: usize) {
+        let new_node = self.nodes.len();
+        if new_node > 0 {
+            let mut node = 0;
+            loop {
+                let distance = (self.nodes[node].hash ^ hash).count_ones();
+                match self.nodes[node].children.iter().find(|(child_distance, _)| *child_distance == distance) {
+                    Some(&(_, child)) => node = child,
+                    None => {
+                        self.nodes[node].children.push((distance, new_node));
+                        break;
+                    }
+                }
+            }
+        }
+        self.nodes.push(BkNode { hash, index, children: Vec::new() });
+    }
+    
+    /// Indexes of the images within `max_distance` of `hash`
+    fn within(&self, hash: u64, max_distance: u32) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(node) = pending.pop() {
+            let node = &self.nodes[node];
+            let distance = (node.hash ^ hash).count_ones();
+            if distance <= max_distance {
+                found.push(node.index);
+            }
+            // By the triangle inequality only children this close to the node can match
+            pending.extend(node.children.iter()
+                .filter(|(child_distance, _)| child_distance.abs_diff(distance) <= max_distance)
+                .map(|&(_, child)| child));
+        }
+        found
+    }
+}
+
+fn find_root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+fn hash_image(path: &str, size: u64, algorithm: HashAlgorithm) -> Result<SimilarImage, Box<dyn std::error::Error>> {
+    let image = image::ImageReader::open(Path::new(path))?.with_guessed_format()?.decode()?;
+    let (width, height) = image.dimensions();
+    
+    let hash = match algorithm {
+        HashAlgorithm::Average => average_hash(&image),
+        HashAlgorithm::Difference => difference_hash(&image),
+        HashAlgorithm::Perceptual => perceptual_hash(&image),
+    };
+    
+    let preview = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+    let thumbnail = Thumbnail {
+        width: preview.width(),
+        height: preview.height(),
+        rgba: preview.into_raw(),
+    };
+    
+    Ok(SimilarImage {
+        path: path.to_string(),
+        size,
+        width,
+        height,
+        hash,
+        thumbnail: Arc::new(thumbnail),
+    })
+}
+
+fn grayscale(image: &DynamicImage, width: u32, height: u32) -> Vec<f64> {
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma8()
+        .into_raw()
+        .into_iter()
+        .map(f64::from)
+        .collect()
+}
+
+fn bits_to_hash(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.take(64).fold(0u64, |hash, bit| (hash << 1) | bit as u64)
+}
+
+fn average_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale(image, 8, 8);
+    let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+    bits_to_hash(pixels.iter().map(|&p| p > mean))
+}
+
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale(image, 9, 8);
+    bits_to_hash((0..8).flat_map(|y| {
+        let row = &pixels[y * 9..(y + 1) * 9];
+        (0..8).map(move |x| row[x] < row[x + 1])
+    }))
+}
+
+fn perceptual_hash(image: &DynamicImage) -> u64 {
+    const N: usize = 32;
+    let pixels = grayscale(image, N as u32, N as u32);
+    
+    // Separable 2D DCT-II, only the 8x8 low-frequency block is needed
+    let cosines: Vec<f64> = (0..8)
+        .flat_map(|u| (0..N).map(move |x| (((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI) / (2 * N) as f64).cos()))
+        .collect();
+    
+    let mut rows = vec![0.0; N * 8];
+    for y in 0..N {
+        for u in 0..8 {
+            rows[y * 8 + u] = (0..N).map(|x| pixels[y * N + x] * cosines[u * N + x]).sum();
+        }
+    }
+    
+    let mut coefficients = Vec::with_capacity(64);
+    for v in 0..8 {
+        for u in 0..8 {
+            coefficients.push((0..N).map(|y| rows[y * 8 + u] * cosines[v * N + y]).sum::<f64>());
+        }
+    }
+    
+    // Skip the DC term, which only encodes overall brightness
+    let mut sorted: Vec<f64> = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    
+    bits_to_hash(coefficients.iter().map(|&c| c > median))
+}
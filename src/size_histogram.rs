@@ -0,0 +1,78 @@
+use crate::file_utils::format_size;
+
+const KB: u64 = 1024;
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// Exclusive upper bounds of the buckets: powers of two from 4 KB to 64 GB,
+/// then 100 GB. Anything larger lands in the final open-ended bucket.
+fn bucket_limits() -> Vec<u64> {
+    let mut limits: Vec<u64> = (12..=36).map(|power| 1u64 << power).collect();
+    limits.push(100 * GB);
+    limits
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistogramBucket {
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+/// File counts and bytes per size bucket over every scanned file
+#[derive(Debug, Clone)]
+pub struct SizeHistogram {
+    limits: Vec<u64>,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+impl Default for SizeHistogram {
+    fn default() -> Self {
+        let limits = bucket_limits();
+        let buckets = vec![HistogramBucket::default(); limits.len() + 1];
+        Self { limits, buckets }
+    }
+}
+
+impl SizeHistogram {
+    pub fn add(&mut self, size: u64) {
+        let index = self.limits.partition_point(|&limit| limit <= size);
+        let bucket = &mut self.buckets[index];
+        bucket.file_count += 1;
+        bucket.total_size += size;
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|bucket| bucket.file_count == 0)
+    }
+    
+    /// Totals of the buckets lying entirely within `min..max` bytes
+    pub fn sum_range(&self, min: u64, max: u64) -> HistogramBucket {
+        let mut sum = HistogramBucket::default();
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            let lower = if index == 0 { 0 } else { self.limits[index - 1] };
+            let upper = self.limits.get(index).copied().unwrap_or(u64::MAX);
+            if lower >= min && upper <= max {
+                sum.file_count += bucket.file_count;
+                sum.total_size += bucket.total_size;
+            }
+        }
+        sum
+    }
+    
+    /// Short label for bucket `index`, e.g. "< 4 KB", "4 KB - 8 KB", "> 100 GB"
+    pub fn label(&self, index: usize) -> String {
+        match index {
+            0 => format!("< {}", short_size(self.limits[0])),
+            i if i >= self.limits.len() => format!("> {}", short_size(self.limits[self.limits.len() - 1])),
+            i => format!("{} - {}", short_size(self.limits[i - 1]), short_size(self.limits[i])),
+        }
+    }
+}
+
+fn short_size(bytes: u64) -> String {
+    // Bucket limits are whole units, drop the ".0" format_size adds
+    if bytes < KB {
+        format!("{} B", bytes)
+    } else {
+        format_size(bytes).replace(".0 ", " ")
+    }
+}
@@ -0,0 +1,50 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::SystemTime;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::scan_index::ScanIndex;
+
+/// Bumped whenever the snapshot layout changes incompatibly
+const SNAPSHOT_VERSION: u32 = 1;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// A saved scan index, stored as zstd-compressed JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created: SystemTime,
+    pub index: ScanIndex,
+}
+
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    created: SystemTime,
+    index: &'a ScanIndex,
+}
+
+pub fn save_snapshot(path: &Path, index: &ScanIndex) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Saving snapshot of {} entries to {}", index.nodes.len(), path.display());
+    let mut encoder = zstd::stream::write::Encoder::new(BufWriter::new(File::create(path)?), COMPRESSION_LEVEL)?;
+    serde_json::to_writer(&mut encoder, &SnapshotRef {
+        version: SNAPSHOT_VERSION,
+        created: SystemTime::now(),
+        index,
+    })?;
+    encoder.finish()?;
+    Ok(())
+}
+
+pub fn load_snapshot(path: &Path) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    info!("Loading snapshot from {}", path.display());
+    let decoder = zstd::stream::read::Decoder::new(BufReader::new(File::open(path)?))?;
+    let snapshot: Snapshot = serde_json::from_reader(BufReader::new(decoder))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!("Unsupported snapshot version {}", snapshot.version).into());
+    }
+    info!("Loaded snapshot with {} entries", snapshot.index.nodes.len());
+    Ok(snapshot)
+}
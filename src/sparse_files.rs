@@ -0,0 +1,183 @@
+use std::fs::Metadata;
+use std::path::Path;
+use log::warn;
+
+/// Differences smaller than this (or than a tenth of the file) are block rounding, not holes
+const MIN_DIFFERENCE: u64 = 1024 * 1024;
+/// On Windows files this large are checked even without the sparse or compressed attribute
+#[cfg(windows)]
+const PREALLOCATION_CHECK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// How a file's allocation differs from its length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AllocationKind {
+    /// Unwritten ranges are holes that take no disk space
+    Sparse,
+    /// The file system stores the data compressed
+    Compressed,
+    /// Space is reserved beyond the end of the data
+    Preallocated,
+}
+
+impl AllocationKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AllocationKind::Sparse => "Sparse",
+            AllocationKind::Compressed => "Compressed",
+            AllocationKind::Preallocated => "Preallocated",
+        }
+    }
+}
+
+/// Bytes a file really occupies on disk, recorded when they differ notably from its length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Allocation {
+    pub allocated_size: u64,
+    pub kind: AllocationKind,
+    /// Number of holes, when the file system can map them (Linux `SEEK_HOLE`)
+    pub holes: Option<u64>,
+}
+
+impl Allocation {
+    /// Share of the file's length that is not backed by disk space
+    pub fn hole_ratio(&self, logical_size: u64) -> f64 {
+        if logical_size == 0 {
+            return 0.0;
+        }
+        1.0 - (self.allocated_size.min(logical_size) as f64 / logical_size as f64)
+    }
+}
+
+/// Allocation of a walked file, `None` when it matches the file's length.
+/// Unix reads the block count already in the metadata; Windows only asks the
+/// file system for sparse or compressed files and for very large ones.
+pub fn allocation_of(path: &Path, metadata: &Metadata) -> Option<Allocation> {
+    let logical_size = metadata.len();
+    let (allocated_size, compressed) = allocated_bytes(path, metadata)?;
+    
+    let difference = logical_size.abs_diff(allocated_size);
+    if difference < MIN_DIFFERENCE || difference < logical_size.max(allocated_size) / 10 {
+        return None;
+    }
+    
+    let kind = if allocated_size > logical_size {
+        AllocationKind::Preallocated
+    } else if compressed {
+        AllocationKind::Compressed
+    } else {
+        AllocationKind::Sparse
+    };
+    Some(Allocation { allocated_size, kind, holes: None })
+}
+
+#[cfg(unix)]
+fn allocated_bytes(_path: &Path, metadata: &Metadata) -> Option<(u64, bool)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.blocks() * 512, false))
+}
+
+#[cfg(windows)]
+fn allocated_bytes(path: &Path, metadata: &Metadata) -> Option<(u64, bool)> {
+    use std::os::windows::fs::MetadataExt;
+    use winapi::um::winnt::{FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_SPARSE_FILE};
+    
+    let attributes = metadata.file_attributes();
+    let compressed = attributes & FILE_ATTRIBUTE_COMPRESSED != 0;
+    let sparse = attributes & FILE_ATTRIBUTE_SPARSE_FILE != 0;
+    if compressed || sparse {
+        // Only for these does the compressed file size differ from the length
+        return crate::file_utils::allocated_size(path).map(|size| (size, compressed));
+    }
+    if metadata.len() < PREALLOCATION_CHECK_SIZE {
+        return None;
+    }
+    reserved_size(path).map(|size| (size, false))
+}
+
+/// Space reserved for a file, more than its length when it was preallocated
+#[cfg(windows)]
+fn reserved_size(path: &Path) -> Option<u64> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::fileapi::FILE_STANDARD_INFO;
+    use winapi::um::minwinbase::FileStandardInfo;
+    use winapi::um::winbase::GetFileInformationByHandleEx;
+    use winapi::um::winnt::FILE_READ_ATTRIBUTES;
+    
+    let file = match std::fs::OpenOptions::new().access_mode(FILE_READ_ATTRIBUTES).open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("Failed to open {} to query its allocation: {}", path.display(), e);
+            return None;
+        }
+    };
+    unsafe {
+        let mut info: FILE_STANDARD_INFO = std::mem::zeroed();
+        let queried = GetFileInformationByHandleEx(
+            file.as_raw_handle().cast(),
+            FileStandardInfo,
+            (&mut info as *mut FILE_STANDARD_INFO).cast(),
+            std::mem::size_of::<FILE_STANDARD_INFO>() as u32,
+        );
+        if queried == 0 {
+            warn!("Failed to query allocation of {}: {}", path.display(), std::io::Error::last_os_error());
+            return None;
+        }
+        Some(*info.AllocationSize.QuadPart() as u64)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn allocated_bytes(_path: &Path, _metadata: &Metadata) -> Option<(u64, bool)> {
+    None
+}
+
+/// Count the holes of a sparse file. Where `SEEK_HOLE` finds none the missing
+/// blocks come from file system compression (ZFS, btrfs) and the file is relabelled.
+pub fn map_holes(path: &Path, logical_size: u64, allocation: &mut Allocation) {
+    if allocation.kind != AllocationKind::Sparse {
+        return;
+    }
+    match count_holes(path, logical_size) {
+        Ok(Some(0)) => allocation.kind = AllocationKind::Compressed,
+        Ok(holes) => allocation.holes = holes,
+        Err(e) => warn!("Failed to map holes of {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn count_holes(path: &Path, logical_size: u64) -> std::io::Result<Option<u64>> {
+    use std::os::unix::io::AsRawFd;
+    
+    let file = std::fs::File::open(path)?;
+    let fd = file.as_raw_fd();
+    let end = logical_size as libc::off_t;
+    let mut offset: libc::off_t = 0;
+    let mut holes = 0u64;
+    while offset < end {
+        let data = unsafe { libc::lseek(fd, offset, libc::SEEK_DATA) };
+        if data < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::ENXIO) {
+                // No data past `offset`: the rest of the file is one hole
+                holes += 1;
+                break;
+            }
+            return Err(error);
+        }
+        if data > offset {
+            holes += 1;
+        }
+        let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        offset = hole;
+    }
+    Ok(Some(holes))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_holes(_path: &Path, _logical_size: u64) -> std::io::Result<Option<u64>> {
+    Ok(None)
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+use std::sync::OnceLock;
+use log::info;
+
+/// Files the operating system creates and sizes on its own
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SystemFileKind {
+    PageFile,
+    Hibernation,
+    /// Windows swap file for modern (Store) apps
+    AppSwap,
+    /// Linux and macOS swap files
+    Swap,
+}
+
+impl SystemFileKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SystemFileKind::PageFile => "Page file",
+            SystemFileKind::Hibernation => "Hibernation file",
+            SystemFileKind::AppSwap => "App swap file",
+            SystemFileKind::Swap => "Swap file",
+        }
+    }
+    
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            SystemFileKind::PageFile => "Virtual memory backing RAM. Windows manages it and it cannot be deleted \
+                while in use; removing it entirely can cause crashes when memory runs low.",
+            SystemFileKind::Hibernation => "Holds the contents of RAM while the machine hibernates and for Fast Startup. \
+                It can be removed safely by turning hibernation off.",
+            SystemFileKind::AppSwap => "Used by Windows to suspend and resume Store apps. It is small and managed \
+                together with the page file.",
+            SystemFileKind::Swap => "Swap space backing RAM. Deleting it while active is not possible; \
+                it has to be disabled first.",
+        }
+    }
+    
+    /// The system setting that controls the file's size
+    pub fn setting(&self) -> &'static str {
+        match self {
+            SystemFileKind::PageFile | SystemFileKind::AppSwap => "System Properties > Advanced > Performance Settings > \
+                Advanced > Virtual memory",
+            SystemFileKind::Hibernation => "`powercfg /hibernate off` (or `powercfg /h /type reduced`) in an \
+                administrator prompt",
+            SystemFileKind::Swap => "`swapoff` the file, then resize it with `fallocate` and `mkswap`, \
+                and keep `/etc/fstab` in sync",
+        }
+    }
+}
+
+/// Identify OS-managed memory files by their well-known names and locations
+pub fn identify_system_file(path: &Path) -> Option<SystemFileKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    // The Windows files only live in a drive's root
+    let in_root = path.parent().is_some_and(|parent| parent.parent().is_none());
+    
+    match name.as_str() {
+        "pagefile.sys" if in_root => Some(SystemFileKind::PageFile),
+        "hiberfil.sys" if in_root => Some(SystemFileKind::Hibernation),
+        "swapfile.sys" if in_root => Some(SystemFileKind::AppSwap),
+        "swapfile" | "swap.img" if in_root => Some(SystemFileKind::Swap),
+        "sleepimage" if path.starts_with("/private/var/vm") => Some(SystemFileKind::Hibernation),
+        _ if name.starts_with("swapfile") && path.starts_with("/private/var/vm") => Some(SystemFileKind::Swap),
+        _ if active_swap_files().iter().any(|swap| Path::new(swap) == path) => Some(SystemFileKind::Swap),
+        _ => None,
+    }
+}
+
+/// Swap files listed in /proc/swaps, read once per run
+fn active_swap_files() -> &'static [String] {
+    static SWAP_FILES: OnceLock<Vec<String>> = OnceLock::new();
+    SWAP_FILES.get_or_init(|| {
+        let swaps: Vec<String> = std::fs::read_to_string("/proc/swaps")
+            .unwrap_or_default()
+            .lines()
+            .skip(1)
+            .filter(|line| line.split_whitespace().nth(1) == Some("file"))
+            .filter_map(|line| line.split_whitespace().next().map(str::to_string))
+            .collect();
+        if !swaps.is_empty() {
+            info!("Active swap files: {:?}", swaps);
+        }
+        swaps
+    })
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+use log::{info, warn};
+
+use crate::audit::{record_done, record_failed};
+use crate::file_utils::total_size;
+
+/// What the platform calls its trash, for labels
+pub const TRASH_NAME: &str = if cfg!(windows) { "Recycle Bin" } else { "Trash" };
+
+/// Move files and folders to the Recycle Bin (or the freedesktop Trash) so they can be restored.
+/// Returns the paths that were moved and one message per failure.
+pub fn move_to_trash(paths: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut removed = Vec::new();
+    let mut errors = Vec::new();
+    
+    for path in paths {
+        if let Err(e) = std::fs::symlink_metadata(path) {
+            errors.push(format!("{}: {}", path, e));
+            continue;
+        }
+        let size = total_size(Path::new(path)).ok();
+        match trash_one(Path::new(path)) {
+            Ok(_) => {
+                record_done("trash", path, size, None, TRASH_NAME);
+                removed.push(path.clone());
+            }
+            Err(e) => {
+                record_failed("trash", path, size, &e.to_string());
+                errors.push(format!("{}: {}", path, e));
+            }
+        }
+    }
+    
+    info!("Moved {} items to the trash, {} errors", removed.len(), errors.len());
+    for error in &errors {
+        warn!("Failed to move to the trash: {}", error);
+    }
+    
+    (removed, errors)
+}
+
+#[cfg(windows)]
+fn trash_one(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::shellapi::{
+        SHFileOperationW, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FOF_NOERRORUI, FOF_SILENT, FO_DELETE, SHFILEOPSTRUCTW,
+    };
+    
+    // The shell wants an absolute path in a double-null-terminated list
+    let absolute = std::fs::canonicalize(path)?;
+    let absolute = absolute.to_string_lossy();
+    let absolute = absolute.strip_prefix(r"\\?\").unwrap_or(&absolute);
+    let wide: Vec<u16> = std::ffi::OsStr::new(absolute).encode_wide().chain([0, 0]).collect();
+    
+    let mut operation: SHFILEOPSTRUCTW = unsafe { std::mem::zeroed() };
+    operation.wFunc = u32::from(FO_DELETE);
+    operation.pFrom = wide.as_ptr();
+    operation.fFlags = FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_SILENT | FOF_NOERRORUI;
+    let result = unsafe { SHFileOperationW(&mut operation) };
+    if result != 0 {
+        return Err(format!("Recycle Bin operation failed with code {:#x}", result).into());
+    }
+    if operation.fAnyOperationsAborted != 0 {
+        return Err("Recycle Bin operation was aborted".into());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn trash_one(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let home = std::env::var_os("HOME").ok_or("HOME is not set")?;
+    let trash = Path::new(&home).join(".Trash");
+    let name = path.file_name().ok_or("Path has no file name")?.to_string_lossy().to_string();
+    
+    let mut target = trash.join(&name);
+    let mut attempt = 2;
+    while target.exists() {
+        target = trash.join(format!("{} {}", name, attempt));
+        attempt += 1;
+    }
+    std::fs::rename(path, &target)?;
+    Ok(())
+}
+
+/// Freedesktop.org Trash: a `files/` entry plus an `info/*.trashinfo` record so file managers can restore it
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_one(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::time::SystemTime;
+    
+    use crate::file_age::format_timestamp;
+    
+    let absolute = std::path::absolute(path)?;
+    let trash = trash_directory(&absolute)?;
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+    
+    let name = absolute.file_name().ok_or("Path has no file name")?.to_string_lossy().to_string();
+    // Creating the .trashinfo exclusively claims the name, even against other applications
+    let mut attempt = 1;
+    let (trashed_name, info_path, mut info_file) = loop {
+        let candidate = if attempt == 1 { name.clone() } else { format!("{}.{}", name, attempt) };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) if !files.join(&candidate).exists() => break (candidate, info_path, file),
+            Ok(_) => {
+                let _ = fs::remove_file(&info_path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+        attempt += 1;
+    };
+    
+    // The spec wants local time without a zone
+    let now = SystemTime::now();
+    let offset = unsafe {
+        let seconds = now.duration_since(std::time::UNIX_EPOCH).map(|age| age.as_secs()).unwrap_or(0) as libc::time_t;
+        let mut local: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&seconds, &mut local);
+        local.tm_gmtoff
+    };
+    let local = if offset >= 0 {
+        now + std::time::Duration::from_secs(offset as u64)
+    } else {
+        now - std::time::Duration::from_secs(offset.unsigned_abs())
+    };
+    let record = format!("[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(&absolute.to_string_lossy()), format_timestamp(local));
+    if let Err(e) = info_file.write_all(record.as_bytes()) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e.into());
+    }
+    
+    if let Err(e) = fs::rename(&absolute, files.join(&trashed_name)) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// The home trash when the path is on the same device, otherwise `.Trash-$uid` at the top of its mount
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_directory(path: &Path) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    use crate::file_utils::{same_volume, volume_root};
+    
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|value| !value.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+    if let Some(data_home) = data_home {
+        // The trash folder may not exist yet, so compare against its nearest existing ancestor
+        if data_home.ancestors().find(|dir| dir.exists()).is_some_and(|dir| same_volume(dir, path)) {
+            return Ok(data_home.join("Trash"));
+        }
+    }
+    
+    let uid = unsafe { libc::getuid() };
+    Ok(volume_root(path).join(format!(".Trash-{}", uid)))
+}
+
+/// Percent-encode a path for the `Path=` key, keeping `/` and unreserved characters
+#[cfg(all(unix, not(target_os = "macos")))]
+fn percent_encode(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use log::{info, warn};
+
+use crate::file_utils::allocated_size;
+
+const SECTOR_SIZE: u64 = 512;
+const VHDX_REGION_TABLE_OFFSET: u64 = 192 * 1024;
+const ISO_VOLUME_DESCRIPTOR_OFFSET: u64 = 0x8000;
+/// Largest VMDK descriptor read as text
+const VMDK_DESCRIPTOR_MAX: u64 = 64 * 1024;
+
+// VHDX GUIDs in their on-disk (mixed-endian) byte order
+const VHDX_METADATA_REGION: [u8; 16] = [0x06, 0xA2, 0x7C, 0x8B, 0x90, 0x47, 0x9A, 0x4B, 0xB8, 0xFE, 0x57, 0x5F, 0x05, 0x0F, 0x88, 0x6E];
+const VHDX_VIRTUAL_DISK_SIZE: [u8; 16] = [0x24, 0x42, 0xA5, 0x2F, 0x1B, 0xCD, 0x76, 0x48, 0xB2, 0x11, 0x5D, 0xBE, 0xD8, 0x3B, 0xF4, 0xB8];
+const VHDX_FILE_PARAMETERS: [u8; 16] = [0x37, 0x67, 0xA1, 0xCA, 0x36, 0xFA, 0x43, 0x4D, 0xB3, 0xB6, 0x33, 0xF0, 0xAA, 0x44, 0xE7, 0x6B];
+
+const VM_EXTENSIONS: &[&str] = &["vhd", "vhdx", "avhd", "avhdx", "vmdk", "qcow", "qcow2", "vdi", "iso", "img"];
+
+/// Virtual machine disk, container data disk or ISO image
+#[derive(Debug, Clone)]
+pub struct VmImage {
+    pub path: String,
+    pub format: String,
+    /// File length as reported by the file system
+    pub logical_size: u64,
+    /// Bytes actually allocated on disk, when the platform reports it
+    pub allocated_size: Option<u64>,
+    /// Capacity the guest sees, when the header records it
+    pub virtual_size: Option<u64>,
+    /// Whether the image grows on demand, when the header says
+    pub dynamic: Option<bool>,
+    /// What the image is and how to shrink it, for well-known images
+    pub note: Option<&'static str>,
+}
+
+impl VmImage {
+    /// Dynamic images never shrink on their own when the guest deletes data
+    pub fn can_compact(&self) -> bool {
+        self.dynamic == Some(true)
+    }
+}
+
+/// Whether a file name looks like a VM or container image worth inspecting
+pub fn is_vm_image_name(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    lower == "docker.raw" || lower.rsplit_once('.').is_some_and(|(_, extension)| VM_EXTENSIONS.contains(&extension))
+}
+
+/// Inspect the collected candidates (path, size), largest image first
+pub fn inspect_vm_images(candidates: &[(String, u64)]) -> Vec<VmImage> {
+    let mut images: Vec<VmImage> = candidates
+        .iter()
+        .filter_map(|(path, size)| inspect_vm_image(Path::new(path), *size))
+        .collect();
+    images.sort_by_key(|image| std::cmp::Reverse(image.logical_size));
+    info!("Found {} virtual machine and container images", images.len());
+    images
+}
+
+/// Read the image's header to identify its format, virtual size and allocation type.
+/// Returns `None` for `.img` files without a recognised header.
+fn inspect_vm_image(path: &Path, logical_size: u64) -> Option<VmImage> {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let header = match read_header(path, logical_size, &name) {
+        Ok(header) => header,
+        Err(e) => {
+            warn!("Failed to read VM image header of {}: {}", path.display(), e);
+            None
+        }
+    };
+    
+    let (format, virtual_size, dynamic) = match header {
+        Some(header) => header,
+        None if name.ends_with(".img") => return None,
+        None if name == "docker.raw" => ("Raw disk".to_string(), Some(logical_size), None),
+        None => ("Unrecognised disk image".to_string(), None, None),
+    };
+    
+    Some(VmImage {
+        path: path.to_string_lossy().to_string(),
+        format,
+        logical_size,
+        allocated_size: allocated_size(path),
+        virtual_size,
+        dynamic,
+        note: known_image_note(&name),
+    })
+}
+
+fn known_image_note(name: &str) -> Option<&'static str> {
+    match name {
+        "ext4.vhdx" => Some("WSL distribution disk. Free space inside the distribution, run `wsl --shutdown`, \
+            then `Optimize-VHD -Mode Full` or diskpart `compact vdisk` to shrink the file."),
+        "docker_data.vhdx" | "dockerdesktop.vhdx" => Some("Docker Desktop data disk. Run `docker system prune`, \
+            then shut down WSL and compact the disk like any other VHDX."),
+        "docker.raw" | "docker.qcow2" => Some("Docker Desktop data disk. Run `docker system prune`; \
+            the sparse file shrinks once Docker releases the space."),
+        _ if name.ends_with(".avhd") || name.ends_with(".avhdx") => Some("Hyper-V checkpoint. Deleting the checkpoint \
+            in Hyper-V Manager merges it into its parent disk."),
+        _ => None,
+    }
+}
+
+type HeaderInfo = (String, Option<u64>, Option<bool>);
+
+fn read_header(path: &Path, logical_size: u64, name: &str) -> std::io::Result<Option<HeaderInfo>> {
+    let mut file = File::open(path)?;
+    let head = read_at(&mut file, 0, 1024)?;
+    
+    if head.starts_with(b"vhdxfile") {
+        let (virtual_size, dynamic) = read_vhdx_metadata(&mut file).unwrap_or((None, None));
+        return Ok(Some(("VHDX".to_string(), virtual_size, dynamic)));
+    }
+    if head.starts_with(b"QFI\xfb") && head.len() >= 32 {
+        let format = if be_u32(&head[4..8]) >= 2 { "QCOW2" } else { "QCOW" };
+        return Ok(Some((format.to_string(), Some(be_u64(&head[24..32])), Some(true))));
+    }
+    if head.starts_with(b"KDMV") && head.len() >= 20 {
+        return Ok(Some(("VMDK (sparse)".to_string(), Some(le_u64(&head[12..20]) * SECTOR_SIZE), Some(true))));
+    }
+    if head.starts_with(b"# Disk DescriptorFile") && logical_size <= VMDK_DESCRIPTOR_MAX {
+        let mut descriptor = String::new();
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_string(&mut descriptor)?;
+        return Ok(Some(parse_vmdk_descriptor(&descriptor)));
+    }
+    if head.len() >= 0x178 && head[0x40..0x44] == [0x7f, 0x10, 0xda, 0xbe] {
+        // VDI image type 1 is dynamically allocated, 2 is fixed
+        let dynamic = match le_u32(&head[0x4C..0x50]) {
+            1 => Some(true),
+            2 => Some(false),
+            _ => None,
+        };
+        return Ok(Some(("VDI".to_string(), Some(le_u64(&head[0x170..0x178])), dynamic)));
+    }
+    
+    // Fixed VHDs only carry the footer, dynamic ones also copy it to the start
+    if logical_size >= SECTOR_SIZE && (head.starts_with(b"conectix") || name.ends_with(".vhd") || name.ends_with(".avhd")) {
+        let footer = read_at(&mut file, logical_size - SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        if footer.starts_with(b"conectix") && footer.len() >= 64 {
+            let dynamic = match be_u32(&footer[60..64]) {
+                2 => Some(false),
+                3 | 4 => Some(true),
+                _ => None,
+            };
+            return Ok(Some(("VHD".to_string(), Some(be_u64(&footer[48..56])), dynamic)));
+        }
+    }
+    
+    let volume = read_at(&mut file, ISO_VOLUME_DESCRIPTOR_OFFSET, 136)?;
+    if volume.len() >= 136 && &volume[1..6] == b"CD001" {
+        let blocks = le_u32(&volume[80..84]) as u64;
+        let block_size = u16::from_le_bytes([volume[128], volume[129]]) as u64;
+        return Ok(Some(("ISO 9660".to_string(), Some(blocks * block_size), None)));
+    }
+    
+    Ok(None)
+}
+
+/// Virtual size and allocation type from the VHDX metadata region
+fn read_vhdx_metadata(file: &mut File) -> Option<(Option<u64>, Option<bool>)> {
+    let regions = read_at(file, VHDX_REGION_TABLE_OFFSET, 64 * 1024).ok()?;
+    if regions.len() < 16 || !regions.starts_with(b"regi") {
+        return None;
+    }
+    let region_count = le_u32(&regions[8..12]) as usize;
+    let (metadata_offset, metadata_length) = regions[16..]
+        .chunks_exact(32)
+        .take(region_count)
+        .find(|entry| entry[..16] == VHDX_METADATA_REGION)
+        .map(|entry| (le_u64(&entry[16..24]), le_u32(&entry[24..28]) as usize))?;
+    
+    let metadata = read_at(file, metadata_offset, metadata_length.min(1024 * 1024)).ok()?;
+    if metadata.len() < 32 || !metadata.starts_with(b"metadata") {
+        return None;
+    }
+    let entry_count = u16::from_le_bytes([metadata[10], metadata[11]]) as usize;
+    let item = |guid: &[u8; 16]| -> Option<&[u8]> {
+        let entry = metadata[32..].chunks_exact(32).take(entry_count).find(|entry| entry[..16] == *guid)?;
+        let offset = le_u32(&entry[16..20]) as usize;
+        let length = le_u32(&entry[20..24]) as usize;
+        metadata.get(offset..offset + length)
+    };
+    
+    let virtual_size = item(&VHDX_VIRTUAL_DISK_SIZE).filter(|data| data.len() >= 8).map(|data| le_u64(&data[..8]));
+    // Flag bit 0 (LeaveBlocksAllocated) marks a fixed disk
+    let dynamic = item(&VHDX_FILE_PARAMETERS).filter(|data| data.len() >= 8).map(|data| le_u32(&data[4..8]) & 1 == 0);
+    Some((virtual_size, dynamic))
+}
+
+/// Sum of the extent sizes and the create type of a text VMDK descriptor
+fn parse_vmdk_descriptor(descriptor: &str) -> HeaderInfo {
+    let mut sectors = 0u64;
+    let mut dynamic = None;
+    for line in descriptor.lines().map(str::trim) {
+        if let Some(create_type) = line.strip_prefix("createType=") {
+            let create_type = create_type.trim_matches('"').to_lowercase();
+            dynamic = Some(create_type.contains("sparse") || create_type.contains("streamoptimized"));
+        } else if line.starts_with("RW ") || line.starts_with("RDONLY ") {
+            sectors += line.split_whitespace().nth(1).and_then(|count| count.parse::<u64>().ok()).unwrap_or(0);
+        }
+    }
+    ("VMDK (descriptor)".to_string(), (sectors > 0).then_some(sectors * SECTOR_SIZE), dynamic)
+}
+
+fn read_at(file: &mut File, offset: u64, length: usize) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(length);
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(length as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes[..4].try_into().unwrap())
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes[..8].try_into().unwrap())
+}